@@ -1353,7 +1353,13 @@ pub async fn submit_shielding_transfer(
                         MASP,
                         "Native VP error: Unable to decode asset type"
                             .to_string(),
-                    )))
+                    )) || vps_errors.iter().any(|(addr, error)| {
+                        *addr == MASP
+                            && error.starts_with(
+                                "Native VP error: Some transparent assets \
+                                 could not be recognized",
+                            )
+                    }))
                 {
                     let submission_masp_epoch =
                         rpc::query_and_print_masp_epoch(namada).await;
@@ -840,6 +840,50 @@ fn masp_check_output(c: &mut Criterion) {
     });
 }
 
+fn masp_commitment_tree_check(c: &mut Criterion) {
+    let mut group = c.benchmark_group("vp_masp_commitment_tree_check");
+
+    // A tree with 2^16 leaves, and the same tree with one more note
+    let mut pre_tree = CommitmentTree::<Node>::empty();
+    for leaf in 0..(1u64 << 16) {
+        pre_tree
+            .append(Node::from_scalar(
+                masp_primitives::bls12_381::Scalar::from(leaf),
+            ))
+            .unwrap();
+    }
+    let cmu =
+        Node::from_scalar(masp_primitives::bls12_381::Scalar::from(1u64 << 16));
+    let mut post_tree = pre_tree.clone();
+    post_tree.append(cmu).unwrap();
+
+    group.bench_function("full_comparison", |b| {
+        b.iter_batched(
+            || pre_tree.clone(),
+            |mut tree| {
+                tree.append(cmu).unwrap();
+                assert!(tree == post_tree);
+            },
+            BatchSize::SmallInput,
+        )
+    });
+    group.bench_function("root_comparison", |b| {
+        b.iter_batched(
+            || pre_tree.clone(),
+            |mut tree| {
+                tree.append(cmu).unwrap();
+                assert!(
+                    tree.size() == post_tree.size()
+                        && tree.root() == post_tree.root()
+                );
+            },
+            BatchSize::SmallInput,
+        )
+    });
+
+    group.finish();
+}
+
 fn masp_final_check(c: &mut Criterion) {
     let (_, _verifiers_from_tx, signed_tx) =
         setup_storage_for_masp_verification("shielded");
@@ -1817,6 +1861,7 @@ criterion_group!(
     masp_check_spend,
     masp_check_convert,
     masp_check_output,
+    masp_commitment_tree_check,
     masp_final_check,
     masp_batch_signature_verification,
     masp_batch_spend_proofs_validate,
@@ -468,6 +468,29 @@ impl Signature {
         let v = v + Self::V_FIX;
         (r.to_bytes().into(), s.to_bytes().into(), v)
     }
+
+    /// Recover the [`EthAddress`] of the key that produced this
+    /// [`Signature`] over the given data, hashed with `H`.
+    pub fn recover_eth_address<H>(
+        &self,
+        data: &impl SignableBytes,
+    ) -> Result<EthAddress, VerifySigError>
+    where
+        H: 'static + StorageHasher,
+    {
+        let msg = data.signable_hash::<H>();
+        let vrf_key = k256::ecdsa::VerifyingKey::recover_from_prehash(
+            &msg, &self.0, self.1,
+        )
+        .map_err(|e| {
+            VerifySigError::SigVerifyError(format!(
+                "Error recovering secp256k1 public key: {}",
+                e
+            ))
+        })?;
+        let pk = PublicKey(vrf_key.into());
+        Ok((&pk).into())
+    }
 }
 
 impl Encode<1> for Signature {
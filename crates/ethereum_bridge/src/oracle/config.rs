@@ -1,4 +1,5 @@
 //! Configuration for an oracle.
+use std::collections::BTreeMap;
 use std::num::NonZeroU64;
 
 use namada_core::ethereum_events::EthAddress;
@@ -10,6 +11,9 @@ pub struct Config {
     /// The minimum number of block confirmations an Ethereum block must have
     /// before it will be checked for bridge events.
     pub min_confirmations: NonZeroU64,
+    /// The minimum number of block confirmations of transfers of specific
+    /// ERC20 assets, overriding [`Config::min_confirmations`] when higher.
+    pub token_min_confirmations: BTreeMap<EthAddress, NonZeroU64>,
     /// The Ethereum address of the current bridge contract.
     pub bridge_contract: EthAddress,
     /// The earliest Ethereum block from which events may be processed.
@@ -25,6 +29,7 @@ impl std::default::Default for Config {
             // SAFETY: we must always call NonZeroU64::new_unchecked here with a
             // value that is >= 1
             min_confirmations: unsafe { NonZeroU64::new_unchecked(100) },
+            token_min_confirmations: BTreeMap::new(),
             bridge_contract: EthAddress([0; 20]),
             start_block: 0.into(),
             active: true,
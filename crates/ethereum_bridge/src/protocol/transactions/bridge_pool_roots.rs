@@ -313,15 +313,12 @@ mod test_apply_bp_roots_to_storage {
         state.commit_block().unwrap();
 
         vp::bridge_pool::init_storage(&mut state);
-        test_utils::commit_bridge_pool_root_at_height(
+        test_utils::commit_bridge_pool_roots(
             &mut state,
-            &KeccakHash([1; 32]),
-            99.into(),
-        );
-        test_utils::commit_bridge_pool_root_at_height(
-            &mut state,
-            &KeccakHash([1; 32]),
-            100.into(),
+            &[
+                (KeccakHash([1; 32]), 99.into()),
+                (KeccakHash([1; 32]), 100.into()),
+            ],
         );
         state
             .write(&get_key_from_hash(&KeccakHash([1; 32])), BlockHeight(101))
@@ -815,6 +815,7 @@ mod tests {
                         test_utils::WhitelistMeta {
                             cap: dai_token_cap,
                             denom: 18,
+                            min_confirmations: None,
                         },
                     )],
                 );
@@ -1031,6 +1032,33 @@ mod tests {
         assert_eq!(bp_erc_balance_post, Amount::from(0));
     }
 
+    #[test]
+    /// Test that the Bridge pool nonce is incremented once for every
+    /// batch of transfers to Ethereum that gets relayed.
+    fn test_bp_nonce_incremented_per_relayed_batch() {
+        let mut state = TestState::default();
+        test_utils::bootstrap_ethereum_bridge(&mut state);
+        state.commit_block().expect("Test failed");
+        init_storage(&mut state);
+        let nonce_pre = state.ethbridge_queries().get_bridge_pool_nonce();
+
+        for _ in 0..2 {
+            let event = EthereumEvent::TransfersToEthereum {
+                nonce: arbitrary_nonce(),
+                transfers: vec![],
+                relayer: gen_implicit_address(),
+            };
+            let (changed_keys, _) = act_on(&mut state, event).unwrap();
+            assert!(changed_keys.contains(&get_nonce_key()));
+        }
+
+        let expected = nonce_pre
+            .checked_increment()
+            .and_then(|nonce| nonce.checked_increment())
+            .expect("Test failed");
+        assert_eq!(state.ethbridge_queries().get_bridge_pool_nonce(), expected);
+    }
+
     #[test]
     /// Test that the transfers time out in the bridge pool then the refund when
     /// we act on a TransfersToEthereum
@@ -419,6 +419,7 @@ mod tests {
                 test_utils::WhitelistMeta {
                     cap: Amount::max(),
                     denom: 18,
+                    min_confirmations: None,
                 },
             )],
         );
@@ -497,6 +498,7 @@ mod tests {
                 test_utils::WhitelistMeta {
                     cap: Amount::max(),
                     denom: 18,
+                    min_confirmations: None,
                 },
             )],
         );
@@ -749,18 +751,15 @@ mod tests {
         );
         let prev_keys = vote_tallies::Keys::from(&event);
 
-        // commit then update the epoch
-        state.commit_block().unwrap();
-        let unbonding_len = namada_proof_of_stake::storage::read_pos_params::<
+        // commit then advance past the unbonding period
+        let params = namada_proof_of_stake::storage::read_pos_params::<
             _,
             GovStore<_>,
         >(&state)
-        .expect("Test failed")
-        .unbonding_len
-            + 1;
-        state.in_mem_mut().last_epoch =
-            state.in_mem().last_epoch + unbonding_len;
-        state.in_mem_mut().block.epoch = state.in_mem().last_epoch + 1_u64;
+        .expect("Test failed");
+        for _ in 0..=params.unbonding_len {
+            test_utils::advance_epoch(&mut state, &params);
+        }
 
         let new_event = EthereumEvent::TransfersToNamada {
             nonce: 1.into(),
@@ -895,18 +894,15 @@ mod tests {
             }
         });
 
-        // commit then update the epoch
-        state.commit_block().unwrap();
-        let unbonding_len = namada_proof_of_stake::storage::read_pos_params::<
+        // commit then advance past the unbonding period
+        let params = namada_proof_of_stake::storage::read_pos_params::<
             _,
             GovStore<_>,
         >(&state)
-        .expect("Test failed")
-        .unbonding_len
-            + 1;
-        state.in_mem_mut().last_epoch =
-            state.in_mem().last_epoch + unbonding_len;
-        state.in_mem_mut().block.epoch = state.in_mem().last_epoch + 1_u64;
+        .expect("Test failed");
+        for _ in 0..=params.unbonding_len {
+            test_utils::advance_epoch(&mut state, &params);
+        }
 
         let result = apply_derived_tx::<_, _, GovStore<_>>(
             &mut state,
@@ -1,33 +1,44 @@
 //! Code for handling validator set update protocol txs.
 
-use eyre::Result;
+use std::collections::BTreeMap;
+
+use eyre::{Result, eyre};
 use namada_core::address::Address;
+use namada_core::borsh::BorshDeserialize;
 use namada_core::chain::{BlockHeight, Epoch};
 use namada_core::collections::{HashMap, HashSet};
 use namada_core::key::common;
-use namada_core::token::Amount;
+use namada_core::storage::{DbKeySeg, Key, KeySeg};
+use namada_core::voting_power::FractionalVotingPower;
 use namada_state::{DB, DBIter, StorageHasher, WlState};
+use namada_storage::StorageRead;
 use namada_systems::governance;
 use namada_tx::data::BatchedTxResult;
 use namada_vote_ext::validator_set_update;
+use namada_vote_ext::validator_set_update::{EthAddrBook, VotingPowersMap};
 
 use super::ChangedKeys;
+use crate::ADDRESS;
 use crate::protocol::transactions::utils;
 use crate::protocol::transactions::votes::update::NewVotes;
-use crate::protocol::transactions::votes::{self, Votes};
+use crate::protocol::transactions::votes::{
+    self, ChangedTallyKeys, EpochedVotingPowerExt, Votes,
+};
 use crate::storage::eth_bridge_queries::{EthBridgeQueries, SendValsetUpd};
 use crate::storage::proof::EthereumProof;
 use crate::storage::vote_tallies;
+use crate::storage::vote_tallies::KeysSegments;
 
 impl utils::GetVoters for (&validator_set_update::VextDigest, BlockHeight) {
     #[inline]
     fn get_voters(self) -> HashSet<(Address, BlockHeight)> {
-        // votes were cast at the 2nd block height of the ext's signing epoch
-        let (ext, epoch_2nd_height) = self;
+        // votes were cast at the given height of the ext's signing epoch,
+        // which is the 2nd block height of the epoch by default
+        let (ext, vote_height) = self;
         ext.signatures
             .keys()
             .cloned()
-            .zip(std::iter::repeat(epoch_2nd_height))
+            .zip(std::iter::repeat(vote_height))
             .collect()
     }
 }
@@ -68,20 +79,77 @@ where
         })
 }
 
+/// Whether the signatures of a validator set update should be
+/// verified before their votes are aggregated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SigVerification {
+    /// Reject signatures which were not produced over the current
+    /// version of the validator set update message, by the
+    /// Ethereum hot key of the signing validator.
+    Enabled,
+    /// Aggregate all signatures, e.g. because they have already
+    /// been verified during vote extension validation.
+    Disabled,
+}
+
+/// The outcome of aggregating validators' votes on a validator set
+/// update with [`aggregate_votes`].
+#[derive(Debug, Clone)]
+pub enum AggregationOutcome {
+    /// The tally of the validator set update had already been seen, so
+    /// the votes were ignored.
+    AlreadySeen,
+    /// None of the votes changed the tally, e.g. because all of them
+    /// had already been aggregated.
+    NoNewVotes,
+    /// The votes were aggregated, but the tally has yet to be seen.
+    Updated(BatchedTxResult),
+    /// The votes were aggregated, and the tally became seen.
+    Completed(BatchedTxResult),
+}
+
+impl AggregationOutcome {
+    /// Get the result of the protocol tx which aggregated the votes.
+    pub fn into_tx_result(self) -> BatchedTxResult {
+        match self {
+            Self::AlreadySeen | Self::NoNewVotes => Default::default(),
+            Self::Updated(tx_result) | Self::Completed(tx_result) => tx_result,
+        }
+    }
+}
+
 /// Aggregate validators' votes
 pub fn aggregate_votes<D, H, Gov>(
     state: &mut WlState<D, H>,
     ext: validator_set_update::VextDigest,
     signing_epoch: Epoch,
-) -> Result<BatchedTxResult>
+    verification: SigVerification,
+) -> Result<AggregationOutcome>
 where
     D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
     H: 'static + StorageHasher + Sync,
     Gov: governance::Read<WlState<D, H>>,
 {
+    let ext = match verification {
+        SigVerification::Enabled => {
+            retain_valid_signatures::<D, H, Gov>(state, ext, signing_epoch)
+        }
+        SigVerification::Disabled => ext,
+    };
     if ext.signatures.is_empty() {
         tracing::debug!("Ignoring empty validator set update");
-        return Ok(Default::default());
+        return Ok(AggregationOutcome::NoNewVotes);
+    }
+    let valset_upd_keys =
+        vote_tallies::Keys::<EthereumProof<VotingPowersMap>>::from(
+            &valset_upd_activation_epoch(signing_epoch),
+        );
+    let seen = votes::storage::maybe_read_seen(state, &valset_upd_keys)?;
+    if seen == Some(true) {
+        tracing::debug!(
+            "Ignoring votes on an already seen validator set update"
+        );
+        return Ok(AggregationOutcome::AlreadySeen);
     }
 
     tracing::info!(
@@ -89,75 +157,522 @@ where
         "Aggregating new votes for validator set update"
     );
 
-    let epoch_2nd_height = state
-        .in_mem()
-        .block
-        .pred_epochs
-        .get_start_height_of_epoch(signing_epoch)
-        // NOTE: The only way this can fail is if validator set updates do not
-        // reach a `seen` state before the relevant epoch data is purged from
-        // Namada. In most scenarios, we should reach a complete proof before
-        // the end of an epoch, and even if we cross an epoch boundary without
-        // a complete proof, we should get one shortly after.
-        .expect("The first block height of the signing epoch should be known")
-        .next_height();
-    let voting_powers =
-        utils::get_voting_powers(state, (&ext, epoch_2nd_height))?;
-    let changed_keys = apply_update::<D, H, Gov>(
+    let vote_height = state
+        .ethbridge_queries()
+        .get_valset_upd_vote_height(signing_epoch)?
+        // NOTE: The start of the signing epoch can only be unknown if
+        // validator set updates do not reach a `seen` state before the
+        // relevant epoch data is purged from Namada. In most scenarios, we
+        // should reach a complete proof before the end of an epoch, and even
+        // if we cross an epoch boundary without a complete proof, we should
+        // get one shortly after.
+        .ok_or_else(|| {
+            eyre!("The vote height of epoch {signing_epoch} is unknown")
+        })?;
+    let (changed_keys, categories) = apply_update::<D, H, Gov>(
         state,
         ext,
         signing_epoch,
-        epoch_2nd_height,
-        voting_powers,
+        vote_height,
+        seen,
     )?;
+    if changed_keys.is_empty() {
+        return Ok(AggregationOutcome::NoNewVotes);
+    }
 
-    Ok(BatchedTxResult {
+    let tx_result = BatchedTxResult {
         changed_keys,
         ..Default::default()
+    };
+    Ok(if categories.tally_seen_changed {
+        AggregationOutcome::Completed(tx_result)
+    } else {
+        AggregationOutcome::Updated(tx_result)
+    })
+}
+
+/// The outcome of aggregating validators' votes on a validator set
+/// update, as computed by [`simulate_aggregate_votes`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TallySimulation {
+    /// Whether the tally would reach a `seen` state.
+    pub would_be_seen: bool,
+    /// Fraction of the total voting power that would be behind the tally.
+    pub resulting_voting_power: FractionalVotingPower,
+    /// The storage keys which aggregating the votes would change.
+    pub changed_keys: ChangedKeys,
+}
+
+/// Compute the outcome of [`aggregate_votes`] without writing anything
+/// to storage, e.g. to determine whether including `ext` in a block
+/// would complete a validator set update proof.
+pub fn simulate_aggregate_votes<D, H, Gov>(
+    state: &WlState<D, H>,
+    ext: validator_set_update::VextDigest,
+    signing_epoch: Epoch,
+    verification: SigVerification,
+) -> Result<TallySimulation>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
+    H: 'static + StorageHasher + Sync,
+    Gov: governance::Read<WlState<D, H>>,
+{
+    let ext = match verification {
+        SigVerification::Enabled => {
+            retain_valid_signatures::<D, H, Gov>(state, ext, signing_epoch)
+        }
+        SigVerification::Disabled => ext,
+    };
+    let keys = vote_tallies::Keys::<EthereumProof<VotingPowersMap>>::from(
+        &valset_upd_activation_epoch(signing_epoch),
+    );
+    let seen = votes::storage::maybe_read_seen(state, &keys)?;
+    let update = if ext.signatures.is_empty() {
+        None
+    } else {
+        let vote_height = state
+            .ethbridge_queries()
+            .get_valset_upd_vote_height(signing_epoch)?
+            .ok_or_else(|| {
+                eyre!("The vote height of epoch {signing_epoch} is unknown")
+            })?;
+        calculate_update::<D, H, Gov>(
+            state,
+            ext,
+            signing_epoch,
+            vote_height,
+            seen,
+        )?
+    };
+
+    let (tally, changed_keys) = match update {
+        Some(update) => (Some(update.tally), update.changed),
+        None => {
+            let tally = seen
+                .map(|_| votes::storage::read(state, &keys))
+                .transpose()?;
+            (tally, ChangedKeys::default())
+        }
+    };
+    Ok(TallySimulation {
+        would_be_seen: tally.as_ref().is_some_and(|tally| tally.seen),
+        resulting_voting_power: tally
+            .map(|tally| {
+                tally.voting_power.fractional_stake::<_, _, Gov>(state)
+            })
+            .unwrap_or_default(),
+        changed_keys,
     })
 }
 
+/// Drop the signatures of `ext` whose recovered signer is not the
+/// Ethereum hot key of the respective validator at `signing_epoch`.
+///
+/// Since the message is recovered according to
+/// [`validator_set_update::VALSET_UPD_MESSAGE_VERSION`], this rejects
+/// signatures produced over messages of other versions.
+fn retain_valid_signatures<D, H, Gov>(
+    state: &WlState<D, H>,
+    ext: validator_set_update::VextDigest,
+    signing_epoch: Epoch,
+) -> validator_set_update::VextDigest
+where
+    D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
+    H: 'static + StorageHasher + Sync,
+    Gov: governance::Read<WlState<D, H>>,
+{
+    let validator_set_update::VextDigest {
+        signatures,
+        voting_powers,
+    } = ext;
+    let signatures = signatures
+        .into_iter()
+        .filter(|(validator, signature)| {
+            let Some(hot_key_addr) = state
+                .ethbridge_queries()
+                .get_ethbridge_from_namada_addr::<Gov>(
+                    validator,
+                    Some(signing_epoch),
+                )
+            else {
+                tracing::warn!(
+                    %validator,
+                    "Rejecting validator set update signature from a \
+                     validator without an Ethereum hot key"
+                );
+                return false;
+            };
+            let valid = validator_set_update::Vext {
+                voting_powers: voting_powers.clone(),
+                validator_addr: validator.clone(),
+                signing_epoch,
+            }
+            .verify_signer(signature, &hot_key_addr)
+            .is_ok();
+            if !valid {
+                tracing::warn!(
+                    %validator,
+                    ?signing_epoch,
+                    "Rejecting validator set update signature not produced \
+                     over the current message format"
+                );
+            }
+            valid
+        })
+        .collect();
+    validator_set_update::VextDigest {
+        signatures,
+        voting_powers,
+    }
+}
+
+/// Read the validator set update proof for the given epoch, along with
+/// whether it is complete, i.e. whether it has reached a `seen` state.
+///
+/// Proofs are stored under the sub-key space of the epoch they are meant
+/// for, such that the proof for `epoch` is signed by the validators of the
+/// epoch before it. Returns `None` if no proof exists for `epoch`.
+pub fn read_valset_upd_proof<D, H>(
+    state: &WlState<D, H>,
+    epoch: Epoch,
+) -> Result<Option<(EthereumProof<VotingPowersMap>, bool)>>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
+    H: 'static + StorageHasher + Sync,
+{
+    let keys = vote_tallies::Keys::from(&epoch);
+    let Some(proof) = votes::storage::maybe_read_body(state, &keys)? else {
+        return Ok(None);
+    };
+    let seen = votes::storage::maybe_read_seen(state, &keys)?.unwrap_or(false);
+    Ok(Some((proof, seen)))
+}
+
+/// Read the voting powers signed over by the validator set update proof
+/// for the given epoch, without decoding the signatures of the proof.
+/// Returns `None` if no proof exists for `epoch`.
+pub fn read_valset_upd_voting_powers<D, H>(
+    state: &WlState<D, H>,
+    epoch: Epoch,
+) -> Result<Option<VotingPowersMap>>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
+    H: 'static + StorageHasher + Sync,
+{
+    let body =
+        vote_tallies::Keys::<EthereumProof<VotingPowersMap>>::from(&epoch)
+            .body();
+    let Some(bytes) = state.read_bytes(&body)? else {
+        return Ok(None);
+    };
+    EthereumProof::<VotingPowersMap>::deserialize_data(&bytes)
+        .map(Some)
+        .map_err(|err| {
+            eyre!("Failed to decode the voting powers at {body}: {err}")
+        })
+}
+
+/// Read the voting power behind the validator set update proof for the
+/// given epoch, as recorded in its tally. Returns `None` if the proof is
+/// not complete, i.e. if it has not reached a `seen` state.
+pub fn completed_voting_power<D, H, Gov>(
+    state: &WlState<D, H>,
+    epoch: Epoch,
+) -> Result<Option<FractionalVotingPower>>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
+    H: 'static + StorageHasher + Sync,
+    Gov: governance::Read<WlState<D, H>>,
+{
+    let keys = vote_tallies::Keys::from(&epoch);
+    if !votes::storage::maybe_read_seen(state, &keys)?.unwrap_or(false) {
+        return Ok(None);
+    }
+    let tally = votes::storage::read(state, &keys)?;
+    Ok(Some(
+        tally.voting_power.fractional_stake::<_, _, Gov>(state),
+    ))
+}
+
+/// Summary of the progress of a validator set update tally towards
+/// a complete proof, which does not require fetching the proof itself.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TallyProgress {
+    /// Whether the tally has reached a `seen` state.
+    pub seen: bool,
+    /// Fraction of the total voting power behind the tally.
+    pub voting_power: FractionalVotingPower,
+    /// Number of signatures attached to the proof.
+    pub num_signers: usize,
+}
+
+/// Read the progress of the validator set update tally for the given
+/// epoch. Returns `None` if no tally exists for `epoch`.
+pub fn valset_upd_tally_progress<D, H, Gov>(
+    state: &WlState<D, H>,
+    epoch: Epoch,
+) -> Result<Option<TallyProgress>>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
+    H: 'static + StorageHasher + Sync,
+    Gov: governance::Read<WlState<D, H>>,
+{
+    let keys = vote_tallies::Keys::from(&epoch);
+    let Some(proof) = votes::storage::maybe_read_body(state, &keys)? else {
+        return Ok(None);
+    };
+    let tally = votes::storage::read(state, &keys)?;
+    Ok(Some(TallyProgress {
+        seen: tally.seen,
+        voting_power: tally.voting_power.fractional_stake::<_, _, Gov>(state),
+        num_signers: proof.signatures.len(),
+    }))
+}
+
+/// Check whether `validator` has signed the validator set update proof
+/// for the given epoch.
+///
+/// As with [`read_valset_upd_proof`], `epoch` is the epoch the proof is
+/// meant for, such that the signers are validators of the epoch before it.
+/// Returns `false` if no tally exists for `epoch`.
+pub fn has_signed_valset_upd<D, H>(
+    state: &WlState<D, H>,
+    epoch: Epoch,
+    validator: &Address,
+) -> Result<bool>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
+    H: 'static + StorageHasher + Sync,
+{
+    let keys = vote_tallies::Keys::from(&epoch);
+    let seen_by = votes::storage::maybe_read_seen_by(state, &keys)?;
+    Ok(seen_by.is_some_and(|seen_by| seen_by.contains_key(validator)))
+}
+
+/// Iterate over all validator set update proofs that have reached a
+/// `seen` state in storage, ordered by the epoch they were signed for.
+pub fn iter_completed_proofs<D, H>(
+    state: &WlState<D, H>,
+) -> Result<impl Iterator<Item = (Epoch, EthereumProof<VotingPowersMap>)>>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
+    H: 'static + StorageHasher + Sync,
+{
+    let proofs = read_seen_flags(state)?
+        .into_iter()
+        .filter_map(|(epoch, seen)| seen.then_some(epoch))
+        .map(|epoch| {
+            let proof = votes::storage::read_body(
+                state,
+                &vote_tallies::Keys::from(&epoch),
+            )?;
+            Ok((epoch, proof))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(proofs.into_iter())
+}
+
+/// Return the epochs of all validator set update proofs in storage that
+/// have not reached a `seen` state yet, in ascending order.
+pub fn pending_valset_upd_epochs<D, H>(
+    state: &WlState<D, H>,
+) -> Result<Vec<Epoch>>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
+    H: 'static + StorageHasher + Sync,
+{
+    Ok(read_seen_flags(state)?
+        .into_iter()
+        .filter_map(|(epoch, seen)| (!seen).then_some(epoch))
+        .collect())
+}
+
+/// Read the `seen` flags of all validator set update tallies in storage,
+/// indexed by the epoch the proofs were signed for.
+fn read_seen_flags<D, H>(state: &WlState<D, H>) -> Result<BTreeMap<Epoch, bool>>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
+    H: 'static + StorageHasher + Sync,
+{
+    let prefix = vote_tallies::valset_upds_prefix();
+    let mut seen_flags = BTreeMap::new();
+    for (key, val, _) in votes::storage::iter_prefix(state, &prefix)? {
+        let key = Key::parse(&key).map_err(|err| {
+            eyre!("Failed to parse the storage key {key}: {err}")
+        })?;
+        let epoch = match &key.segments[..] {
+            [
+                DbKeySeg::AddressSeg(ADDRESS),
+                DbKeySeg::StringSeg(_prefix),
+                DbKeySeg::StringSeg(epoch),
+                DbKeySeg::StringSeg(seg),
+            ] if seg == KeysSegments::VALUES.seen => {
+                Epoch::parse(epoch.clone())?
+            }
+            _ => continue,
+        };
+        let seen = bool::try_from_slice(&val[..]).map_err(|err| {
+            eyre!("Failed to decode the seen flag at {key}: {err}")
+        })?;
+        seen_flags.insert(epoch, seen);
+    }
+    Ok(seen_flags)
+}
+
+/// Apply `ext` to its tally, returning the changed keys, along with which
+/// of the keys of the tally they are. `seen` is the `seen` flag of the
+/// tally in storage, if the tally exists.
 fn apply_update<D, H, Gov>(
     state: &mut WlState<D, H>,
     ext: validator_set_update::VextDigest,
     signing_epoch: Epoch,
-    epoch_2nd_height: BlockHeight,
-    voting_powers: HashMap<(Address, BlockHeight), Amount>,
-) -> Result<ChangedKeys>
+    vote_height: BlockHeight,
+    seen: Option<bool>,
+) -> Result<(ChangedKeys, ChangedTallyKeys)>
 where
     D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
     H: 'static + StorageHasher + Sync,
     Gov: governance::Read<WlState<D, H>>,
 {
-    let next_epoch = {
-        // proofs should be written to the sub-key space of the next epoch.
-        // this way, we do, for instance, an RPC call to `E=2` to query a
-        // validator set proof for epoch 2 signed by validators of epoch 1.
-        signing_epoch.next()
+    let Some(PendingUpdate {
+        keys: valset_upd_keys,
+        tally,
+        proof,
+        changed,
+        confirmed,
+        already_present,
+    }) = calculate_update::<D, H, Gov>(
+        state,
+        ext,
+        signing_epoch,
+        vote_height,
+        seen,
+    )?
+    else {
+        return Ok(Default::default());
     };
-    let valset_upd_keys = vote_tallies::Keys::from(&next_epoch);
-    let maybe_proof = 'check_storage: {
-        let Some(seen) =
-            votes::storage::maybe_read_seen(state, &valset_upd_keys)?
-        else {
-            break 'check_storage None;
-        };
-        if seen {
+
+    tracing::debug!(
+        ?tally,
+        ?proof,
+        "Applying validator set update state changes"
+    );
+    votes::storage::write(
+        state,
+        &valset_upd_keys,
+        &proof,
+        &tally,
+        already_present,
+    )?;
+
+    if confirmed {
+        tracing::debug!(
+            %valset_upd_keys.prefix,
+            "Acquired complete proof on validator set update"
+        );
+    }
+
+    let categories = ChangedTallyKeys::categorize(&valset_upd_keys, &changed);
+    Ok((changed, categories))
+}
+
+/// The state changes of applying a validator set update digest to its
+/// tally, which have yet to be written to storage.
+struct PendingUpdate {
+    keys: vote_tallies::Keys<EthereumProof<VotingPowersMap>>,
+    tally: votes::Tally,
+    proof: EthereumProof<VotingPowersMap>,
+    changed: ChangedKeys,
+    confirmed: bool,
+    already_present: bool,
+}
+
+/// Compute the epoch at which the validator set signed at `signing_epoch`
+/// becomes active.
+#[inline]
+fn valset_upd_activation_epoch(signing_epoch: Epoch) -> Epoch {
+    // proofs should be written to the sub-key space of the next epoch.
+    // this way, we do, for instance, an RPC call to `E=2` to query a
+    // validator set proof for epoch 2 signed by validators of epoch 1.
+    //
+    // NOTE: the offset of one epoch is not configurable, since the
+    // bridge contract expects validator set updates to be signed
+    // against the next nonce (i.e. the next epoch), and readers such
+    // as vote extension validation look up proofs at the next epoch
+    signing_epoch.next()
+}
+
+/// Key the signatures of a validator set update by the Ethereum address
+/// book of their signers at `signing_epoch`, failing if any of the
+/// signers lacks valid (i.e. secp256k1) Ethereum keys.
+fn signatures_by_addr_book<D, H, Gov>(
+    state: &WlState<D, H>,
+    signatures: HashMap<Address, common::Signature>,
+    signing_epoch: Epoch,
+) -> Result<Vec<(EthAddrBook, common::Signature)>>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
+    H: 'static + StorageHasher + Sync,
+    Gov: governance::Read<WlState<D, H>>,
+{
+    signatures
+        .into_iter()
+        .map(|(addr, sig)| {
+            let addr_book = state
+                .ethbridge_queries()
+                .get_eth_addr_book::<Gov>(&addr, Some(signing_epoch))
+                .ok_or_else(|| {
+                    eyre!(
+                        "Validator {addr} has no secp256k1 Ethereum keys at \
+                         epoch {signing_epoch}"
+                    )
+                })?;
+            Ok((addr_book, sig))
+        })
+        .collect()
+}
+
+/// Calculate the state changes of applying `ext` to its tally, without
+/// writing them to storage. Returns `None` if the tally is left unchanged.
+///
+/// `seen` is the `seen` flag of the tally in storage, if the tally exists,
+/// as already read by the caller.
+fn calculate_update<D, H, Gov>(
+    state: &WlState<D, H>,
+    ext: validator_set_update::VextDigest,
+    signing_epoch: Epoch,
+    vote_height: BlockHeight,
+    seen: Option<bool>,
+) -> Result<Option<PendingUpdate>>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
+    H: 'static + StorageHasher + Sync,
+    Gov: governance::Read<WlState<D, H>>,
+{
+    let activation_epoch = valset_upd_activation_epoch(signing_epoch);
+    let valset_upd_keys = vote_tallies::Keys::from(&activation_epoch);
+    let maybe_proof = match seen {
+        None => None,
+        Some(true) => {
             tracing::debug!("Validator set update tally is already seen");
-            return Ok(ChangedKeys::default());
+            return Ok(None);
+        }
+        Some(false) => {
+            Some(votes::storage::read_body(state, &valset_upd_keys)?)
         }
-        let proof = votes::storage::read_body(state, &valset_upd_keys)?;
-        Some(proof)
     };
+    // only look up voting powers once we know the tally needs updating
+    let voting_powers = utils::get_voting_powers(state, (&ext, vote_height))?;
 
-    let mut seen_by = Votes::default();
-    for address in ext.signatures.keys().cloned() {
-        if let Some(present) = seen_by.insert(address, epoch_2nd_height) {
-            // TODO(namada#770): this shouldn't be happening in any case and we
-            // should be refactoring to get rid of `BlockHeight`
-            tracing::warn!(?present, "Duplicate vote in digest");
-        }
-    }
+    // NB: the signatures of a digest are keyed by the address of their
+    // signer, so a validator can never vote more than once in a digest,
+    // nor have its voting power counted twice
+    let seen_by: Votes = ext
+        .signatures
+        .keys()
+        .cloned()
+        .map(|address| (address, vote_height))
+        .collect();
 
     let (tally, proof, changed, confirmed, already_present) =
         if let Some(mut proof) = maybe_proof {
@@ -172,24 +687,21 @@ where
                 new_votes,
             )?;
             if changed.is_empty() {
-                return Ok(changed);
+                return Ok(None);
             }
             let confirmed =
                 tally.seen && changed.contains(&valset_upd_keys.seen());
-            proof.attach_signature_batch(ext.signatures.into_iter().map(
-                |(addr, sig)| {
-                    (
-                        state
-                            .ethbridge_queries()
-                            .get_eth_addr_book::<Gov>(
-                                &addr,
-                                Some(signing_epoch),
-                            )
-                            .expect("All validators should have eth keys"),
-                        sig,
-                    )
-                },
-            ));
+            let signatures = signatures_by_addr_book::<D, H, Gov>(
+                state,
+                ext.signatures,
+                signing_epoch,
+            )?;
+            let fresh_signatures = proof.attach_signature_batch(signatures);
+            tracing::debug!(
+                %valset_upd_keys.prefix,
+                fresh_signatures,
+                "Attached new signatures to validator set update proof"
+            );
             (tally, proof, changed, confirmed, true)
         } else {
             tracing::debug!(
@@ -202,62 +714,68 @@ where
                 seen_by,
                 &voting_powers,
             )?;
+            let signatures = signatures_by_addr_book::<D, H, Gov>(
+                state,
+                ext.signatures,
+                signing_epoch,
+            )?;
             let mut proof = EthereumProof::new(ext.voting_powers);
-            proof.attach_signature_batch(ext.signatures.into_iter().map(
-                |(addr, sig)| {
-                    (
-                        state
-                            .ethbridge_queries()
-                            .get_eth_addr_book::<Gov>(
-                                &addr,
-                                Some(signing_epoch),
-                            )
-                            .expect("All validators should have eth keys"),
-                        sig,
-                    )
-                },
-            ));
+            proof.attach_signature_batch(signatures);
             let changed = valset_upd_keys.into_iter().collect();
             let confirmed = tally.seen;
             (tally, proof, changed, confirmed, false)
         };
 
-    tracing::debug!(
-        ?tally,
-        ?proof,
-        "Applying validator set update state changes"
-    );
-    votes::storage::write(
-        state,
-        &valset_upd_keys,
-        &proof,
-        &tally,
+    Ok(Some(PendingUpdate {
+        keys: valset_upd_keys,
+        tally,
+        proof,
+        changed,
+        confirmed,
         already_present,
-    )?;
-
-    if confirmed {
-        tracing::debug!(
-            %valset_upd_keys.prefix,
-            "Acquired complete proof on validator set update"
-        );
-    }
-
-    Ok(changed)
+    }))
 }
 
 #[cfg(test)]
 mod test_valset_upd_state_changes {
-    use namada_core::address;
-    use namada_core::voting_power::FractionalVotingPower;
+    use namada_core::collections::HashMap;
+    use namada_core::ethereum_events::EthAddress;
+    use namada_core::token::Amount;
+    use namada_core::{address, key};
     use namada_proof_of_stake::queries::{
         get_total_voting_power, read_validator_stake,
     };
-    use namada_state::StorageRead;
-    use namada_vote_ext::validator_set_update::VotingPowersMap;
+    use namada_state::testing::TestState;
+    use namada_storage::StorageWrite;
+    use namada_tx::Signed;
+    use namada_vote_ext::validator_set_update::VALSET_UPD_MESSAGE_VERSION;
 
     use super::*;
     use crate::test_utils::{self, GovStore};
 
+    /// Write a validator set update `proof` to storage under `epoch`,
+    /// along with an empty tally which may or may not be `seen`.
+    fn write_valset_upd_tally(
+        state: &mut TestState,
+        epoch: Epoch,
+        proof: &EthereumProof<VotingPowersMap>,
+        seen: bool,
+    ) {
+        let tally = votes::Tally {
+            voting_power: Default::default(),
+            seen_by: Default::default(),
+            seen,
+        };
+        votes::storage::write(
+            state,
+            &vote_tallies::Keys::from(&epoch),
+            proof,
+            &tally,
+            false,
+        )
+        .expect("Test failed");
+    }
+
     /// Test that if a validator set update becomes "seen", then
     /// it should have a complete proof backing it up in storage.
     #[test]
@@ -265,29 +783,26 @@ mod test_valset_upd_state_changes {
         let (mut state, keys) = test_utils::setup_default_storage();
 
         let last_height = state.in_mem().get_last_block_height();
-        let signing_epoch = state
-            .get_epoch_at_height(last_height)
-            .unwrap()
-            .expect("The epoch of the last block height should be known");
+        let signing_epoch = test_utils::epoch_of_height(&state, last_height);
 
         let tx_result = aggregate_votes::<_, _, GovStore<_>>(
             &mut state,
-            validator_set_update::VextDigest::singleton(
-                validator_set_update::Vext {
-                    voting_powers: VotingPowersMap::new(),
-                    validator_addr: address::testing::established_address_1(),
-                    signing_epoch,
-                }
-                .sign(
+            test_utils::build_valset_upd_digest(
+                &[(
+                    address::testing::established_address_1(),
                     &keys
                         .get(&address::testing::established_address_1())
                         .expect("Test failed")
                         .eth_bridge,
-                ),
+                )],
+                VotingPowersMap::new(),
+                signing_epoch,
             ),
             signing_epoch,
+            SigVerification::Disabled,
         )
-        .expect("Test failed");
+        .expect("Test failed")
+        .into_tx_result();
 
         // let's make sure we updated storage
         assert!(!tx_result.changed_keys.is_empty());
@@ -366,29 +881,26 @@ mod test_valset_upd_state_changes {
             ]));
 
         let last_height = state.in_mem().get_last_block_height();
-        let signing_epoch = state
-            .get_epoch_at_height(last_height)
-            .unwrap()
-            .expect("The epoch of the last block height should be known");
+        let signing_epoch = test_utils::epoch_of_height(&state, last_height);
 
         let tx_result = aggregate_votes::<_, _, GovStore<_>>(
             &mut state,
-            validator_set_update::VextDigest::singleton(
-                validator_set_update::Vext {
-                    voting_powers: VotingPowersMap::new(),
-                    validator_addr: address::testing::established_address_1(),
-                    signing_epoch,
-                }
-                .sign(
+            test_utils::build_valset_upd_digest(
+                &[(
+                    address::testing::established_address_1(),
                     &keys
                         .get(&address::testing::established_address_1())
                         .expect("Test failed")
                         .eth_bridge,
-                ),
+                )],
+                VotingPowersMap::new(),
+                signing_epoch,
             ),
             signing_epoch,
+            SigVerification::Disabled,
         )
-        .expect("Test failed");
+        .expect("Test failed")
+        .into_tx_result();
 
         // let's make sure we updated storage
         assert!(!tx_result.changed_keys.is_empty());
@@ -447,4 +959,926 @@ mod test_valset_upd_state_changes {
 
         assert!(voting_power <= FractionalVotingPower::TWO_THIRDS);
     }
+
+    /// Test that only proofs of validator set updates that have been
+    /// "seen" are returned, in ascending epoch order.
+    #[test]
+    fn test_iter_completed_proofs_in_epoch_order() {
+        let (mut state, _) = test_utils::setup_default_storage();
+
+        let proof = EthereumProof::new(VotingPowersMap::new());
+        write_valset_upd_tally(&mut state, Epoch(3), &proof, true);
+        write_valset_upd_tally(&mut state, Epoch(1), &proof, false);
+        write_valset_upd_tally(&mut state, Epoch(2), &proof, true);
+
+        let epochs: Vec<_> = iter_completed_proofs(&state)
+            .expect("Test failed")
+            .map(|(epoch, proof)| {
+                assert_eq!(proof.data, VotingPowersMap::new());
+                epoch
+            })
+            .collect();
+        assert_eq!(epochs, vec![Epoch(2), Epoch(3)]);
+    }
+
+    /// Test that a malformed `seen` flag in storage is reported as an
+    /// error, rather than panicking.
+    #[test]
+    fn test_malformed_seen_flag() {
+        let (mut state, _) = test_utils::setup_default_storage();
+        let keys = vote_tallies::Keys::<EthereumProof<VotingPowersMap>>::from(
+            &Epoch(1),
+        );
+        state.write_bytes(&keys.seen(), [2u8]).expect("Test failed");
+
+        assert!(iter_completed_proofs(&state).is_err());
+        assert!(pending_valset_upd_epochs(&state).is_err());
+    }
+
+    /// Test that only the epochs of proofs which have not been seen
+    /// are reported as pending.
+    #[test]
+    fn test_pending_valset_upd_epochs() {
+        let (mut state, _) = test_utils::setup_default_storage();
+
+        let proof = EthereumProof::new(VotingPowersMap::new());
+        for (epoch, seen) in [(1, true), (2, false)] {
+            write_valset_upd_tally(&mut state, Epoch(epoch), &proof, seen);
+        }
+
+        let pending = pending_valset_upd_epochs(&state).expect("Test failed");
+        assert_eq!(pending, vec![Epoch(2)]);
+    }
+
+    /// Test reading validator set update proofs by epoch.
+    #[test]
+    fn test_read_valset_upd_proof() {
+        let (mut state, _) = test_utils::setup_default_storage();
+
+        let proof = EthereumProof::new(VotingPowersMap::new());
+        for (epoch, seen) in [(1, false), (2, true)] {
+            write_valset_upd_tally(&mut state, Epoch(epoch), &proof, seen);
+        }
+
+        let (proof, complete) = read_valset_upd_proof(&state, Epoch(1))
+            .expect("Test failed")
+            .expect("Test failed");
+        assert_eq!(proof.data, VotingPowersMap::new());
+        assert!(!complete);
+
+        let (_, complete) = read_valset_upd_proof(&state, Epoch(2))
+            .expect("Test failed")
+            .expect("Test failed");
+        assert!(complete);
+
+        assert!(
+            read_valset_upd_proof(&state, Epoch(3))
+                .expect("Test failed")
+                .is_none()
+        );
+    }
+
+    /// Test that the voting powers of a validator set update proof can
+    /// be read without its signatures.
+    #[test]
+    fn test_read_valset_upd_voting_powers() {
+        let (mut state, _) = test_utils::setup_default_storage();
+
+        let addr_book = |i: u8| EthAddrBook {
+            hot_key_addr: EthAddress([i; 20]),
+            cold_key_addr: EthAddress([i.wrapping_add(1); 20]),
+        };
+        let voting_powers: VotingPowersMap = [
+            (addr_book(1), Amount::from_u64(100)),
+            (addr_book(3), Amount::from_u64(200)),
+        ]
+        .into_iter()
+        .collect();
+        let mut proof = EthereumProof::new(voting_powers);
+        for (i, key) in [
+            (1, key::testing::keypair_3()),
+            (3, key::testing::keypair_4()),
+        ] {
+            proof.attach_signature(
+                addr_book(i),
+                Signed::<&'static str>::new(&key, "data").sig,
+            );
+        }
+        write_valset_upd_tally(&mut state, Epoch(1), &proof, false);
+
+        let voting_powers = read_valset_upd_voting_powers(&state, Epoch(1))
+            .expect("Test failed")
+            .expect("Test failed");
+        assert_eq!(voting_powers, proof.data);
+        assert!(
+            read_valset_upd_voting_powers(&state, Epoch(2))
+                .expect("Test failed")
+                .is_none()
+        );
+    }
+
+    /// Test that votes on an already seen validator set update are
+    /// ignored without looking up the voting powers of their signers.
+    #[test]
+    fn test_seen_update_skips_voting_power_lookup() {
+        let (mut state, _) = test_utils::setup_default_storage();
+
+        let last_height = state.in_mem().get_last_block_height();
+        let signing_epoch = test_utils::epoch_of_height(&state, last_height);
+        write_valset_upd_tally(
+            &mut state,
+            signing_epoch.next(),
+            &EthereumProof::new(VotingPowersMap::new()),
+            true,
+        );
+
+        // the signer is not a consensus validator, so looking up its voting
+        // power would fail
+        let signer = address::testing::established_address_2();
+        let tx_result = aggregate_votes::<_, _, GovStore<_>>(
+            &mut state,
+            test_utils::build_valset_upd_digest(
+                &[(
+                    signer,
+                    &test_utils::TestValidatorKeys::generate().eth_bridge,
+                )],
+                VotingPowersMap::new(),
+                signing_epoch,
+            ),
+            signing_epoch,
+            SigVerification::Disabled,
+        )
+        .expect("Test failed")
+        .into_tx_result();
+
+        assert!(tx_result.changed_keys.is_empty());
+    }
+
+    /// Test that aggregating votes on an already seen validator set update
+    /// writes nothing, and that its storage reads do not depend on the
+    /// number of votes being aggregated.
+    #[test]
+    fn test_seen_update_storage_accesses() {
+        let (mut state, _) = test_utils::setup_default_storage();
+
+        let last_height = state.in_mem().get_last_block_height();
+        let signing_epoch = test_utils::epoch_of_height(&state, last_height);
+        write_valset_upd_tally(
+            &mut state,
+            signing_epoch.next(),
+            &EthereumProof::new(VotingPowersMap::new()),
+            true,
+        );
+        state.commit_block().expect("Test failed");
+
+        let signer_key = test_utils::TestValidatorKeys::generate().eth_bridge;
+        let mut aggregate = |signers: Vec<Address>| {
+            let signers: Vec<_> = signers
+                .into_iter()
+                .map(|signer| (signer, &signer_key))
+                .collect();
+            let ext = test_utils::build_valset_upd_digest(
+                &signers,
+                VotingPowersMap::new(),
+                signing_epoch,
+            );
+
+            let before = test_utils::StorageAccessCounter::snapshot(&state);
+            let tx_result = aggregate_votes::<_, _, GovStore<_>>(
+                &mut state,
+                ext,
+                signing_epoch,
+                SigVerification::Disabled,
+            )
+            .expect("Test failed")
+            .into_tx_result();
+            assert!(tx_result.changed_keys.is_empty());
+            test_utils::StorageAccessCounter::snapshot(&state).since(&before)
+        };
+
+        let one_vote =
+            aggregate(vec![address::testing::established_address_1()]);
+        let many_votes = aggregate(vec![
+            address::testing::established_address_1(),
+            address::testing::established_address_2(),
+            address::testing::established_address_3(),
+        ]);
+
+        assert_eq!(one_vote.writes(), 0);
+        assert!(one_vote.reads() > 0);
+        assert_eq!(many_votes, one_vote);
+    }
+
+    /// Test that votes are recorded at the block height given by the
+    /// configured vote height offset.
+    #[test]
+    fn test_valset_upd_vote_height_offset() {
+        let (mut state, keys) = test_utils::setup_default_storage();
+        state
+            .write(&crate::storage::valset_upd_vote_height_offset_key(), 0u64)
+            .expect("Test failed");
+
+        let last_height = state.in_mem().get_last_block_height();
+        let signing_epoch = test_utils::epoch_of_height(&state, last_height);
+        let epoch_start_height = state
+            .in_mem()
+            .block
+            .pred_epochs
+            .get_start_height_of_epoch(signing_epoch)
+            .expect("Test failed");
+
+        let validator = address::testing::established_address_1();
+        aggregate_votes::<_, _, GovStore<_>>(
+            &mut state,
+            test_utils::build_valset_upd_digest(
+                &[(
+                    validator.clone(),
+                    &keys.get(&validator).expect("Test failed").eth_bridge,
+                )],
+                VotingPowersMap::new(),
+                signing_epoch,
+            ),
+            signing_epoch,
+            SigVerification::Disabled,
+        )
+        .expect("Test failed");
+
+        let valset_upd_keys = vote_tallies::Keys::from(&signing_epoch.next());
+        let tally = votes::storage::read(&state, &valset_upd_keys)
+            .expect("Test failed");
+        assert_eq!(tally.seen_by.get(&validator), Some(&epoch_start_height));
+    }
+
+    /// Test that a vote height offset beyond the minimum number of
+    /// blocks per epoch is rejected, rather than aggregating votes at a
+    /// height which may never be reached.
+    #[test]
+    fn test_valset_upd_vote_height_offset_out_of_range() {
+        let (mut state, keys) = test_utils::setup_default_storage();
+        let min_num_of_blocks =
+            namada_parameters::read_epoch_duration_parameter(&state)
+                .expect("Test failed")
+                .min_num_of_blocks;
+        state
+            .write(
+                &crate::storage::valset_upd_vote_height_offset_key(),
+                min_num_of_blocks,
+            )
+            .expect("Test failed");
+        assert!(
+            state
+                .ethbridge_queries()
+                .get_valset_upd_vote_height_offset()
+                .is_err()
+        );
+
+        let last_height = state.in_mem().get_last_block_height();
+        let signing_epoch = test_utils::epoch_of_height(&state, last_height);
+        let validator = address::testing::established_address_1();
+        let err = aggregate_votes::<_, _, GovStore<_>>(
+            &mut state,
+            test_utils::build_valset_upd_digest(
+                &[(
+                    validator.clone(),
+                    &keys.get(&validator).expect("Test failed").eth_bridge,
+                )],
+                VotingPowersMap::new(),
+                signing_epoch,
+            ),
+            signing_epoch,
+            SigVerification::Disabled,
+        )
+        .expect_err("Test failed");
+        assert!(err.to_string().contains("vote height offset"));
+        assert!(
+            read_valset_upd_proof(&state, signing_epoch.next())
+                .expect("Test failed")
+                .is_none()
+        );
+    }
+
+    /// Test that the progress of an incomplete tally is reported.
+    #[test]
+    fn test_valset_upd_tally_progress() {
+        let (mut state, keys) =
+            test_utils::setup_storage_with_validators(HashMap::from_iter([
+                (
+                    address::testing::established_address_1(),
+                    Amount::native_whole(50_000),
+                ),
+                (
+                    address::testing::established_address_2(),
+                    Amount::native_whole(50_000),
+                ),
+            ]));
+
+        let last_height = state.in_mem().get_last_block_height();
+        let signing_epoch = test_utils::epoch_of_height(&state, last_height);
+        let activation_epoch = signing_epoch.next();
+
+        let progress = valset_upd_tally_progress::<_, _, GovStore<_>>(
+            &state,
+            activation_epoch,
+        )
+        .expect("Test failed");
+        assert_eq!(progress, None);
+
+        let validator = address::testing::established_address_1();
+        aggregate_votes::<_, _, GovStore<_>>(
+            &mut state,
+            test_utils::build_valset_upd_digest(
+                &[(
+                    validator.clone(),
+                    &keys.get(&validator).expect("Test failed").eth_bridge,
+                )],
+                VotingPowersMap::new(),
+                signing_epoch,
+            ),
+            signing_epoch,
+            SigVerification::Disabled,
+        )
+        .expect("Test failed");
+
+        let progress = valset_upd_tally_progress::<_, _, GovStore<_>>(
+            &state,
+            activation_epoch,
+        )
+        .expect("Test failed")
+        .expect("Test failed");
+        assert_eq!(
+            progress,
+            TallyProgress {
+                seen: false,
+                voting_power: FractionalVotingPower::HALF,
+                num_signers: 1,
+            }
+        );
+        assert!(progress.voting_power < FractionalVotingPower::TWO_THIRDS);
+    }
+
+    /// Test that a validator set update signed with an Ethereum hot key
+    /// of the wrong scheme is rejected.
+    #[test]
+    fn test_reject_non_secp256k1_eth_bridge_key() {
+        use namada_core::key::RefTo;
+        use namada_proof_of_stake::storage::validator_eth_hot_key_handle;
+
+        let (mut state, _) = test_utils::setup_default_storage();
+        let validator = address::testing::established_address_1();
+
+        let keys = test_utils::TestValidatorKeys::generate_with(
+            test_utils::KeySchemes {
+                eth_bridge: key::SchemeType::Ed25519,
+                ..Default::default()
+            },
+        );
+        assert!(matches!(keys.eth_bridge, common::SecretKey::Ed25519(_)));
+
+        // Register the Ed25519 key as the validator's Ethereum hot key
+        let last_height = state.in_mem().get_last_block_height();
+        let signing_epoch = test_utils::epoch_of_height(&state, last_height);
+        validator_eth_hot_key_handle(&validator)
+            .set::<_, GovStore<_>>(
+                &mut state,
+                keys.eth_bridge.ref_to(),
+                signing_epoch,
+                0,
+            )
+            .expect("Test failed");
+
+        let tx_result = aggregate_votes::<_, _, GovStore<_>>(
+            &mut state,
+            validator_set_update::VextDigest::singleton(
+                validator_set_update::Vext {
+                    voting_powers: VotingPowersMap::new(),
+                    validator_addr: validator,
+                    signing_epoch,
+                }
+                .sign_with_version(
+                    &keys.eth_bridge,
+                    VALSET_UPD_MESSAGE_VERSION,
+                ),
+            ),
+            signing_epoch,
+            SigVerification::Enabled,
+        )
+        .expect("Test failed")
+        .into_tx_result();
+
+        assert!(tx_result.changed_keys.is_empty());
+        assert!(
+            read_valset_upd_proof(&state, signing_epoch.next())
+                .expect("Test failed")
+                .is_none()
+        );
+    }
+
+    /// Test that simulating the aggregation of votes predicts the outcome
+    /// of the real aggregation, without writing to storage.
+    #[test]
+    fn test_simulate_aggregate_votes() {
+        let (mut state, keys) =
+            test_utils::setup_storage_with_validators(HashMap::from_iter([
+                (
+                    address::testing::established_address_1(),
+                    Amount::native_whole(50_000),
+                ),
+                (
+                    address::testing::established_address_2(),
+                    Amount::native_whole(50_000),
+                ),
+            ]));
+
+        let last_height = state.in_mem().get_last_block_height();
+        let signing_epoch = test_utils::epoch_of_height(&state, last_height);
+        let valset_upd_keys = vote_tallies::Keys::from(&signing_epoch.next());
+        let digest = |validator: Address| {
+            test_utils::build_valset_upd_digest(
+                &[(
+                    validator.clone(),
+                    &keys.get(&validator).expect("Test failed").eth_bridge,
+                )],
+                VotingPowersMap::new(),
+                signing_epoch,
+            )
+        };
+        // The keys modified in the write log, along with the raw values of
+        // the tally
+        let snapshot = |state: &TestState| {
+            let write_log = state.write_log();
+            let tally: Vec<_> = valset_upd_keys
+                .into_iter()
+                .map(|key| state.read_bytes(&key).expect("Test failed"))
+                .collect();
+            (
+                write_log.get_keys(),
+                write_log.block_modifications_count(),
+                tally,
+            )
+        };
+
+        for (validator, seen, voting_power) in [
+            (
+                address::testing::established_address_1(),
+                false,
+                FractionalVotingPower::HALF,
+            ),
+            (
+                address::testing::established_address_2(),
+                true,
+                FractionalVotingPower::WHOLE,
+            ),
+        ] {
+            let pre = snapshot(&state);
+            let simulation = simulate_aggregate_votes::<_, _, GovStore<_>>(
+                &state,
+                digest(validator.clone()),
+                signing_epoch,
+                SigVerification::Disabled,
+            )
+            .expect("Test failed");
+            assert_eq!(simulation.would_be_seen, seen);
+            assert_eq!(simulation.resulting_voting_power, voting_power);
+            // nothing was written to storage
+            assert_eq!(snapshot(&state), pre);
+
+            let tx_result = aggregate_votes::<_, _, GovStore<_>>(
+                &mut state,
+                digest(validator),
+                signing_epoch,
+                SigVerification::Disabled,
+            )
+            .expect("Test failed")
+            .into_tx_result();
+            assert_eq!(simulation.changed_keys, tx_result.changed_keys);
+            let tally = votes::storage::read(&state, &valset_upd_keys)
+                .expect("Test failed");
+            assert_eq!(tally.seen, simulation.would_be_seen);
+            assert_eq!(
+                tally
+                    .voting_power
+                    .fractional_stake::<_, _, GovStore<_>>(&state),
+                simulation.resulting_voting_power
+            );
+        }
+
+        // once seen, aggregating more votes changes nothing
+        let simulation = simulate_aggregate_votes::<_, _, GovStore<_>>(
+            &state,
+            digest(address::testing::established_address_1()),
+            signing_epoch,
+            SigVerification::Disabled,
+        )
+        .expect("Test failed");
+        assert!(simulation.would_be_seen);
+        assert!(simulation.changed_keys.is_empty());
+    }
+
+    /// Test that only the validators who voted are reported as signers
+    /// of a validator set update.
+    #[test]
+    fn test_has_signed_valset_upd() {
+        let signer = address::testing::established_address_1();
+        let non_signer = address::testing::established_address_2();
+        let (mut state, keys) =
+            test_utils::setup_storage_with_validators(HashMap::from_iter([
+                (signer.clone(), Amount::native_whole(50_000)),
+                (non_signer.clone(), Amount::native_whole(50_000)),
+            ]));
+
+        let last_height = state.in_mem().get_last_block_height();
+        let signing_epoch = test_utils::epoch_of_height(&state, last_height);
+        aggregate_votes::<_, _, GovStore<_>>(
+            &mut state,
+            test_utils::build_valset_upd_digest(
+                &[(
+                    signer.clone(),
+                    &keys.get(&signer).expect("Test failed").eth_bridge,
+                )],
+                VotingPowersMap::new(),
+                signing_epoch,
+            ),
+            signing_epoch,
+            SigVerification::Disabled,
+        )
+        .expect("Test failed");
+
+        // the proof signed in `signing_epoch` is stored under the next epoch
+        let epoch = signing_epoch.next();
+        assert!(
+            has_signed_valset_upd(&state, epoch, &signer).expect("Test failed")
+        );
+        assert!(
+            !has_signed_valset_upd(&state, epoch, &non_signer)
+                .expect("Test failed")
+        );
+        assert!(
+            !has_signed_valset_upd(&state, signing_epoch, &signer)
+                .expect("Test failed")
+        );
+    }
+
+    /// Test that the voting power behind a completed proof is reported.
+    #[test]
+    fn test_completed_voting_power() {
+        let (mut state, keys) = test_utils::setup_default_storage();
+
+        let last_height = state.in_mem().get_last_block_height();
+        let signing_epoch = test_utils::epoch_of_height(&state, last_height);
+        let activation_epoch = signing_epoch.next();
+        assert!(
+            completed_voting_power::<_, _, GovStore<_>>(
+                &state,
+                activation_epoch
+            )
+            .expect("Test failed")
+            .is_none()
+        );
+
+        aggregate_votes::<_, _, GovStore<_>>(
+            &mut state,
+            test_utils::build_valset_upd_digest(
+                &[(
+                    address::testing::established_address_1(),
+                    &keys
+                        .get(&address::testing::established_address_1())
+                        .expect("Test failed")
+                        .eth_bridge,
+                )],
+                VotingPowersMap::new(),
+                signing_epoch,
+            ),
+            signing_epoch,
+            SigVerification::Disabled,
+        )
+        .expect("Test failed");
+
+        let voting_power = completed_voting_power::<_, _, GovStore<_>>(
+            &state,
+            activation_epoch,
+        )
+        .expect("Test failed")
+        .expect("Test failed");
+        assert!(voting_power > FractionalVotingPower::TWO_THIRDS);
+    }
+
+    /// Test that aggregating the same validator's vote twice does not
+    /// add a second signature to the proof.
+    #[test]
+    fn test_duplicate_vote_single_signature() {
+        let (mut state, keys) =
+            test_utils::setup_storage_with_validators(HashMap::from_iter([
+                (
+                    address::testing::established_address_1(),
+                    Amount::native_whole(50_000),
+                ),
+                (
+                    address::testing::established_address_2(),
+                    Amount::native_whole(50_000),
+                ),
+            ]));
+
+        let last_height = state.in_mem().get_last_block_height();
+        let signing_epoch = test_utils::epoch_of_height(&state, last_height);
+        let validator = address::testing::established_address_1();
+        let eth_bridge_key =
+            &keys.get(&validator).expect("Test failed").eth_bridge;
+
+        for _ in 0..2 {
+            aggregate_votes::<_, _, GovStore<_>>(
+                &mut state,
+                test_utils::build_valset_upd_digest(
+                    &[(validator.clone(), eth_bridge_key)],
+                    VotingPowersMap::new(),
+                    signing_epoch,
+                ),
+                signing_epoch,
+                SigVerification::Disabled,
+            )
+            .expect("Test failed");
+        }
+
+        let (proof, complete) =
+            read_valset_upd_proof(&state, signing_epoch.next())
+                .expect("Test failed")
+                .expect("Test failed");
+        assert!(!complete);
+        assert_eq!(proof.signatures.len(), 1);
+    }
+
+    /// Test that appending signatures to a partially signed proof
+    /// already in storage transitions its tally to `seen`.
+    #[test]
+    fn test_append_sigs_to_partial_proof() {
+        let validator_1 = address::testing::established_address_1();
+        let validator_2 = address::testing::established_address_2();
+        let (mut state, keys) =
+            test_utils::setup_storage_with_validators(HashMap::from_iter([
+                (validator_1.clone(), Amount::native_whole(40_000)),
+                (validator_2.clone(), Amount::native_whole(40_000)),
+                (
+                    address::testing::established_address_3(),
+                    Amount::native_whole(20_000),
+                ),
+            ]));
+
+        let last_height = state.in_mem().get_last_block_height();
+        let signing_epoch = test_utils::epoch_of_height(&state, last_height);
+        let valset_upd_keys = test_utils::write_partial_valset_upd_proof(
+            &mut state,
+            signing_epoch,
+            &[validator_1],
+            &keys,
+            VotingPowersMap::new(),
+        );
+        assert_eq!(
+            valset_upd_keys.prefix,
+            vote_tallies::Keys::from(&signing_epoch.next()).prefix
+        );
+
+        let (proof, complete) =
+            read_valset_upd_proof(&state, signing_epoch.next())
+                .expect("Test failed")
+                .expect("Test failed");
+        assert!(!complete);
+        assert_eq!(proof.signatures.len(), 1);
+
+        let tx_result = aggregate_votes::<_, _, GovStore<_>>(
+            &mut state,
+            test_utils::build_valset_upd_digest(
+                &[(
+                    validator_2.clone(),
+                    &keys.get(&validator_2).expect("Test failed").eth_bridge,
+                )],
+                VotingPowersMap::new(),
+                signing_epoch,
+            ),
+            signing_epoch,
+            SigVerification::Disabled,
+        )
+        .expect("Test failed")
+        .into_tx_result();
+        assert!(tx_result.changed_keys.contains(&valset_upd_keys.seen()));
+
+        let (proof, complete) =
+            read_valset_upd_proof(&state, signing_epoch.next())
+                .expect("Test failed")
+                .expect("Test failed");
+        assert!(complete);
+        assert_eq!(proof.signatures.len(), 2);
+    }
+
+    /// Test that when signature verification is enabled, signatures
+    /// produced over an old version of the validator set update
+    /// message are rejected.
+    #[test]
+    fn test_reject_sigs_over_old_message_version() {
+        let (mut state, keys) = test_utils::setup_default_storage();
+
+        let last_height = state.in_mem().get_last_block_height();
+        let signing_epoch = test_utils::epoch_of_height(&state, last_height);
+        let validator = address::testing::established_address_1();
+        let eth_bridge_key =
+            &keys.get(&validator).expect("Test failed").eth_bridge;
+        let ext = validator_set_update::Vext {
+            voting_powers: VotingPowersMap::new(),
+            validator_addr: validator,
+            signing_epoch,
+        };
+
+        let old_version = 0;
+        assert_ne!(old_version, VALSET_UPD_MESSAGE_VERSION);
+        let tx_result = aggregate_votes::<_, _, GovStore<_>>(
+            &mut state,
+            validator_set_update::VextDigest::singleton(
+                ext.sign_with_version(eth_bridge_key, old_version),
+            ),
+            signing_epoch,
+            SigVerification::Enabled,
+        )
+        .expect("Test failed")
+        .into_tx_result();
+        assert!(tx_result.changed_keys.is_empty());
+        assert!(
+            read_valset_upd_proof(&state, signing_epoch.next())
+                .expect("Test failed")
+                .is_none()
+        );
+
+        let tx_result = aggregate_votes::<_, _, GovStore<_>>(
+            &mut state,
+            validator_set_update::VextDigest::singleton(
+                ext.sign_with_version(
+                    eth_bridge_key,
+                    VALSET_UPD_MESSAGE_VERSION,
+                ),
+            ),
+            signing_epoch,
+            SigVerification::Enabled,
+        )
+        .expect("Test failed")
+        .into_tx_result();
+        assert!(!tx_result.changed_keys.is_empty());
+    }
+
+    /// Test that applying a vote which makes a validator set update
+    /// "seen" reports every tally key category as changed.
+    #[test]
+    fn test_apply_update_categorizes_newly_seen_tally() {
+        let (mut state, keys) = test_utils::setup_default_storage();
+
+        let last_height = state.in_mem().get_last_block_height();
+        let signing_epoch = test_utils::epoch_of_height(&state, last_height);
+        let vote_height = state
+            .ethbridge_queries()
+            .get_valset_upd_vote_height(signing_epoch)
+            .expect("Test failed")
+            .expect("Test failed");
+
+        let (changed, categories) = apply_update::<_, _, GovStore<_>>(
+            &mut state,
+            test_utils::build_valset_upd_digest(
+                &[(
+                    address::testing::established_address_1(),
+                    &keys
+                        .get(&address::testing::established_address_1())
+                        .expect("Test failed")
+                        .eth_bridge,
+                )],
+                VotingPowersMap::new(),
+                signing_epoch,
+            ),
+            signing_epoch,
+            vote_height,
+            None,
+        )
+        .expect("Test failed");
+
+        assert!(!changed.is_empty());
+        assert_eq!(
+            categories,
+            ChangedTallyKeys {
+                proof_body_changed: true,
+                tally_seen_changed: true,
+                seen_by_changed: true,
+            }
+        );
+        assert_eq!(
+            categories,
+            ChangedTallyKeys::categorize(
+                &vote_tallies::Keys::from(&signing_epoch.next()),
+                &changed,
+            )
+        );
+    }
+
+    /// Test the outcomes of aggregating votes on a validator set update
+    /// which is completed, then voted on again.
+    #[test]
+    fn test_aggregation_outcomes() {
+        let (validator_1, validator_2, validator_3) = (
+            address::testing::established_address_1(),
+            address::testing::established_address_2(),
+            address::testing::established_address_3(),
+        );
+        let (mut state, keys) =
+            test_utils::setup_storage_with_validators(HashMap::from_iter([
+                (validator_1.clone(), Amount::native_whole(100)),
+                (validator_2.clone(), Amount::native_whole(100)),
+                (validator_3.clone(), Amount::native_whole(100)),
+            ]));
+        let last_height = state.in_mem().get_last_block_height();
+        let signing_epoch = test_utils::epoch_of_height(&state, last_height);
+        let digest = |validators: &[&Address]| {
+            let signers: Vec<_> = validators
+                .iter()
+                .map(|&validator| {
+                    (validator.clone(), &keys[validator].eth_bridge)
+                })
+                .collect();
+            test_utils::build_valset_upd_digest(
+                &signers,
+                VotingPowersMap::new(),
+                signing_epoch,
+            )
+        };
+        let mut aggregate = |validators: &[&Address]| {
+            aggregate_votes::<_, _, GovStore<_>>(
+                &mut state,
+                digest(validators),
+                signing_epoch,
+                SigVerification::Disabled,
+            )
+            .expect("Test failed")
+        };
+
+        // a third of the voting power is not enough to see the update
+        let outcome = aggregate(&[&validator_1]);
+        assert!(matches!(
+            &outcome,
+            AggregationOutcome::Updated(tx_result)
+                if !tx_result.changed_keys.is_empty()
+        ));
+
+        // a duplicate vote leaves the tally untouched
+        let outcome = aggregate(&[&validator_1]);
+        assert!(matches!(outcome, AggregationOutcome::NoNewVotes));
+        assert!(outcome.into_tx_result().changed_keys.is_empty());
+
+        // the votes of the whole set complete the proof
+        let outcome = aggregate(&[&validator_2, &validator_3]);
+        assert!(matches!(
+            &outcome,
+            AggregationOutcome::Completed(tx_result)
+                if !tx_result.changed_keys.is_empty()
+        ));
+
+        // further votes are ignored
+        let outcome = aggregate(&[&validator_1]);
+        assert!(matches!(outcome, AggregationOutcome::AlreadySeen));
+        assert!(outcome.into_tx_result().changed_keys.is_empty());
+    }
+
+    /// Test that aggregating the vote of a validator whose Ethereum hot
+    /// key is not a secp256k1 key fails gracefully, rather than panicking.
+    #[test]
+    fn test_signer_without_eth_keys() {
+        use namada_core::key::RefTo;
+        use namada_proof_of_stake::storage::validator_eth_hot_key_handle;
+
+        let (mut state, keys) = test_utils::setup_default_storage();
+        let validator = address::testing::established_address_1();
+
+        let last_height = state.in_mem().get_last_block_height();
+        let signing_epoch = test_utils::epoch_of_height(&state, last_height);
+        let ed25519_key = test_utils::TestValidatorKeys::generate_with(
+            test_utils::KeySchemes {
+                eth_bridge: key::SchemeType::Ed25519,
+                ..Default::default()
+            },
+        )
+        .eth_bridge
+        .ref_to();
+        validator_eth_hot_key_handle(&validator)
+            .set::<_, GovStore<_>>(&mut state, ed25519_key, signing_epoch, 0)
+            .expect("Test failed");
+
+        let err = aggregate_votes::<_, _, GovStore<_>>(
+            &mut state,
+            test_utils::build_valset_upd_digest(
+                &[(validator.clone(), &keys[&validator].eth_bridge)],
+                VotingPowersMap::new(),
+                signing_epoch,
+            ),
+            signing_epoch,
+            SigVerification::Disabled,
+        )
+        .expect_err("Test failed");
+        assert!(err.to_string().contains("no secp256k1 Ethereum keys"));
+        assert!(
+            read_valset_upd_proof(&state, signing_epoch.next())
+                .expect("Test failed")
+                .is_none()
+        );
+    }
 }
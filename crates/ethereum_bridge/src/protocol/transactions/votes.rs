@@ -18,8 +18,9 @@ use namada_state::{DB, DBIter, StorageHasher, StorageRead, WlState};
 use namada_systems::governance;
 
 use super::{ChangedKeys, read};
+use crate::storage::vote_tallies;
 
-pub(super) mod storage;
+pub(crate) mod storage;
 pub(super) mod update;
 
 /// The addresses of validators that voted for something, and the block
@@ -50,8 +51,8 @@ pub trait EpochedVotingPowerExt {
         Gov: governance::Read<WlState<D, H>>;
 
     /// Fetch the sum of the stake tallied on an
-    /// [`EpochedVotingPower`].
-    fn tallied_stake(&self) -> token::Amount;
+    /// [`EpochedVotingPower`], or [`None`] if it overflows.
+    fn checked_tallied_stake(&self) -> Option<token::Amount>;
 
     /// Fetch the sum of the stake tallied on an
     /// [`EpochedVotingPower`], as a fraction over
@@ -71,8 +72,13 @@ pub trait EpochedVotingPowerExt {
         else {
             return FractionalVotingPower::NULL;
         };
+        // NB: a tally whose stake overflows has necessarily
+        // accumulated more than the maximum voting power
+        let Some(tallied_stake) = self.checked_tallied_stake() else {
+            return FractionalVotingPower::WHOLE;
+        };
         FractionalVotingPower::new(
-            self.tallied_stake().into(),
+            tallied_stake.into(),
             max_voting_power.into(),
         )
         .unwrap()
@@ -81,17 +87,15 @@ pub trait EpochedVotingPowerExt {
     /// Check if the [`Tally`] associated with an [`EpochedVotingPower`]
     /// can be considered `seen`.
     #[inline]
-    fn has_majority_quorum<D, H, Gov>(&self, state: &WlState<D, H>) -> bool
+    fn has_majority_quorum<D, H, Gov>(
+        &self,
+        state: &WlState<D, H>,
+    ) -> Result<bool>
     where
         D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
         H: 'static + StorageHasher + Sync,
         Gov: governance::Read<WlState<D, H>>,
     {
-        let Some(max_voting_power) =
-            self.epoch_max_voting_power::<_, _, Gov>(state)
-        else {
-            return false;
-        };
         // NB: Preserve the safety property of the Tendermint protocol across
         // all the epochs we vote on.
         //
@@ -102,10 +106,45 @@ pub trait EpochedVotingPowerExt {
         // arbitrarily faulty nodes. Therefore, we can consider a tally secure
         // if has accumulated an amount of stake greater than the threshold
         // stake of S_max - F = 2/3 S_max.
-        let threshold = FractionalVotingPower::TWO_THIRDS
+        self.has_quorum::<D, H, Gov>(state, FractionalVotingPower::TWO_THIRDS)
+    }
+
+    /// Check if the [`Tally`] associated with an [`EpochedVotingPower`]
+    /// has accumulated strictly more than `threshold` of the maximum
+    /// stake seen in the epochs voted on.
+    ///
+    /// Fails rather than panicking if the tallied stake overflows. Since
+    /// stake is accumulated in a [`token::Amount`], any number of
+    /// validators is supported, as long as the sum of their stake over
+    /// the epochs voted on does not exceed [`token::Amount::max`].
+    #[inline]
+    fn has_quorum<D, H, Gov>(
+        &self,
+        state: &WlState<D, H>,
+        threshold: FractionalVotingPower,
+    ) -> Result<bool>
+    where
+        D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
+        H: 'static + StorageHasher + Sync,
+        Gov: governance::Read<WlState<D, H>>,
+    {
+        let Some(max_voting_power) =
+            self.epoch_max_voting_power::<_, _, Gov>(state)
+        else {
+            return Ok(false);
+        };
+        let threshold = threshold
             .checked_mul_amount(max_voting_power)
-            .expect("Cannot overflow");
-        self.tallied_stake() > threshold
+            .ok_or_else(|| {
+                eyre!(
+                    "Quorum threshold overflow over a maximum voting power of \
+                     {max_voting_power}"
+                )
+            })?;
+        let tallied_stake = self
+            .checked_tallied_stake()
+            .ok_or_else(|| eyre!("Tallied voting power overflow"))?;
+        Ok(tallied_stake > threshold)
     }
 }
 
@@ -125,9 +164,8 @@ impl EpochedVotingPowerExt for EpochedVotingPower {
             .max()
     }
 
-    fn tallied_stake(&self) -> token::Amount {
+    fn checked_tallied_stake(&self) -> Option<token::Amount> {
         token::Amount::sum(self.values().copied())
-            .expect("Talling stake shouldn't overflow")
     }
 }
 
@@ -156,13 +194,107 @@ pub struct Tally {
     pub seen: bool,
 }
 
+impl Tally {
+    /// Compute the progress made by this [`Tally`] since an `earlier`
+    /// snapshot of the tally of the same piece of data.
+    pub fn diff(&self, earlier: &Tally) -> TallyDelta {
+        let new_voters = self
+            .seen_by
+            .iter()
+            .filter(|(validator, _)| !earlier.seen_by.contains_key(*validator))
+            .map(|(validator, height)| (validator.clone(), *height))
+            .collect();
+        let added_power = self
+            .voting_power
+            .iter()
+            .filter_map(|(epoch, power)| {
+                let earlier_power = earlier
+                    .voting_power
+                    .get(epoch)
+                    .copied()
+                    .unwrap_or_default();
+                let added = power.checked_sub(earlier_power)?;
+                (!added.is_zero()).then_some((*epoch, added))
+            })
+            .collect();
+        TallyDelta {
+            new_voters,
+            added_power,
+            newly_seen: self.seen && !earlier.seen,
+        }
+    }
+}
+
+/// The difference between two snapshots of a [`Tally`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TallyDelta {
+    /// The votes cast since the earlier snapshot.
+    pub new_voters: Votes,
+    /// The voting power added in each epoch since the earlier snapshot.
+    pub added_power: EpochedVotingPower,
+    /// Whether the tallied data became `seen` since the earlier snapshot.
+    pub newly_seen: bool,
+}
+
+/// The keys of a [`Tally`] which changed while applying some votes to it,
+/// grouped by the data they hold.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ChangedTallyKeys {
+    /// Whether the body of the tallied data changed, e.g. because new
+    /// signatures were attached to a proof.
+    pub proof_body_changed: bool,
+    /// Whether the `seen` flag of the tally changed, i.e. whether the
+    /// tallied data became `seen`.
+    pub tally_seen_changed: bool,
+    /// Whether the validators that voted for the tallied data changed.
+    pub seen_by_changed: bool,
+}
+
+impl ChangedTallyKeys {
+    /// Group the `changed` keys which belong to the tally with the given
+    /// `keys`. Keys of other tallies are ignored.
+    pub fn categorize<T>(
+        keys: &vote_tallies::Keys<T>,
+        changed: &ChangedKeys,
+    ) -> Self {
+        Self {
+            proof_body_changed: changed.contains(&keys.body()),
+            tally_seen_changed: changed.contains(&keys.seen()),
+            seen_by_changed: changed.contains(&keys.seen_by()),
+        }
+    }
+}
+
 /// Calculate a new [`Tally`] based on some validators' fractional voting powers
 /// as specific block heights
+#[inline]
 pub fn calculate_new<D, H, Gov>(
     state: &WlState<D, H>,
     seen_by: Votes,
     voting_powers: &HashMap<(Address, BlockHeight), token::Amount>,
 ) -> Result<Tally>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
+    H: 'static + StorageHasher + Sync,
+    Gov: governance::Read<WlState<D, H>>,
+{
+    calculate_new_with_threshold::<D, H, Gov>(
+        state,
+        seen_by,
+        voting_powers,
+        FractionalVotingPower::TWO_THIRDS,
+    )
+}
+
+/// Like [`calculate_new`], but the resulting [`Tally`] is only considered
+/// `seen` once it has accumulated strictly more than `threshold` of the
+/// voting power.
+pub fn calculate_new_with_threshold<D, H, Gov>(
+    state: &WlState<D, H>,
+    seen_by: Votes,
+    voting_powers: &HashMap<(Address, BlockHeight), token::Amount>,
+    threshold: FractionalVotingPower,
+) -> Result<Tally>
 where
     D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
     H: 'static + StorageHasher + Sync,
@@ -195,7 +327,7 @@ where
     }
 
     let newly_confirmed =
-        seen_by_voting_power.has_majority_quorum::<D, H, Gov>(state);
+        seen_by_voting_power.has_quorum::<D, H, Gov>(state, threshold)?;
     Ok(Tally {
         voting_power: seen_by_voting_power,
         seen_by,
@@ -223,6 +355,53 @@ mod tests {
     use super::*;
     use crate::test_utils::{self, GovStore};
 
+    /// Test that diffing two snapshots of a [`Tally`] yields the votes
+    /// and voting power added in between.
+    #[test]
+    fn test_tally_diff() {
+        let validator_1 = address::testing::established_address_1();
+        let validator_2 = address::testing::established_address_2();
+        let earlier = Tally {
+            voting_power: EpochedVotingPower::from([(
+                0.into(),
+                token::Amount::from(100u64),
+            )]),
+            seen_by: Votes::from([(validator_1.clone(), BlockHeight(10))]),
+            seen: false,
+        };
+        let later = Tally {
+            voting_power: EpochedVotingPower::from([
+                (0.into(), token::Amount::from(100u64)),
+                (1.into(), token::Amount::from(250u64)),
+            ]),
+            seen_by: Votes::from([
+                (validator_1, BlockHeight(10)),
+                (validator_2.clone(), BlockHeight(20)),
+            ]),
+            seen: true,
+        };
+
+        assert_eq!(
+            later.diff(&earlier),
+            TallyDelta {
+                new_voters: Votes::from([(validator_2, BlockHeight(20))]),
+                added_power: EpochedVotingPower::from([(
+                    1.into(),
+                    token::Amount::from(250u64),
+                )]),
+                newly_seen: true,
+            }
+        );
+        assert_eq!(
+            later.diff(&later),
+            TallyDelta {
+                new_voters: Votes::new(),
+                added_power: EpochedVotingPower::new(),
+                newly_seen: false,
+            }
+        );
+    }
+
     #[test]
     fn test_dedupe_empty() {
         let signers = BTreeSet::new();
@@ -326,6 +505,150 @@ mod tests {
         );
     }
 
+    /// Test that the threshold a tally must exceed to be `seen` can
+    /// be configured.
+    #[test]
+    fn test_calculate_new_with_threshold() {
+        let stake = token::Amount::native_whole(100);
+        let validators = [
+            address::testing::established_address_1(),
+            address::testing::established_address_2(),
+            address::testing::established_address_3(),
+            address::testing::established_address_4(),
+        ];
+        let (state, _) = test_utils::setup_storage_with_validators(
+            validators.iter().cloned().map(|v| (v, stake)).collect(),
+        );
+
+        // three out of four validators vote, i.e. 3/4 of the stake
+        let vote_height = BlockHeight(100);
+        let seen_by: Votes = validators
+            .iter()
+            .take(3)
+            .cloned()
+            .map(|v| (v, vote_height))
+            .collect();
+        let voting_powers: HashMap<_, _> = seen_by
+            .iter()
+            .map(|(v, h)| ((v.clone(), *h), stake))
+            .collect();
+
+        let tally = calculate_new::<_, _, GovStore<_>>(
+            &state,
+            seen_by.clone(),
+            &voting_powers,
+        )
+        .unwrap();
+        assert!(tally.seen);
+
+        let tally = calculate_new_with_threshold::<_, _, GovStore<_>>(
+            &state,
+            seen_by,
+            &voting_powers,
+            FractionalVotingPower::new_u64(3, 4).unwrap(),
+        )
+        .unwrap();
+        assert!(!tally.seen);
+        assert_eq!(
+            tally
+                .voting_power
+                .fractional_stake::<_, _, GovStore<_>>(&state),
+            FractionalVotingPower::new_u64(3, 4).unwrap()
+        );
+    }
+
+    /// Test that overflowing the tallied voting power is reported
+    /// as an error, rather than panicking.
+    #[test]
+    fn test_has_quorum_tallied_stake_overflow() {
+        let (state, _) = test_utils::setup_default_storage();
+
+        let aggregated = EpochedVotingPower::from([
+            (0.into(), token::Amount::max()),
+            (1.into(), token::Amount::max()),
+        ]);
+        assert_eq!(aggregated.checked_tallied_stake(), None);
+        assert_eq!(
+            aggregated.fractional_stake::<_, _, GovStore<_>>(&state),
+            FractionalVotingPower::WHOLE
+        );
+        let error = aggregated
+            .has_majority_quorum::<_, _, GovStore<_>>(&state)
+            .expect_err("Test failed");
+        assert_eq!(error.to_string(), "Tallied voting power overflow");
+    }
+
+    /// Test that a tally of many validators with varying stake becomes
+    /// `seen` exactly once it accumulates more than 2/3 of the stake.
+    #[test]
+    #[allow(clippy::arithmetic_side_effects)]
+    fn test_calculate_new_many_validators() {
+        // the default maximum number of validator slots
+        const NUM_VALIDATORS: u64 = 100;
+
+        let validators: Vec<_> = (0..NUM_VALIDATORS)
+            .map(|i| {
+                (
+                    address::gen_deterministic_established_address(format!(
+                        "validator {i}"
+                    )),
+                    token::Amount::native_whole(i % 7 + 1),
+                )
+            })
+            .collect();
+        let (state, _) = test_utils::setup_storage_with_validators(
+            validators.iter().cloned().collect(),
+        );
+        let total_stake: token::Amount =
+            validators.iter().map(|(_, stake)| *stake).sum();
+        assert_eq!(
+            get_total_voting_power::<_, GovStore<_>>(&state, 0.into()),
+            total_stake,
+        );
+
+        let vote_height = BlockHeight(100);
+        let voting_powers: HashMap<_, _> = validators
+            .iter()
+            .map(|(validator, stake)| {
+                ((validator.clone(), vote_height), *stake)
+            })
+            .collect();
+
+        // the number of voters after which the tally should be seen
+        let mut cumulative_stake = token::Amount::zero();
+        let quorum = validators
+            .iter()
+            .position(|(_, stake)| {
+                cumulative_stake += *stake;
+                FractionalVotingPower::TWO_THIRDS
+                    .checked_mul_amount(total_stake)
+                    .expect("Test failed")
+                    < cumulative_stake
+            })
+            .expect("Test failed")
+            + 1;
+
+        let tally_of = |num_voters: usize| {
+            let seen_by: Votes = validators
+                .iter()
+                .take(num_voters)
+                .map(|(validator, _)| (validator.clone(), vote_height))
+                .collect();
+            calculate_new::<_, _, GovStore<_>>(&state, seen_by, &voting_powers)
+                .expect("Test failed")
+        };
+        assert!(!tally_of(quorum - 1).seen);
+        assert!(tally_of(quorum).seen);
+        let tally = tally_of(validators.len());
+        assert!(tally.seen);
+        assert_eq!(
+            tally
+                .voting_power
+                .fractional_stake::<_, _, GovStore<_>>(&state),
+            FractionalVotingPower::WHOLE
+        );
+    }
+
     /// Test that voting on a tally across epoch boundaries accounts
     /// for the maximum voting power attained along those epochs.
     #[test]
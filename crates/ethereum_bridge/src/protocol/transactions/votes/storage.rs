@@ -118,6 +118,19 @@ where
     super::read::value(state, &keys.body())
 }
 
+#[inline]
+pub fn maybe_read_body<D, H, T>(
+    state: &WlState<D, H>,
+    keys: &vote_tallies::Keys<T>,
+) -> Result<Option<T>>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
+    H: 'static + StorageHasher + Sync,
+    T: BorshDeserialize,
+{
+    super::read::maybe_value(state, &keys.body())
+}
+
 #[inline]
 pub fn maybe_read_seen<D, H, T>(
     state: &WlState<D, H>,
@@ -131,6 +144,19 @@ where
     super::read::maybe_value(state, &keys.seen())
 }
 
+#[inline]
+pub fn maybe_read_seen_by<D, H, T>(
+    state: &WlState<D, H>,
+    keys: &vote_tallies::Keys<T>,
+) -> Result<Option<Votes>>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
+    H: 'static + StorageHasher + Sync,
+    T: BorshDeserialize,
+{
+    super::read::maybe_value(state, &keys.seen_by())
+}
+
 #[cfg(test)]
 mod tests {
     use std::collections::BTreeMap;
@@ -6,10 +6,13 @@ use namada_core::address::Address;
 use namada_core::chain::BlockHeight;
 use namada_core::collections::{HashMap, HashSet};
 use namada_core::token;
+use namada_core::voting_power::FractionalVotingPower;
 use namada_state::{DB, DBIter, StorageHasher, StorageRead, WlState};
 use namada_systems::governance;
 
-use super::{ChangedKeys, EpochedVotingPowerExt, Tally, Votes};
+use super::{
+    ChangedKeys, EpochedVotingPower, EpochedVotingPowerExt, Tally, Votes,
+};
 use crate::storage::vote_tallies;
 
 /// Wraps all the information about new votes to be applied to some existing
@@ -50,6 +53,36 @@ impl NewVotes {
         self.inner.keys().cloned().collect()
     }
 
+    /// Returns the fraction of voting power held by the voters in this
+    /// [`NewVotes`], over the maximum stake seen in the epochs they
+    /// voted on.
+    pub fn added_voting_power<D, H, Gov>(
+        &self,
+        state: &WlState<D, H>,
+    ) -> Result<FractionalVotingPower>
+    where
+        D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
+        H: 'static + StorageHasher + Sync,
+        Gov: governance::Read<WlState<D, H>>,
+    {
+        let mut voting_power = EpochedVotingPower::new();
+        for (vote_height, stake) in self.inner.values() {
+            let epoch =
+                state.get_epoch_at_height(*vote_height)?.ok_or_else(|| {
+                    eyre!(
+                        "The epoch of block height {vote_height} is not known"
+                    )
+                })?;
+            let aggregated = voting_power
+                .entry(epoch)
+                .or_insert_with(token::Amount::zero);
+            *aggregated = aggregated
+                .checked_add(*stake)
+                .ok_or_else(|| eyre!("Aggregated voting power overflow"))?;
+        }
+        Ok(voting_power.fractional_stake::<D, H, Gov>(state))
+    }
+
     /// Consumes `self` and returns a [`NewVotes`] with any addresses from
     /// `voters` removed, as well as the set of addresses that were actually
     /// removed. Useful for removing voters who have already voted for
@@ -90,10 +123,34 @@ impl IntoIterator for NewVotes {
 /// would change. If [`Tally`] is already `seen = true` in storage, then no
 /// votes from `vote_info` should be applied, and the returned changed keys will
 /// be empty.
+#[inline]
 pub(in super::super) fn calculate<D, H, Gov, T>(
-    state: &mut WlState<D, H>,
+    state: &WlState<D, H>,
+    keys: &vote_tallies::Keys<T>,
+    vote_info: NewVotes,
+) -> Result<(Tally, ChangedKeys)>
+where
+    D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
+    H: 'static + StorageHasher + Sync,
+    Gov: governance::Read<WlState<D, H>>,
+    T: BorshDeserialize,
+{
+    calculate_with_threshold::<D, H, Gov, T>(
+        state,
+        keys,
+        vote_info,
+        FractionalVotingPower::TWO_THIRDS,
+    )
+}
+
+/// Like [`calculate`], but the updated [`Tally`] is only considered `seen`
+/// once it has accumulated strictly more than `threshold` of the voting
+/// power.
+pub(in super::super) fn calculate_with_threshold<D, H, Gov, T>(
+    state: &WlState<D, H>,
     keys: &vote_tallies::Keys<T>,
     vote_info: NewVotes,
+    threshold: FractionalVotingPower,
 ) -> Result<(Tally, ChangedKeys)>
 where
     D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
@@ -120,8 +177,17 @@ where
             "Ignoring duplicate voter"
         );
     }
-    let tally_post = apply::<D, H, Gov>(state, &tally_pre, vote_info)
-        .expect("We deduplicated voters already, so this should never error");
+    let added_voting_power =
+        vote_info.added_voting_power::<D, H, Gov>(state)?;
+    tracing::debug!(
+        ?keys.prefix,
+        %added_voting_power,
+        "Applying new votes to tally"
+    );
+    // NB: we deduplicated voters already, so this can only fail if the
+    // aggregated voting power overflows
+    let tally_post =
+        apply::<D, H, Gov>(state, &tally_pre, vote_info, threshold)?;
 
     let changed_keys = keys_changed(keys, &tally_pre, &tally_post);
 
@@ -152,6 +218,7 @@ fn apply<D, H, Gov>(
     state: &WlState<D, H>,
     tally: &Tally,
     vote_info: NewVotes,
+    threshold: FractionalVotingPower,
 ) -> Result<Tally>
 where
     D: 'static + DB + for<'iter> DBIter<'iter> + Sync,
@@ -182,7 +249,8 @@ where
             .ok_or_else(|| eyre!("Aggregated voting power overflow"))?;
     }
 
-    let seen_post = voting_power_post.has_majority_quorum::<D, H, Gov>(state);
+    let seen_post =
+        voting_power_post.has_quorum::<D, H, Gov>(state, threshold)?;
 
     Ok(Tally {
         voting_power: voting_power_post,
@@ -222,7 +290,7 @@ mod tests {
 
     use self::helpers::{TallyParams, default_event, default_total_stake};
     use super::*;
-    use crate::protocol::transactions::votes::{self, EpochedVotingPower};
+    use crate::protocol::transactions::votes;
     use crate::test_utils::{self, GovStore};
 
     mod helpers {
@@ -381,6 +449,43 @@ mod tests {
         Ok(())
     }
 
+    /// Test that the voting power added by new votes only accounts for
+    /// voters who had not voted yet.
+    #[test]
+    fn test_vote_info_added_voting_power() -> Result<()> {
+        let stake = token::Amount::native_whole(100);
+        let validators = [
+            address::testing::established_address_1(),
+            address::testing::established_address_2(),
+            address::testing::established_address_3(),
+        ];
+        let (state, _) = test_utils::setup_storage_with_validators(
+            validators.iter().cloned().map(|v| (v, stake)).collect(),
+        );
+
+        let vote_height = BlockHeight(100);
+        let votes: Votes = validators
+            .iter()
+            .cloned()
+            .map(|v| (v, vote_height))
+            .collect();
+        let voting_powers = votes
+            .iter()
+            .map(|(v, h)| ((v.clone(), *h), stake))
+            .collect();
+        let vote_info = NewVotes::new(votes, &voting_powers)?;
+
+        // the first validator had already voted
+        let (vote_info, removed) = vote_info.without_voters(&validators[..1]);
+        assert_eq!(removed.len(), 1);
+
+        assert_eq!(
+            vote_info.added_voting_power::<_, _, GovStore<_>>(&state)?,
+            FractionalVotingPower::new_u64(2, 3)?
+        );
+        Ok(())
+    }
+
     #[test]
     fn test_apply_duplicate_votes() -> Result<()> {
         let mut state = TestState::default();
@@ -409,7 +514,12 @@ mod tests {
         )]);
         let vote_info = NewVotes::new(votes, &voting_powers)?;
 
-        let result = apply::<_, _, GovStore<_>>(&state, &tally_pre, vote_info);
+        let result = apply::<_, _, GovStore<_>>(
+            &state,
+            &tally_pre,
+            vote_info,
+            FractionalVotingPower::TWO_THIRDS,
+        );
 
         assert!(result.is_err());
         Ok(())
@@ -446,7 +556,7 @@ mod tests {
         let vote_info = NewVotes::new(votes, &voting_powers)?;
 
         let (tally_post, changed_keys) =
-            calculate::<_, _, GovStore<_>, _>(&mut state, &keys, vote_info)?;
+            calculate::<_, _, GovStore<_>, _>(&state, &keys, vote_info)?;
 
         assert_eq!(tally_post, tally_pre);
         assert!(changed_keys.is_empty());
@@ -473,7 +583,7 @@ mod tests {
         let vote_info = NewVotes::new(Votes::default(), &HashMap::default())?;
 
         let (tally_post, changed_keys) =
-            calculate::<_, _, GovStore<_>, _>(&mut state, &keys, vote_info)?;
+            calculate::<_, _, GovStore<_>, _>(&state, &keys, vote_info)?;
 
         assert_eq!(tally_post, tally_pre);
         assert!(changed_keys.is_empty());
@@ -510,7 +620,7 @@ mod tests {
         let vote_info = NewVotes::new(votes, &voting_powers)?;
 
         let (tally_post, changed_keys) =
-            calculate::<_, _, GovStore<_>, _>(&mut state, &keys, vote_info)?;
+            calculate::<_, _, GovStore<_>, _>(&state, &keys, vote_info)?;
 
         assert_eq!(
             tally_post,
@@ -566,7 +676,7 @@ mod tests {
         let vote_info = NewVotes::new(votes, &voting_powers)?;
 
         let (tally_post, changed_keys) =
-            calculate::<_, _, GovStore<_>, _>(&mut state, &keys, vote_info)?;
+            calculate::<_, _, GovStore<_>, _>(&state, &keys, vote_info)?;
 
         assert_eq!(
             tally_post,
@@ -1,7 +1,9 @@
 //! Storage queries for ethereum bridge.
 
+use std::collections::BTreeMap;
+
 use borsh::{BorshDeserialize, BorshSerialize};
-use namada_core::address::Address;
+use namada_core::address::{Address, InternalAddress};
 use namada_core::chain::{BlockHeight, Epoch};
 use namada_core::eth_abi::Encode;
 use namada_core::eth_bridge_pool::PendingTransfer;
@@ -15,6 +17,7 @@ use namada_core::{hints, token};
 use namada_macros::BorshDeserializer;
 #[cfg(feature = "migrations")]
 use namada_migrations::*;
+use namada_parameters::read_epoch_duration_parameter;
 use namada_proof_of_stake::queries::get_total_voting_power;
 use namada_proof_of_stake::storage::{
     read_consensus_validator_set_addresses_with_stake, read_pos_params,
@@ -27,8 +30,11 @@ use namada_vote_ext::validator_set_update::{
     EthAddrBook, ValidatorSetArgs, VotingPowersMap, VotingPowersMapExt,
 };
 
+use crate::storage::parameters::MinimumConfirmations;
 use crate::storage::proof::BridgePoolRootProof;
-use crate::storage::{active_key, bridge_pool, vote_tallies, whitelist};
+use crate::storage::{
+    active_key, bridge_pool, min_confirmations_key, vote_tallies, whitelist,
+};
 
 /// Check if the Ethereum Bridge has been enabled at compile time.
 pub const fn is_bridge_comptime_enabled() -> bool {
@@ -184,6 +190,50 @@ where
             .unwrap_or(false)
     }
 
+    /// Get the number of blocks after the first block of an epoch at
+    /// which validator set update votes are cast. Defaults to 1, i.e.
+    /// votes are cast at the 2nd block height of the signing epoch.
+    ///
+    /// A configured offset must be less than the minimum number of
+    /// blocks per epoch, otherwise epochs could end before votes are
+    /// ever cast.
+    pub fn get_valset_upd_vote_height_offset(
+        self,
+    ) -> namada_storage::Result<u64> {
+        let Some(offset) = self
+            .state
+            .read(&crate::storage::valset_upd_vote_height_offset_key())?
+        else {
+            return Ok(1);
+        };
+        let min_num_of_blocks =
+            read_epoch_duration_parameter(self.state)?.min_num_of_blocks;
+        if offset >= min_num_of_blocks {
+            return Err(namada_storage::Error::new_alloc(format!(
+                "The validator set update vote height offset {offset} must be \
+                 less than the minimum number of blocks per epoch \
+                 {min_num_of_blocks}"
+            )));
+        }
+        Ok(offset)
+    }
+
+    /// Get the block height at which validator set update votes of
+    /// `signing_epoch` are cast, if the start of the epoch is known.
+    pub fn get_valset_upd_vote_height(
+        self,
+        signing_epoch: Epoch,
+    ) -> namada_storage::Result<Option<BlockHeight>> {
+        let offset = self.get_valset_upd_vote_height_offset()?;
+        Ok(self
+            .state
+            .in_mem()
+            .block
+            .pred_epochs
+            .get_start_height_of_epoch(signing_epoch)
+            .and_then(|start_height| start_height.checked_add(offset)))
+    }
+
     /// Check if the bridge is disabled, enabled, or
     /// scheduled to be enabled at a specified epoch.
     #[inline]
@@ -302,9 +352,20 @@ where
             // and ProcessProposal, we simply return true
             true
         } else {
-            // offset of 1 => are we at the 2nd
-            // block within the epoch?
-            self.state.is_deciding_offset_within_epoch(1)
+            // by default, an offset of 1 => are we at
+            // the 2nd block within the epoch?
+            match self.get_valset_upd_vote_height_offset() {
+                Ok(offset) => {
+                    self.state.is_deciding_offset_within_epoch(offset)
+                }
+                Err(err) => {
+                    tracing::error!(
+                        %err,
+                        "Not sending a validator set update vote extension"
+                    );
+                    false
+                }
+            }
         }
     }
 
@@ -392,6 +453,22 @@ where
             })
     }
 
+    /// Build the map of the Ethereum address books of the consensus
+    /// validators at the given [`Epoch`] to their voting power. The
+    /// total voting power at the epoch is the sum of the map's values.
+    pub fn eth_bridge_voting_powers_at<Gov>(
+        self,
+        epoch: Epoch,
+    ) -> namada_storage::Result<VotingPowersMap>
+    where
+        Gov: governance::Read<WlState<D, H>>,
+    {
+        Ok(self
+            .get_consensus_eth_addresses::<Gov>(epoch)
+            .map(|(eth_addr_book, _, power)| (eth_addr_book, power))
+            .collect())
+    }
+
     /// Query a chosen [`ValidatorSetArgs`] at the given [`Epoch`].
     /// Also returns a map of each validator's voting power.
     fn get_validator_set_args<Gov, F>(
@@ -498,6 +575,90 @@ where
             .expect("Reading from storage should not fail")
     }
 
+    /// Fetch the minimum number of confirmations of transfers of the
+    /// asset associated with the given [`EthAddress`].
+    ///
+    /// If no value was configured for the asset, fall back to the
+    /// global minimum confirmations of the bridge.
+    pub fn get_min_confirmations(
+        self,
+        &token: &EthAddress,
+    ) -> MinimumConfirmations {
+        let key = whitelist::Key {
+            asset: token,
+            suffix: whitelist::KeyType::MinConfirmations,
+        }
+        .into();
+
+        self.state
+            .read(&key)
+            .expect("Reading from storage should not fail")
+            .unwrap_or_else(|| {
+                self.state
+                    .read(&min_confirmations_key())
+                    .expect("Reading from storage should not fail")
+                    .expect(
+                        "The minimum confirmations of the bridge should be in \
+                         storage",
+                    )
+            })
+    }
+
+    /// Fetch the minimum number of confirmations of all the assets
+    /// with a value configured in the whitelist.
+    pub fn get_tokens_min_confirmations(
+        self,
+    ) -> BTreeMap<EthAddress, MinimumConfirmations> {
+        namada_storage::iter_prefix_with_filter_map(
+            self.state,
+            &whitelist::prefix(),
+            whitelist::is_min_confirmations_key,
+        )
+        .expect("Iterating over storage should not fail")
+        .collect::<namada_storage::Result<_>>()
+        .expect("Reading from storage should not fail")
+    }
+
+    /// Fetch the ERC20 assets which are currently whitelisted, along with
+    /// their token cap and denomination.
+    pub fn read_erc20_whitelist(
+        self,
+    ) -> namada_storage::Result<Vec<(EthAddress, token::Amount, u8)>> {
+        let whitelisted: Vec<(EthAddress, bool)> =
+            namada_storage::iter_prefix_with_filter_map(
+                self.state,
+                &whitelist::prefix(),
+                whitelist::is_whitelisted_key,
+            )?
+            .collect::<namada_storage::Result<_>>()?;
+        whitelisted
+            .into_iter()
+            .filter_map(|(asset, whitelisted)| whitelisted.then_some(asset))
+            .map(|asset| {
+                let key = |suffix| -> StorageKey {
+                    whitelist::Key { asset, suffix }.into()
+                };
+                let cap: token::Amount = self
+                    .state
+                    .read(&key(whitelist::KeyType::Cap))?
+                    .ok_or_else(|| {
+                        namada_storage::Error::new_alloc(format!(
+                            "Missing token cap of whitelisted asset {asset}"
+                        ))
+                    })?;
+                let denom: token::Denomination = self
+                    .state
+                    .read(&key(whitelist::KeyType::Denomination))?
+                    .ok_or_else(|| {
+                        namada_storage::Error::new_alloc(format!(
+                            "Missing denomination of whitelisted asset {asset}"
+                        ))
+                    })?;
+                Ok((asset, cap, denom.0))
+            })
+            .collect()
+    }
+
     /// Fetch the token supply of the asset associated with the given
     /// [`EthAddress`].
     ///
@@ -571,6 +732,22 @@ where
         }
     }
 
+    /// Resolve the bridge classification of the given Namada token.
+    pub fn token_kind(self, token: &Address) -> TokenKind {
+        match token {
+            _ if *token == self.state.in_mem().native_token => {
+                TokenKind::Native
+            }
+            Address::Internal(InternalAddress::Erc20(asset)) => {
+                TokenKind::WrappedErc20(*asset)
+            }
+            Address::Internal(InternalAddress::Nut(asset)) => {
+                TokenKind::Nut(*asset)
+            }
+            _ => TokenKind::Other,
+        }
+    }
+
     /// Given a [`TransferToEthereum`] event, look-up the corresponding
     /// [`PendingTransfer`].
     pub fn lookup_transfer_to_eth(
@@ -632,6 +809,20 @@ where
     }
 }
 
+/// The classification of a Namada token with regards to the
+/// Ethereum bridge.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum TokenKind {
+    /// The native token of Namada.
+    Native,
+    /// A wrapped ERC20 asset, minted by the Ethereum bridge.
+    WrappedErc20(EthAddress),
+    /// A non-usable token, minted by the Ethereum bridge.
+    Nut(EthAddress),
+    /// A token unrelated to the Ethereum bridge.
+    Other,
+}
+
 /// Number of tokens to mint after receiving a "transfer
 /// to Namada" Ethereum event.
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
@@ -655,3 +846,196 @@ impl EthAssetMint {
         !self.erc20_amount.is_zero()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use namada_core::address;
+    use namada_core::ethereum_events::testing::DAI_ERC20_ETH_ADDRESS;
+    use namada_state::testing::TestState;
+
+    use super::*;
+    use crate::protocol::transactions::validator_set_update::{
+        SigVerification, aggregate_votes,
+    };
+    use crate::protocol::transactions::votes;
+    use crate::storage::proof::EthereumProof;
+    use crate::storage::wrapped_erc20s;
+    use crate::test_utils::{self, GovStore};
+
+    /// Test that Namada tokens are classified according to their
+    /// relation with the Ethereum bridge.
+    #[test]
+    fn test_token_kind() {
+        let (state, _) = test_utils::setup_default_storage();
+        let native_token = state.in_mem().native_token.clone();
+
+        assert_eq!(
+            state.ethbridge_queries().token_kind(&native_token),
+            TokenKind::Native
+        );
+        assert_eq!(
+            state
+                .ethbridge_queries()
+                .token_kind(&wrapped_erc20s::token(&DAI_ERC20_ETH_ADDRESS)),
+            TokenKind::WrappedErc20(DAI_ERC20_ETH_ADDRESS)
+        );
+        assert_eq!(
+            state
+                .ethbridge_queries()
+                .token_kind(&wrapped_erc20s::nut(&DAI_ERC20_ETH_ADDRESS)),
+            TokenKind::Nut(DAI_ERC20_ETH_ADDRESS)
+        );
+        assert_eq!(
+            state
+                .ethbridge_queries()
+                .token_kind(&address::testing::established_address_1()),
+            TokenKind::Other
+        );
+    }
+
+    /// Test that the minimum confirmations of an asset fall back to
+    /// the global value of the bridge.
+    #[test]
+    fn test_token_min_confirmations() {
+        let (mut state, _) = test_utils::setup_default_storage();
+        let usdc = EthAddress([1; 20]);
+        test_utils::whitelist_tokens(
+            &mut state,
+            [
+                (
+                    DAI_ERC20_ETH_ADDRESS,
+                    test_utils::WhitelistMeta {
+                        cap: token::Amount::max(),
+                        denom: 18,
+                        min_confirmations: None,
+                    },
+                ),
+                (
+                    usdc,
+                    test_utils::WhitelistMeta {
+                        cap: token::Amount::max(),
+                        denom: 6,
+                        min_confirmations: Some(
+                            std::num::NonZeroU64::new(42).unwrap(),
+                        ),
+                    },
+                ),
+            ],
+        );
+        let global: MinimumConfirmations = state
+            .read(&min_confirmations_key())
+            .expect("Test failed")
+            .expect("Test failed");
+        let custom =
+            MinimumConfirmations::from(std::num::NonZeroU64::new(42).unwrap());
+
+        assert_eq!(
+            state
+                .ethbridge_queries()
+                .get_min_confirmations(&DAI_ERC20_ETH_ADDRESS),
+            global
+        );
+        assert_eq!(
+            state.ethbridge_queries().get_min_confirmations(&usdc),
+            custom
+        );
+        assert_eq!(
+            state.ethbridge_queries().get_tokens_min_confirmations(),
+            BTreeMap::from([(usdc, custom)])
+        );
+    }
+
+    /// Test that the voting powers of the validators at some epoch match
+    /// those signed over by a freshly aggregated validator set update.
+    #[test]
+    fn test_eth_bridge_voting_powers_at() {
+        let validators = [
+            (address::testing::established_address_1(), 100),
+            (address::testing::established_address_2(), 200),
+        ];
+        let (mut state, keys) = test_utils::setup_storage_with_validators(
+            validators
+                .iter()
+                .map(|(validator, stake)| {
+                    (validator.clone(), token::Amount::native_whole(*stake))
+                })
+                .collect(),
+        );
+        let last_height = state.in_mem().get_last_block_height();
+        let signing_epoch = test_utils::epoch_of_height(&state, last_height);
+        let next_epoch = signing_epoch.next();
+
+        let signed_powers: VotingPowersMap = state
+            .ethbridge_queries()
+            .get_consensus_eth_addresses::<GovStore<_>>(next_epoch)
+            .map(|(addr_book, _, power)| (addr_book, power))
+            .collect();
+        let signers: Vec<_> = validators
+            .iter()
+            .map(|(validator, _)| {
+                (validator.clone(), &keys[validator].eth_bridge)
+            })
+            .collect();
+        aggregate_votes::<_, _, GovStore<_>>(
+            &mut state,
+            test_utils::build_valset_upd_digest(
+                &signers,
+                signed_powers,
+                signing_epoch,
+            ),
+            signing_epoch,
+            SigVerification::Enabled,
+        )
+        .expect("Test failed");
+        let proof: EthereumProof<VotingPowersMap> = votes::storage::read_body(
+            &state,
+            &vote_tallies::Keys::from(&next_epoch),
+        )
+        .expect("Test failed");
+
+        let voting_powers = state
+            .ethbridge_queries()
+            .eth_bridge_voting_powers_at::<GovStore<_>>(next_epoch)
+            .expect("Test failed");
+        assert_eq!(voting_powers, proof.data);
+        assert_eq!(voting_powers.len(), validators.len());
+        let total = voting_powers
+            .values()
+            .try_fold(token::Amount::zero(), |acc, power| {
+                acc.checked_add(*power)
+            })
+            .expect("Test failed");
+        assert_eq!(
+            total,
+            get_total_voting_power::<_, GovStore<_>>(&state, next_epoch)
+        );
+    }
+
+    /// Test that only the ERC20 assets which are still whitelisted are
+    /// listed.
+    #[test]
+    fn test_read_erc20_whitelist() {
+        let mut state = TestState::default();
+        let active = EthAddress([1; 20]);
+        let inactive = EthAddress([2; 20]);
+        let meta = |cap| test_utils::WhitelistMeta {
+            cap: token::Amount::from_u64(cap),
+            denom: 18,
+            min_confirmations: None,
+        };
+        test_utils::whitelist_tokens(
+            &mut state,
+            [(active, meta(1_000)), (inactive, meta(2_000))],
+        );
+        test_utils::dewhitelist_tokens(&mut state, &[inactive], false);
+
+        let whitelist = state
+            .ethbridge_queries()
+            .read_erc20_whitelist()
+            .expect("Test failed");
+        assert_eq!(
+            whitelist,
+            vec![(active, token::Amount::from_u64(1_000), 18)]
+        );
+    }
+}
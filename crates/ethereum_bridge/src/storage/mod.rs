@@ -66,6 +66,12 @@ pub fn bridge_contract_key() -> Key {
     get_bridge_contract_address_key_at_addr(PARAM_ADDRESS)
 }
 
+/// Storage key for the number of blocks after the start of an epoch
+/// at which validator set update votes are cast.
+pub fn valset_upd_vote_height_offset_key() -> Key {
+    get_valset_upd_vote_height_offset_key_at_addr(PARAM_ADDRESS)
+}
+
 #[cfg(test)]
 mod test {
     use namada_core::address;
@@ -38,6 +38,11 @@ pub struct Erc20WhitelistEntry {
     pub token_address: EthAddress,
     /// The token cap of the whitelisted ERC20 token.
     pub token_cap: DenominatedAmount,
+    /// The minimum number of confirmations of transfers of the
+    /// whitelisted ERC20 token. If not set, the global minimum
+    /// confirmations of the bridge apply.
+    #[serde(default)]
+    pub min_confirmations: Option<MinimumConfirmations>,
 }
 
 /// Represents a configuration value for the minimum number of
@@ -84,6 +89,8 @@ impl From<MinimumConfirmations> for NonZeroU64 {
     Copy,
     Eq,
     PartialEq,
+    Ord,
+    PartialOrd,
     Debug,
     Deserialize,
     Serialize,
@@ -103,6 +110,19 @@ impl Default for ContractVersion {
     }
 }
 
+impl ContractVersion {
+    /// Return the version following this one.
+    ///
+    /// Panics if the version overflows.
+    pub fn next(&self) -> ContractVersion {
+        Self(
+            self.0
+                .checked_add(1)
+                .expect("The contract version should not overflow"),
+        )
+    }
+}
+
 /// Represents an Ethereum contract that may be upgraded.
 #[derive(
     Copy,
@@ -193,7 +213,6 @@ impl EthereumBridgeParams {
         let active_key = bridge_storage::active_key();
         let min_confirmations_key = bridge_storage::min_confirmations_key();
         let native_erc20_key = bridge_storage::native_erc20_key();
-        let bridge_contract_key = bridge_storage::bridge_contract_key();
         let eth_start_height_key = bridge_storage::eth_start_height_key();
         state
             .write(
@@ -205,13 +224,14 @@ impl EthereumBridgeParams {
             .write(&min_confirmations_key, min_confirmations)
             .unwrap();
         state.write(&native_erc20_key, native_erc20).unwrap();
-        state.write(&bridge_contract_key, bridge).unwrap();
+        write_bridge_contract(state, bridge).unwrap();
         state
             .write(&eth_start_height_key, eth_start_height)
             .unwrap();
         for Erc20WhitelistEntry {
             token_address: addr,
             token_cap,
+            min_confirmations,
         } in erc20_whitelist
         {
             let cap = token_cap.amount();
@@ -244,6 +264,15 @@ impl EthereumBridgeParams {
             }
             .into();
             state.write(&key, denom).unwrap();
+
+            if let Some(min_confirmations) = min_confirmations {
+                let key = whitelist::Key {
+                    asset: *addr,
+                    suffix: whitelist::KeyType::MinConfirmations,
+                }
+                .into();
+                state.write(&key, min_confirmations).unwrap();
+            }
         }
         // Initialize the storage for the Ethereum Bridge VP.
         vp::ethereum_bridge::init_storage(state);
@@ -325,6 +354,29 @@ impl EthereumOracleConfig {
     }
 }
 
+/// Write the bridge contract to storage, rejecting any downgrade of the
+/// version of the contract currently in storage.
+pub fn write_bridge_contract<S>(
+    storage: &mut S,
+    contract: &UpgradeableContract,
+) -> Result<()>
+where
+    S: StorageRead + StorageWrite,
+{
+    let bridge_contract_key = bridge_storage::bridge_contract_key();
+    if let Some(stored) =
+        storage.read::<UpgradeableContract>(&bridge_contract_key)?
+    {
+        if contract.version < stored.version {
+            return Err(Error::new_alloc(format!(
+                "Cannot downgrade the bridge contract from version {} to {}",
+                stored.version.0, contract.version.0
+            )));
+        }
+    }
+    storage.write(&bridge_contract_key, contract)
+}
+
 /// Get the Ethereum address for wNam from storage, if possible
 pub fn read_native_erc20_address<S>(storage: &S) -> Result<EthAddress>
 where
@@ -362,6 +414,8 @@ where
 
 #[cfg(test)]
 mod tests {
+    use std::collections::BTreeMap;
+
     use namada_state::testing::TestState;
     use namada_storage::ResultExt;
 
@@ -422,6 +476,94 @@ mod tests {
         assert_eq!(config, read);
     }
 
+    /// Test that the bridge contract can be upgraded to a newer version.
+    #[test]
+    fn test_write_bridge_contract_upgrade() {
+        let mut state = TestState::default();
+        let contract = UpgradeableContract {
+            address: EthAddress([23; 20]),
+            version: ContractVersion::default(),
+        };
+        write_bridge_contract(&mut state, &contract).unwrap();
+
+        let upgraded = UpgradeableContract {
+            address: EthAddress([24; 20]),
+            version: contract.version.next(),
+        };
+        assert!(upgraded.version > contract.version);
+        write_bridge_contract(&mut state, &upgraded).unwrap();
+
+        let stored: UpgradeableContract = state
+            .read(&bridge_storage::bridge_contract_key())
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored, upgraded);
+    }
+
+    /// Test that downgrading the bridge contract is rejected.
+    #[test]
+    fn test_write_bridge_contract_downgrade() {
+        let mut state = TestState::default();
+        let contract = UpgradeableContract {
+            address: EthAddress([23; 20]),
+            version: ContractVersion::default().next(),
+        };
+        write_bridge_contract(&mut state, &contract).unwrap();
+
+        let downgraded = UpgradeableContract {
+            address: EthAddress([24; 20]),
+            version: ContractVersion::default(),
+        };
+        let err = write_bridge_contract(&mut state, &downgraded).unwrap_err();
+        assert!(err.to_string().contains("from version 2 to 1"));
+
+        let stored: UpgradeableContract = state
+            .read(&bridge_storage::bridge_contract_key())
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored, contract);
+    }
+
+    /// Test that the minimum confirmations of whitelisted tokens are
+    /// written to storage at genesis, if configured.
+    #[test]
+    fn test_erc20_whitelist_min_confirmations() {
+        let mut state = TestState::default();
+        let min_confirmations =
+            MinimumConfirmations::from(NonZeroU64::new(200).unwrap());
+        let config = EthereumBridgeParams {
+            erc20_whitelist: vec![
+                Erc20WhitelistEntry {
+                    token_address: EthAddress([1; 20]),
+                    token_cap: namada_core::token::Amount::from_u64(1_000)
+                        .native_denominated(),
+                    min_confirmations: Some(min_confirmations),
+                },
+                Erc20WhitelistEntry {
+                    token_address: EthAddress([2; 20]),
+                    token_cap: namada_core::token::Amount::from_u64(1_000)
+                        .native_denominated(),
+                    min_confirmations: None,
+                },
+            ],
+            eth_start_height: Default::default(),
+            min_confirmations: MinimumConfirmations::default(),
+            contracts: Contracts {
+                native_erc20: EthAddress([42; 20]),
+                bridge: UpgradeableContract {
+                    address: EthAddress([23; 20]),
+                    version: ContractVersion::default(),
+                },
+            },
+        };
+        config.init_storage(&mut state);
+
+        assert_eq!(
+            state.ethbridge_queries().get_tokens_min_confirmations(),
+            BTreeMap::from([(EthAddress([1; 20]), min_confirmations)])
+        );
+    }
+
     #[test]
     fn test_ethereum_bridge_config_uninitialized() {
         let state = TestState::default();
@@ -2,16 +2,20 @@
 
 use borsh::{BorshDeserialize, BorshSchema, BorshSerialize};
 use ethers::abi::Tokenizable;
+use eyre::eyre;
+use namada_core::borsh::BorshSerializeExt;
+use namada_core::bytes::HEXUPPER;
 use namada_core::chain::Epoch;
 use namada_core::collections::HashMap;
 use namada_core::eth_abi::Encode;
 use namada_core::ethereum_events::Uint;
 use namada_core::keccak::KeccakHash;
-use namada_core::key::{common, secp256k1};
+use namada_core::key::{RefTo, SigScheme, common, secp256k1};
 use namada_core::{eth_abi, ethereum_structs};
 use namada_vote_ext::validator_set_update::{
     EthAddrBook, VotingPowersMap, VotingPowersMapExt, valset_upd_toks_to_hashes,
 };
+use serde::{Deserialize, Serialize};
 
 /// Ethereum proofs contain the [`secp256k1`] signatures of validators
 /// over some data to be signed.
@@ -19,7 +23,9 @@ use namada_vote_ext::validator_set_update::{
 /// At any given time, an [`EthereumProof`] will be considered
 /// "complete" once a number of signatures pertaining to validators
 /// reflecting more than 2/3 of the bonded stake on Namada is available.
-#[derive(Clone, Debug, BorshSerialize, BorshDeserialize, BorshSchema)]
+#[derive(
+    Clone, Debug, PartialEq, Eq, BorshSerialize, BorshDeserialize, BorshSchema,
+)]
 pub struct EthereumProof<T> {
     /// The signatures contained in the proof.
     pub signatures: HashMap<EthAddrBook, secp256k1::Signature>,
@@ -63,14 +69,102 @@ impl<T> EthereumProof<T> {
     }
 
     /// Add a new batch of signatures to this [`EthereumProof`].
-    pub fn attach_signature_batch<I, K>(&mut self, batch: I)
+    ///
+    /// Signatures of address books already present in the proof are
+    /// skipped. Returns the number of newly added signatures.
+    pub fn attach_signature_batch<I, K>(&mut self, batch: I) -> usize
     where
         I: IntoIterator<Item = (EthAddrBook, K)>,
         K: Into<common::Signature>,
     {
+        let mut added = 0usize;
         for (addr_book, signature) in batch {
-            self.attach_signature(addr_book, signature.into());
+            if self.signatures.contains_key(&addr_book) {
+                continue;
+            }
+            if let common::Signature::Secp256k1(sig) = signature.into() {
+                self.signatures.insert(addr_book, sig);
+                added = added.saturating_add(1);
+            }
         }
+        added
+    }
+}
+
+impl<T: BorshDeserialize> EthereumProof<T> {
+    /// Decode only the signed data of a Borsh encoded [`EthereumProof`],
+    /// skipping over its signatures without decoding them.
+    pub fn deserialize_data(proof: &[u8]) -> std::io::Result<T> {
+        EthereumProofData::try_from_slice(proof).map(|proof| proof.data)
+    }
+}
+
+/// Borsh layout of an [`EthereumProof`], whose signatures are kept as
+/// the raw bytes of the encoding of a [`secp256k1::Signature`].
+#[derive(BorshDeserialize)]
+struct EthereumProofData<T> {
+    _signatures: Vec<(EthAddrBook, ([u8; 64], u8))>,
+    data: T,
+}
+
+impl<T: BorshSerialize> EthereumProof<T> {
+    /// Export this [`EthereumProof`] to a [`SignedProofEnvelope`],
+    /// signed by `signer` over the proof and the given `epoch`.
+    pub fn to_signed_envelope(
+        &self,
+        epoch: Epoch,
+        signer: &common::SecretKey,
+    ) -> SignedProofEnvelope {
+        let proof = self.serialize_to_vec();
+        let signature = common::SigScheme::sign(
+            signer,
+            SignedProofEnvelope::signable_bytes(epoch, &proof),
+        );
+        SignedProofEnvelope {
+            epoch,
+            proof: HEXUPPER.encode(&proof),
+            signer: signer.ref_to(),
+            signature,
+        }
+    }
+}
+
+/// A JSON friendly envelope around a Borsh encoded [`EthereumProof`],
+/// carrying a Namada signature over the proof and its epoch.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SignedProofEnvelope {
+    /// The epoch the proof pertains to.
+    pub epoch: Epoch,
+    /// The hex encoded Borsh bytes of the proof.
+    pub proof: String,
+    /// The public key of the signer of this envelope.
+    pub signer: common::PublicKey,
+    /// The signature over the proof and its epoch.
+    pub signature: common::Signature,
+}
+
+impl SignedProofEnvelope {
+    /// Return the bytes signed over in a [`SignedProofEnvelope`].
+    fn signable_bytes(epoch: Epoch, proof: &[u8]) -> Vec<u8> {
+        (epoch, proof).serialize_to_vec()
+    }
+
+    /// Verify the signature of this envelope, and decode the
+    /// [`EthereumProof`] it contains.
+    pub fn verify<T: BorshDeserialize>(
+        &self,
+    ) -> eyre::Result<EthereumProof<T>> {
+        let proof = HEXUPPER
+            .decode(self.proof.as_bytes())
+            .map_err(|err| eyre!("Invalid proof encoding: {err}"))?;
+        common::SigScheme::verify_signature(
+            &self.signer,
+            &Self::signable_bytes(self.epoch, &proof),
+            &self.signature,
+        )
+        .map_err(|err| eyre!("Invalid proof envelope signature: {err}"))?;
+        EthereumProof::try_from_slice(&proof)
+            .map_err(|err| eyre!("Invalid proof bytes: {err}"))
     }
 }
 
@@ -148,4 +242,103 @@ mod test_ethbridge_proofs {
         );
         assert!(proof.signatures.is_empty());
     }
+
+    /// Test that attaching a batch of signatures skips the address
+    /// books which already have a signature in the proof.
+    #[test]
+    fn test_attach_signature_batch_dedup() {
+        let addr_book = |i| EthAddrBook {
+            hot_key_addr: EthAddress([i; 20]),
+            cold_key_addr: EthAddress([i; 20]),
+        };
+        let sig =
+            Signed::<&'static str>::new(&key::testing::keypair_3(), "data").sig;
+
+        let mut proof = EthereumProof::new("data");
+        assert_eq!(
+            proof.attach_signature_batch([(addr_book(1), sig.clone())]),
+            1
+        );
+        assert_eq!(
+            proof.attach_signature_batch([
+                (addr_book(1), sig.clone()),
+                (addr_book(2), sig),
+            ]),
+            1
+        );
+        assert_eq!(proof.signatures.len(), 2);
+    }
+
+    /// Test that a proof exported to a [`SignedProofEnvelope`] survives
+    /// a JSON round trip, and that its signature is verified.
+    #[test]
+    fn test_signed_envelope_roundtrip() {
+        let mut proof: BridgePoolRootProof =
+            EthereumProof::new((KeccakHash([1; 32]), Uint::from(7)));
+        let eth_key = key::testing::keypair_3();
+        assert_matches!(&eth_key, common::SecretKey::Secp256k1(_));
+        let addr_book = EthAddrBook {
+            hot_key_addr: EthAddress([1; 20]),
+            cold_key_addr: EthAddress([2; 20]),
+        };
+        proof.attach_signature(
+            addr_book.clone(),
+            Signed::<&'static str>::new(&eth_key, "root").sig,
+        );
+
+        let signer = key::testing::keypair_1();
+        let envelope = proof.to_signed_envelope(Epoch(3), &signer);
+        let json = serde_json::to_string(&envelope).expect("Test failed");
+        let decoded: SignedProofEnvelope =
+            serde_json::from_str(&json).expect("Test failed");
+        assert_eq!(decoded.epoch, Epoch(3));
+        assert_eq!(decoded.signer, signer.ref_to());
+
+        let verified: BridgePoolRootProof =
+            decoded.verify().expect("Test failed");
+        assert_eq!(verified.data, proof.data);
+        assert_eq!(verified.signatures, proof.signatures);
+
+        // tampering with the epoch invalidates the signature
+        let tampered = SignedProofEnvelope {
+            epoch: Epoch(4),
+            ..decoded
+        };
+        assert!(tampered.verify::<(KeccakHash, Uint)>().is_err());
+    }
+
+    /// Test that proofs built by attaching the same signatures in
+    /// different orders are equal.
+    #[test]
+    fn test_proofs_eq_regardless_of_sig_order() {
+        let sigs: Vec<_> = [
+            (1u8, key::testing::keypair_3()),
+            (2u8, key::testing::keypair_4()),
+        ]
+        .into_iter()
+        .map(|(i, key)| {
+            let addr_book = EthAddrBook {
+                hot_key_addr: EthAddress([i; 20]),
+                cold_key_addr: EthAddress([i; 20]),
+            };
+            (addr_book, Signed::<&'static str>::new(&key, "data").sig)
+        })
+        .collect();
+
+        let mut proof = EthereumProof::new("data");
+        proof.attach_signature_batch(sigs.clone());
+        let mut reversed = EthereumProof::new("data");
+        reversed.attach_signature_batch(sigs.iter().rev().cloned());
+        assert_eq!(proof, reversed);
+
+        // a proof missing a signature is not equal
+        let mut partial = EthereumProof::new("data");
+        partial.attach_signature_batch(sigs.iter().take(1).cloned());
+        assert_ne!(proof, partial);
+
+        // nor is a proof over different data
+        let mut other_data = EthereumProof::new("other data");
+        other_data.attach_signature_batch(sigs);
+        assert_ne!(proof, other_data);
+    }
 }
@@ -29,13 +29,13 @@ mod segments {
         pub whitelisted: &'static str,
         /// The token cap of an ERC20 asset.
         pub cap: &'static str,
+        /// The minimum number of confirmations of transfers of an ERC20
+        /// asset.
+        pub min_confirmations: &'static str,
     }
 
     /// All the values of the generated [`Segments`].
     pub(super) const VALUES: Segments = Segments::VALUES;
-
-    /// Listing of each of the generated [`Segments`].
-    pub(super) const ALL: &[&str] = Segments::ALL;
 }
 
 /// Represents the type of a key relating to whitelisted ERC20.
@@ -50,6 +50,9 @@ pub enum KeyType {
     WrappedSupply,
     /// The denomination of the ERC20 asset.
     Denomination,
+    /// The minimum number of confirmations of transfers of an ERC20 asset,
+    /// overriding the global minimum confirmations of the bridge.
+    MinConfirmations,
 }
 
 /// Whitelisted ERC20 token storage sub-space.
@@ -60,11 +63,16 @@ pub struct Key {
     pub suffix: KeyType,
 }
 
-/// Return the whitelist storage key sub-space prefix.
-fn whitelist_prefix(asset: &EthAddress) -> storage::Key {
+/// Return the storage key prefix of the whole token whitelist.
+pub fn prefix() -> storage::Key {
     ethbridge_key_prefix()
         .push(&segments::MAIN_SEGMENT.to_owned())
         .expect("Should be able to push a storage key segment")
+}
+
+/// Return the whitelist storage key sub-space prefix.
+fn whitelist_prefix(asset: &EthAddress) -> storage::Key {
+    prefix()
         .push(&asset.to_canonical())
         .expect("Should be able to push a storage key segment")
 }
@@ -93,6 +101,9 @@ impl From<&Key> for storage::Key {
                 let token = erc20_token_address(&key.asset);
                 denom_key(&token)
             }
+            KeyType::MinConfirmations => whitelist_prefix(&key.asset)
+                .push(&segments::VALUES.min_confirmations.to_owned())
+                .expect("Should be able to push a storage key segment"),
         }
     }
 }
@@ -110,12 +121,53 @@ pub fn is_cap_or_whitelisted_key(key: &storage::Key) -> bool {
             s1 == &BRIDGE_ADDRESS
                 && s2 == segments::MAIN_SEGMENT
                 && EthAddress::from_str(s3).is_ok()
-                && segments::ALL.binary_search(&s4.as_str()).is_ok()
+                && (s4 == segments::VALUES.cap
+                    || s4 == segments::VALUES.whitelisted)
         }
         _ => false,
     }
 }
 
+/// Check if some [`storage::Key`] is an Ethereum bridge whitelist key
+/// of type [`KeyType::MinConfirmations`], returning the ERC20 asset
+/// it refers to.
+pub fn is_min_confirmations_key(key: &storage::Key) -> Option<EthAddress> {
+    match &key.segments[..] {
+        [
+            DbKeySeg::AddressSeg(s1),
+            DbKeySeg::StringSeg(s2),
+            DbKeySeg::StringSeg(s3),
+            DbKeySeg::StringSeg(s4),
+        ] if s1 == &BRIDGE_ADDRESS
+            && s2 == segments::MAIN_SEGMENT
+            && s4 == segments::VALUES.min_confirmations =>
+        {
+            EthAddress::from_str(s3).ok()
+        }
+        _ => None,
+    }
+}
+
+/// Check if some [`storage::Key`] is an Ethereum bridge whitelist key
+/// of type [`KeyType::Whitelisted`], returning the ERC20 asset it
+/// refers to.
+pub fn is_whitelisted_key(key: &storage::Key) -> Option<EthAddress> {
+    match &key.segments[..] {
+        [
+            DbKeySeg::AddressSeg(s1),
+            DbKeySeg::StringSeg(s2),
+            DbKeySeg::StringSeg(s3),
+            DbKeySeg::StringSeg(s4),
+        ] if s1 == &BRIDGE_ADDRESS
+            && s2 == segments::MAIN_SEGMENT
+            && s4 == segments::VALUES.whitelisted =>
+        {
+            EthAddress::from_str(s3).ok()
+        }
+        _ => None,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use namada_core::ethereum_events::testing::DAI_ERC20_ETH_ADDRESS;
@@ -164,4 +216,42 @@ mod tests {
         };
         assert!(!is_cap_or_whitelisted_key(&unexpected_key));
     }
+
+    /// Test that min confirmations keys are recognized, and not
+    /// mistaken for cap or whitelisted keys.
+    #[test]
+    fn test_min_confirmations_key() {
+        let key: storage::Key = Key {
+            asset: DAI_ERC20_ETH_ADDRESS,
+            suffix: KeyType::MinConfirmations,
+        }
+        .into();
+        assert_eq!(is_min_confirmations_key(&key), Some(DAI_ERC20_ETH_ADDRESS));
+        assert!(!is_cap_or_whitelisted_key(&key));
+
+        let cap_key: storage::Key = Key {
+            asset: DAI_ERC20_ETH_ADDRESS,
+            suffix: KeyType::Cap,
+        }
+        .into();
+        assert_eq!(is_min_confirmations_key(&cap_key), None);
+    }
+
+    /// Test that whitelisted keys are recognized, along with their asset.
+    #[test]
+    fn test_whitelisted_key() {
+        let key: storage::Key = Key {
+            asset: DAI_ERC20_ETH_ADDRESS,
+            suffix: KeyType::Whitelisted,
+        }
+        .into();
+        assert_eq!(is_whitelisted_key(&key), Some(DAI_ERC20_ETH_ADDRESS));
+
+        let cap_key: storage::Key = Key {
+            asset: DAI_ERC20_ETH_ADDRESS,
+            suffix: KeyType::Cap,
+        }
+        .into();
+        assert_eq!(is_whitelisted_key(&cap_key), None);
+    }
 }
@@ -7,30 +7,41 @@ use std::num::NonZeroU64;
 use namada_account::protocol_pk_key;
 use namada_core::address::testing::wnam;
 use namada_core::address::{self, Address};
-use namada_core::chain::BlockHeight;
+use namada_core::chain::{BlockHeight, Epoch};
 use namada_core::collections::HashMap;
 use namada_core::dec::Dec;
 use namada_core::ethereum_events::EthAddress;
+use namada_core::ethereum_structs;
 use namada_core::keccak::KeccakHash;
-use namada_core::key::{self, RefTo};
-use namada_core::storage::Key;
-use namada_proof_of_stake::parameters::OwnedPosParams;
-use namada_proof_of_stake::types::GenesisValidator;
+use namada_core::key::{self, RefTo, SchemeType};
+use namada_core::storage::{Key, KeySeg};
+use namada_proof_of_stake::parameters::{OwnedPosParams, PosParams};
+use namada_proof_of_stake::queries::read_validator_stake;
+use namada_proof_of_stake::types::{GenesisValidator, ValidatorMetaData};
+use namada_proof_of_stake::validator_set_update::copy_validator_sets_and_positions;
 use namada_proof_of_stake::{
     BecomeValidator, become_validator, bond_tokens,
     compute_and_store_total_consensus_stake, staking_token_address,
 };
+use namada_state::merkle_tree::eth_bridge_pool::BridgePoolProof;
 use namada_state::testing::TestState;
+use namada_state::{MembershipProof, StoreType};
 use namada_storage::{StorageRead, StorageWrite};
 use namada_trans_token as token;
 use namada_trans_token::credit_tokens;
+use namada_vote_ext::validator_set_update::{
+    self, SignedVext, VextDigest, VotingPowersMap,
+};
 
-use crate::storage::bridge_pool::get_key_from_hash;
+use crate::protocol::transactions::votes::{self, Votes};
+use crate::storage::bridge_pool::{BRIDGE_POOL_ADDRESS, get_key_from_hash};
+use crate::storage::eth_bridge_queries::EthBridgeQueries;
 use crate::storage::parameters::{
-    ContractVersion, Contracts, EthereumBridgeParams, MinimumConfirmations,
-    UpgradeableContract,
+    ContractVersion, Contracts, Erc20WhitelistEntry, EthereumBridgeParams,
+    MinimumConfirmations, UpgradeableContract, write_bridge_contract,
 };
-use crate::storage::whitelist;
+use crate::storage::proof::EthereumProof;
+use crate::storage::{vote_tallies, whitelist};
 
 /// Validator keys used for testing purposes.
 pub struct TestValidatorKeys {
@@ -48,23 +59,64 @@ impl TestValidatorKeys {
     /// Generate a new test wallet.
     #[inline]
     pub fn generate() -> Self {
+        Self::generate_with(KeySchemes::default())
+    }
+
+    /// Generate a new test wallet, with keys of the given schemes.
+    pub fn generate_with(schemes: KeySchemes) -> Self {
         TestValidatorKeys {
-            consensus: key::common::SecretKey::Ed25519(
-                key::testing::gen_keypair::<key::ed25519::SigScheme>(),
-            ),
-            protocol: key::common::SecretKey::Ed25519(
-                key::testing::gen_keypair::<key::ed25519::SigScheme>(),
-            ),
-            eth_bridge: key::common::SecretKey::Secp256k1(
-                key::testing::gen_keypair::<key::secp256k1::SigScheme>(),
-            ),
-            eth_gov: key::common::SecretKey::Secp256k1(
-                key::testing::gen_keypair::<key::secp256k1::SigScheme>(),
-            ),
+            consensus: gen_keypair(schemes.consensus),
+            protocol: gen_keypair(schemes.protocol),
+            eth_bridge: gen_keypair(schemes.eth_bridge),
+            eth_gov: gen_keypair(schemes.eth_gov),
+        }
+    }
+}
+
+/// Signature schemes of the keys in a [`TestValidatorKeys`].
+#[derive(Debug, Clone, Copy)]
+pub struct KeySchemes {
+    /// Scheme of the consensus key. Defaults to Ed25519.
+    pub consensus: SchemeType,
+    /// Scheme of the protocol key. Defaults to Ed25519.
+    pub protocol: SchemeType,
+    /// Scheme of the Ethereum hot key. Defaults to Secp256k1.
+    pub eth_bridge: SchemeType,
+    /// Scheme of the Ethereum cold key. Defaults to Secp256k1.
+    pub eth_gov: SchemeType,
+}
+
+impl Default for KeySchemes {
+    fn default() -> Self {
+        Self {
+            consensus: SchemeType::Ed25519,
+            protocol: SchemeType::Ed25519,
+            eth_bridge: SchemeType::Secp256k1,
+            eth_gov: SchemeType::Secp256k1,
         }
     }
 }
 
+/// Generate a keypair of the given scheme.
+fn gen_keypair(scheme: SchemeType) -> key::common::SecretKey {
+    match scheme {
+        SchemeType::Ed25519 => {
+            key::common::SecretKey::Ed25519(key::testing::gen_keypair::<
+                key::ed25519::SigScheme,
+            >())
+        }
+        SchemeType::Secp256k1 => {
+            key::common::SecretKey::Secp256k1(key::testing::gen_keypair::<
+                key::secp256k1::SigScheme,
+            >())
+        }
+        SchemeType::Common => panic!(
+            "Cannot generate common signing scheme. Must pick a concrete \
+             scheme."
+        ),
+    }
+}
+
 /// Set up a [`TestState`] initialized at genesis with a single
 /// validator.
 ///
@@ -100,31 +152,120 @@ pub fn default_validator() -> (Address, token::Amount) {
     (addr, voting_power)
 }
 
+/// Builder of [`EthereumBridgeParams`] to bootstrap the Ethereum bridge
+/// with in tests.
+///
+/// Defaults to an empty ERC20 whitelist, 10 minimum confirmations,
+/// [`wnam`] as the native ERC20 token, and `EthAddress([2; 20])` as the
+/// address of the bridge contract.
+pub struct EthereumBridgeParamsBuilder {
+    params: EthereumBridgeParams,
+}
+
+impl Default for EthereumBridgeParamsBuilder {
+    fn default() -> Self {
+        Self {
+            params: EthereumBridgeParams {
+                // start with empty erc20 whitelist
+                erc20_whitelist: vec![],
+                eth_start_height: Default::default(),
+                min_confirmations: MinimumConfirmations::from(unsafe {
+                    // SAFETY: The only way the API contract of `NonZeroU64`
+                    // can be violated is if we construct values
+                    // of this type using 0 as argument.
+                    NonZeroU64::new_unchecked(10)
+                }),
+                contracts: Contracts {
+                    native_erc20: wnam(),
+                    bridge: UpgradeableContract {
+                        address: EthAddress([2; 20]),
+                        version: ContractVersion::default(),
+                    },
+                },
+            },
+        }
+    }
+}
+
+impl EthereumBridgeParamsBuilder {
+    /// Create a new builder with the default parameters.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the minimum number of confirmations of Ethereum events.
+    pub fn with_min_confirmations(
+        mut self,
+        min_confirmations: NonZeroU64,
+    ) -> Self {
+        self.params.min_confirmations = min_confirmations.into();
+        self
+    }
+
+    /// Set the address of the bridge contract.
+    pub fn with_bridge_address(mut self, address: EthAddress) -> Self {
+        self.params.contracts.bridge.address = address;
+        self
+    }
+
+    /// Set the ERC20 tokens whitelisted at genesis.
+    pub fn with_erc20_whitelist(
+        mut self,
+        erc20_whitelist: Vec<Erc20WhitelistEntry>,
+    ) -> Self {
+        self.params.erc20_whitelist = erc20_whitelist;
+        self
+    }
+
+    /// Set the Ethereum block height from which events are extracted.
+    pub fn with_eth_start_height(
+        mut self,
+        eth_start_height: ethereum_structs::BlockHeight,
+    ) -> Self {
+        self.params.eth_start_height = eth_start_height;
+        self
+    }
+
+    /// Return the built [`EthereumBridgeParams`].
+    #[inline]
+    pub fn build(self) -> EthereumBridgeParams {
+        self.params
+    }
+
+    /// Write the built [`EthereumBridgeParams`] to the given [`TestState`],
+    /// and return them.
+    pub fn bootstrap(self, state: &mut TestState) -> EthereumBridgeParams {
+        self.params.init_storage(state);
+        self.params
+    }
+}
+
 /// Writes a dummy [`EthereumBridgeParams`] to the given [`TestState`], and
 /// returns it.
+#[inline]
 pub fn bootstrap_ethereum_bridge(
     state: &mut TestState,
 ) -> EthereumBridgeParams {
-    let config = EthereumBridgeParams {
-        // start with empty erc20 whitelist
-        erc20_whitelist: vec![],
-        eth_start_height: Default::default(),
-        min_confirmations: MinimumConfirmations::from(unsafe {
-            // SAFETY: The only way the API contract of `NonZeroU64` can
-            // be violated is if we construct values
-            // of this type using 0 as argument.
-            NonZeroU64::new_unchecked(10)
-        }),
-        contracts: Contracts {
-            native_erc20: wnam(),
-            bridge: UpgradeableContract {
-                address: EthAddress([2; 20]),
-                version: ContractVersion::default(),
-            },
-        },
+    EthereumBridgeParamsBuilder::new().bootstrap(state)
+}
+
+/// Upgrade the bridge contract in storage to `new_address`, at version
+/// `new_version`.
+///
+/// Panics if `new_version` would downgrade the contract in storage.
+pub fn upgrade_bridge_contract(
+    state: &mut TestState,
+    new_address: EthAddress,
+    new_version: ContractVersion,
+) {
+    let contract = UpgradeableContract {
+        address: new_address,
+        version: new_version,
     };
-    config.init_storage(state);
-    config
+    write_bridge_contract(state, &contract).unwrap_or_else(|err| {
+        panic!("Failed to upgrade the bridge contract: {err}")
+    });
 }
 
 /// Whitelist metadata to pass to [`whitelist_tokens`].
@@ -133,14 +274,62 @@ pub struct WhitelistMeta {
     pub cap: token::Amount,
     /// Token denomination.
     pub denom: u8,
+    /// Minimum number of confirmations of transfers of the token. If
+    /// [`None`], the global minimum confirmations of the bridge apply.
+    pub min_confirmations: Option<NonZeroU64>,
+}
+
+impl WhitelistMeta {
+    /// Create whitelist metadata subject to the global minimum
+    /// confirmations of the bridge, checking that `denom` is a valid
+    /// token denomination.
+    pub fn new(
+        cap: token::Amount,
+        denom: u8,
+    ) -> Result<Self, token::AmountParseError> {
+        validate_denom(denom)?;
+        Ok(Self {
+            cap,
+            denom,
+            min_confirmations: None,
+        })
+    }
+}
+
+/// Check that amounts with the given number of decimal places can be
+/// represented in 256 bits.
+fn validate_denom(denom: u8) -> Result<(), token::AmountParseError> {
+    token::DenominatedAmount::new(token::Amount::from_u64(1), 0.into())
+        .increase_precision(denom.into())
+        .map(|_| ())
+}
+
+/// Panic if the given whitelisted token has an invalid denomination.
+fn assert_valid_denom(asset: &EthAddress, denom: u8) {
+    if let Err(err) = validate_denom(denom) {
+        panic!(
+            "Invalid denomination {denom} for whitelisted token {asset}: {err}"
+        );
+    }
 }
 
 /// Whitelist the given Ethereum tokens.
+///
+/// Panics if any of the tokens has an invalid denomination.
 pub fn whitelist_tokens<L>(state: &mut TestState, token_list: L)
 where
     L: Into<HashMap<EthAddress, WhitelistMeta>>,
 {
-    for (asset, WhitelistMeta { cap, denom }) in token_list.into() {
+    for (
+        asset,
+        WhitelistMeta {
+            cap,
+            denom,
+            min_confirmations,
+        },
+    ) in token_list.into()
+    {
+        assert_valid_denom(&asset, denom);
         let cap_key = whitelist::Key {
             asset,
             suffix: whitelist::KeyType::Cap,
@@ -161,6 +350,62 @@ where
         }
         .into();
         state.write(&denom_key, denom).expect("Test failed");
+
+        if let Some(min_confirmations) = min_confirmations {
+            let min_confirmations_key = whitelist::Key {
+                asset,
+                suffix: whitelist::KeyType::MinConfirmations,
+            }
+            .into();
+            state
+                .write(
+                    &min_confirmations_key,
+                    MinimumConfirmations::from(min_confirmations),
+                )
+                .expect("Test failed");
+        }
+    }
+}
+
+/// Set whether the given Ethereum token is whitelisted, leaving the
+/// rest of its whitelist entries untouched.
+pub fn set_token_whitelisted(
+    state: &mut TestState,
+    asset: EthAddress,
+    whitelisted: bool,
+) {
+    let whitelisted_key = whitelist::Key {
+        asset,
+        suffix: whitelist::KeyType::Whitelisted,
+    }
+    .into();
+    state
+        .write(&whitelisted_key, whitelisted)
+        .expect("Test failed");
+}
+
+/// Remove the given Ethereum tokens from the whitelist.
+///
+/// If `delete_entries` is set, the cap, denomination and minimum
+/// confirmations of the tokens are deleted from storage as well.
+pub fn dewhitelist_tokens(
+    state: &mut TestState,
+    tokens: &[EthAddress],
+    delete_entries: bool,
+) {
+    for &asset in tokens {
+        set_token_whitelisted(state, asset, false);
+        if !delete_entries {
+            continue;
+        }
+        for suffix in [
+            whitelist::KeyType::Cap,
+            whitelist::KeyType::Denomination,
+            whitelist::KeyType::MinConfirmations,
+        ] {
+            let key = whitelist::Key { asset, suffix }.into();
+            state.delete(&key).expect("Test failed");
+        }
     }
 }
 
@@ -170,6 +415,84 @@ pub fn stored_keys_count(state: &TestState) -> usize {
     state.iter_prefix(&root).expect("Test failed").count()
 }
 
+/// Returns the number of Ethereum bridge related keys in `storage`
+/// which have values present.
+///
+/// This includes all keys under the Ethereum bridge and bridge pool
+/// storage subspaces (e.g. the whitelist and vote tallies), as well as
+/// the Ethereum bridge parameters.
+pub fn bridge_keys_count(state: &TestState) -> usize {
+    let subspace_keys: usize = [
+        crate::storage::prefix(),
+        Key::from(BRIDGE_POOL_ADDRESS.to_db_key()),
+    ]
+    .iter()
+    .map(|prefix| state.iter_prefix(prefix).expect("Test failed").count())
+    .sum();
+    let param_keys = [
+        crate::storage::active_key(),
+        crate::storage::min_confirmations_key(),
+        crate::storage::native_erc20_key(),
+        crate::storage::bridge_contract_key(),
+        crate::storage::eth_start_height_key(),
+        crate::storage::valset_upd_vote_height_offset_key(),
+    ]
+    .iter()
+    .filter(|key| state.has_key(key).expect("Test failed"))
+    .count();
+    subspace_keys + param_keys
+}
+
+/// A snapshot of the storage accesses performed against a [`TestState`].
+///
+/// Reads are only counted when they reach the DB, i.e. reads served from
+/// the write log are not accounted for, and a prefix iteration counts as a
+/// single read. Writes are counted as the number of keys modified in the
+/// block write log, so committing a block resets them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StorageAccessCounter {
+    reads: u64,
+    writes: usize,
+}
+
+impl StorageAccessCounter {
+    /// Take a snapshot of the storage accesses performed on `state`.
+    pub fn snapshot(state: &TestState) -> Self {
+        Self {
+            reads: state.db().subspace_reads(),
+            writes: state.write_log().block_modifications_count(),
+        }
+    }
+
+    /// Return the storage accesses performed since the `earlier` snapshot.
+    pub fn since(&self, earlier: &Self) -> Self {
+        Self {
+            reads: self.reads - earlier.reads,
+            writes: self.writes.saturating_sub(earlier.writes),
+        }
+    }
+
+    /// The number of storage reads that reached the DB.
+    pub fn reads(&self) -> u64 {
+        self.reads
+    }
+
+    /// The number of keys modified in the block write log.
+    pub fn writes(&self) -> usize {
+        self.writes
+    }
+}
+
+/// Return the epoch of the block at height `h`.
+///
+/// Panics if the epoch of `h` is not known.
+pub fn epoch_of_height(state: &TestState, h: BlockHeight) -> Epoch {
+    state
+        .get_epoch_at_height(h)
+        .expect("Test failed")
+        .unwrap_or_else(|| panic!("The epoch of block height {h} is unknown"))
+}
+
 /// Set up a [`TestState`] initialized at genesis with the given
 /// validators.
 pub fn setup_storage_with_validators(
@@ -181,11 +504,40 @@ pub fn setup_storage_with_validators(
     (state, all_keys)
 }
 
+/// Set up a [`TestState`] initialized at genesis with the given
+/// validators, and the Ethereum bridge bootstrapped with `params`.
+pub fn setup_storage_with_validators_and_bridge_config(
+    consensus_validators: HashMap<Address, token::Amount>,
+    params: EthereumBridgeParams,
+) -> (TestState, HashMap<Address, TestValidatorKeys>) {
+    let mut state = TestState::default();
+    let all_keys = init_storage_with_validators_and_bridge_config(
+        &mut state,
+        consensus_validators,
+        params,
+    );
+    (state, all_keys)
+}
+
 /// Set up a [`TestState`] initialized at genesis with the given
 /// validators.
 pub fn init_storage_with_validators(
     state: &mut TestState,
     consensus_validators: HashMap<Address, token::Amount>,
+) -> HashMap<Address, TestValidatorKeys> {
+    init_storage_with_validators_and_bridge_config(
+        state,
+        consensus_validators,
+        EthereumBridgeParamsBuilder::new().build(),
+    )
+}
+
+/// Set up a [`TestState`] initialized at genesis with the given
+/// validators, and the Ethereum bridge bootstrapped with `params`.
+pub fn init_storage_with_validators_and_bridge_config(
+    state: &mut TestState,
+    consensus_validators: HashMap<Address, token::Amount>,
+    params: EthereumBridgeParams,
 ) -> HashMap<Address, TestValidatorKeys> {
     // set last height to a reasonable value;
     // it should allow vote extensions to be cast
@@ -227,7 +579,7 @@ pub fn init_storage_with_validators(
         0.into(),
     )
     .expect("Test failed");
-    bootstrap_ethereum_bridge(state);
+    params.init_storage(state);
 
     for (validator, keys) in all_keys.iter() {
         let protocol_key = keys.protocol.ref_to();
@@ -244,6 +596,62 @@ pub fn init_storage_with_validators(
     all_keys
 }
 
+/// Set up a [`TestState`] initialized at genesis with the given
+/// validators, and advance it to `start_epoch`.
+///
+/// One block is committed per epoch, and the PoS validator sets are
+/// carried over as they would be by `finalize_block`, such that
+/// validator voting powers can be queried at `start_epoch`.
+pub fn init_storage_with_validators_at_epoch(
+    state: &mut TestState,
+    consensus_validators: HashMap<Address, token::Amount>,
+    start_epoch: Epoch,
+) -> HashMap<Address, TestValidatorKeys> {
+    let all_keys = init_storage_with_validators(state, consensus_validators);
+    let params = namada_proof_of_stake::storage::read_pos_params::<
+        _,
+        GovStore<_>,
+    >(state)
+    .expect("Test failed");
+
+    while state.in_mem().block.epoch < start_epoch {
+        advance_epoch(state, &params);
+    }
+
+    all_keys
+}
+
+/// Advance the given [`TestState`] to the next epoch, committing a
+/// block at the epoch boundary, and return the new epoch.
+///
+/// As in `finalize_block`, the PoS validator sets are carried over to
+/// the new pipeline epoch, and their total consensus stake is recomputed.
+pub fn advance_epoch(state: &mut TestState, params: &PosParams) -> Epoch {
+    let current_epoch = state.in_mem().block.epoch.next();
+    let height = state.in_mem().block.height;
+    state.in_mem_mut().block.epoch = current_epoch;
+    state.in_mem_mut().block.pred_epochs.new_epoch(height);
+
+    let pipeline_epoch = current_epoch + params.pipeline_len;
+    copy_validator_sets_and_positions(
+        state,
+        params,
+        current_epoch,
+        pipeline_epoch,
+    )
+    .expect("Test failed");
+    compute_and_store_total_consensus_stake::<_, GovStore<_>>(
+        state,
+        pipeline_epoch,
+    )
+    .expect("Test failed");
+
+    state.commit_block().expect("Test failed");
+    state.in_mem_mut().block.height += 1;
+
+    current_epoch
+}
+
 /// Commit a bridge pool root at a given height
 /// to storage.
 ///
@@ -259,11 +667,92 @@ pub fn commit_bridge_pool_root_at_height(
     state.delete(&get_key_from_hash(root)).unwrap();
 }
 
+/// Build a Merkle inclusion proof for the pending transfer with hash
+/// `transfer_hash`, against the bridge pool root committed at `height`.
+///
+/// The proof is built from the bridge pool tree stored in the DB, the same
+/// way as when relayers request proofs from the ledger. Returns `None` if
+/// the transfer is not in storage or was not in the pool at `height`.
+pub fn bridge_pool_inclusion_proof(
+    state: &TestState,
+    transfer_hash: &KeccakHash,
+    height: BlockHeight,
+) -> Option<BridgePoolProof> {
+    let key = get_key_from_hash(transfer_hash);
+    let transfer = state.read_bytes(&key).expect("Test failed")?;
+    let tree = state
+        .get_merkle_tree(height, Some(StoreType::BridgePool))
+        .expect("Test failed");
+    match tree.get_sub_tree_existence_proof(&[key], vec![transfer.as_slice()]) {
+        Ok(MembershipProof::BridgePool(proof)) => Some(proof),
+        Ok(MembershipProof::ICS23(_)) => {
+            unreachable!("Bridge pool keys have bespoke membership proofs")
+        }
+        Err(_) => None,
+    }
+}
+
+/// Commit a sequence of bridge pool roots to storage, each at its
+/// given height.
+///
+/// N.B. assumes the bridge pool is empty.
+///
+/// Panics if the heights are not strictly increasing.
+pub fn commit_bridge_pool_roots(
+    state: &mut TestState,
+    roots: &[(KeccakHash, BlockHeight)],
+) {
+    for window in roots.windows(2) {
+        let [(_, prev), (_, next)] = window else {
+            unreachable!("Windows have exactly two elements");
+        };
+        assert!(
+            prev < next,
+            "Bridge pool root heights must be strictly increasing, but {next} \
+             follows {prev}"
+        );
+    }
+    for (root, height) in roots {
+        commit_bridge_pool_root_at_height(state, root, *height);
+    }
+}
+
+/// Options used to set up a validator appended to storage with
+/// [`append_validators_to_storage_with`].
+#[derive(Default)]
+pub struct ValidatorSetupOpts {
+    /// Commission rate of the validator. Defaults to 5%.
+    pub commission_rate: Option<Dec>,
+    /// Max commission rate change of the validator. Defaults to 1%.
+    pub max_commission_rate_change: Option<Dec>,
+    /// Metadata of the validator.
+    pub metadata: Option<ValidatorMetaData>,
+    /// Keys of the validator. Fresh keys are generated if absent.
+    pub keys: Option<TestValidatorKeys>,
+}
+
 /// Append validators to storage at the current epoch
 /// offset by pipeline length.
 pub fn append_validators_to_storage(
     state: &mut TestState,
     consensus_validators: HashMap<Address, token::Amount>,
+) -> HashMap<Address, TestValidatorKeys> {
+    append_validators_to_storage_with(
+        state,
+        consensus_validators
+            .into_iter()
+            .map(|(validator, stake)| {
+                (validator, stake, ValidatorSetupOpts::default())
+            })
+            .collect(),
+    )
+}
+
+/// Append validators to storage at the current epoch
+/// offset by pipeline length, set up with the given options.
+pub fn append_validators_to_storage_with(
+    state: &mut TestState,
+    consensus_validators: Vec<(Address, token::Amount, ValidatorSetupOpts)>,
 ) -> HashMap<Address, TestValidatorKeys> {
     let current_epoch = state.in_mem().get_current_epoch().0;
 
@@ -276,8 +765,8 @@ pub fn append_validators_to_storage(
 
     let staking_token = staking_token_address(state);
 
-    for (validator, stake) in consensus_validators {
-        let keys = TestValidatorKeys::generate();
+    for (validator, stake, opts) in consensus_validators {
+        let keys = opts.keys.unwrap_or_else(TestValidatorKeys::generate);
 
         let consensus_key = &keys.consensus.ref_to();
         let protocol_key = &&keys.protocol.ref_to();
@@ -294,9 +783,13 @@ pub fn append_validators_to_storage(
                 eth_cold_key,
                 eth_hot_key,
                 current_epoch,
-                commission_rate: Dec::new(5, 2).unwrap(),
-                max_commission_rate_change: Dec::new(1, 2).unwrap(),
-                metadata: Default::default(),
+                commission_rate: opts
+                    .commission_rate
+                    .unwrap_or_else(|| Dec::new(5, 2).unwrap()),
+                max_commission_rate_change: opts
+                    .max_commission_rate_change
+                    .unwrap_or_else(|| Dec::new(1, 2).unwrap()),
+                metadata: opts.metadata.unwrap_or_default(),
                 offset_opt: Some(1),
             },
         )
@@ -333,5 +826,619 @@ pub fn append_validators_to_storage(
     all_keys
 }
 
+/// Write a validator set update proof to storage, signed by `signers`
+/// over the new set of `voting_powers` during `signing_epoch`, whose
+/// tally has not yet been seen by a quorum of validators.
+///
+/// Returns the keys of the written validator set update tally.
+///
+/// Panics if the signers hold enough voting power for the tally to
+/// be seen.
+pub fn write_partial_valset_upd_proof(
+    state: &mut TestState,
+    signing_epoch: Epoch,
+    signers: &[Address],
+    all_keys: &HashMap<Address, TestValidatorKeys>,
+    voting_powers: VotingPowersMap,
+) -> vote_tallies::Keys<EthereumProof<VotingPowersMap>> {
+    let vote_height = state
+        .ethbridge_queries()
+        .get_valset_upd_vote_height(signing_epoch)
+        .expect("Test failed")
+        .expect("Test failed");
+    let keys = vote_tallies::Keys::from(&signing_epoch.next());
+
+    let seen_by: Votes = signers
+        .iter()
+        .cloned()
+        .map(|validator| (validator, vote_height))
+        .collect();
+    let stakes = signers
+        .iter()
+        .map(|validator| {
+            let stake = read_validator_stake::<_, GovStore<_>>(
+                state,
+                validator,
+                signing_epoch,
+            )
+            .expect("Test failed");
+            ((validator.clone(), vote_height), stake)
+        })
+        .collect();
+    let tally =
+        votes::calculate_new::<_, _, GovStore<_>>(state, seen_by, &stakes)
+            .expect("Test failed");
+    assert!(
+        !tally.seen,
+        "The signers of a partial proof must not hold a quorum"
+    );
+
+    let mut proof = EthereumProof::new(voting_powers.clone());
+    proof.attach_signature_batch(signers.iter().map(|validator| {
+        let SignedVext(signed) = validator_set_update::Vext {
+            voting_powers: voting_powers.clone(),
+            validator_addr: validator.clone(),
+            signing_epoch,
+        }
+        .sign(&all_keys[validator].eth_bridge);
+        let addr_book = state
+            .ethbridge_queries()
+            .get_eth_addr_book::<GovStore<_>>(validator, Some(signing_epoch))
+            .expect("Test failed");
+        (addr_book, signed.sig)
+    }));
+
+    votes::storage::write(state, &keys, &proof, &tally, false)
+        .expect("Test failed");
+    keys
+}
+
+/// Build a validator set update digest over the new set of
+/// `voting_powers`, with the signatures of each of the given `signers`
+/// over a [`validator_set_update::Vext`] of `signing_epoch`.
+pub fn build_valset_upd_digest(
+    signers: &[(Address, &key::common::SecretKey)],
+    voting_powers: VotingPowersMap,
+    signing_epoch: Epoch,
+) -> VextDigest {
+    VextDigest {
+        signatures: signers
+            .iter()
+            .map(|(validator, eth_bridge_key)| {
+                let SignedVext(signed) = validator_set_update::Vext {
+                    voting_powers: voting_powers.clone(),
+                    validator_addr: validator.clone(),
+                    signing_epoch,
+                }
+                .sign(eth_bridge_key);
+                (validator.clone(), signed.sig)
+            })
+            .collect(),
+        voting_powers,
+    }
+}
+
 /// Gov impl type
 pub type GovStore<S> = namada_governance::Store<S>;
+
+#[cfg(test)]
+mod tests {
+    use namada_core::eth_abi::Encode;
+    use namada_core::eth_bridge_pool::{
+        GasFee, PendingTransfer, TransferToEthereum, TransferToEthereumKind,
+    };
+
+    use super::*;
+    use crate::storage::bridge_pool::{BridgePoolTree, get_pending_key};
+
+    /// Test that a digest built from several signers aggregates into a
+    /// proof carrying all of their signatures.
+    #[test]
+    fn test_build_valset_upd_digest() {
+        use crate::protocol::transactions::validator_set_update::{
+            SigVerification, aggregate_votes,
+        };
+
+        let validators = [
+            address::testing::established_address_1(),
+            address::testing::established_address_2(),
+            address::testing::established_address_3(),
+        ];
+        let (mut state, keys) = setup_storage_with_validators(
+            validators
+                .iter()
+                .map(|validator| {
+                    (validator.clone(), token::Amount::native_whole(100))
+                })
+                .collect(),
+        );
+        let last_height = state.in_mem().get_last_block_height();
+        let signing_epoch = epoch_of_height(&state, last_height);
+
+        let signers: Vec<_> = validators
+            .iter()
+            .map(|validator| (validator.clone(), &keys[validator].eth_bridge))
+            .collect();
+        let digest = build_valset_upd_digest(
+            &signers,
+            VotingPowersMap::new(),
+            signing_epoch,
+        );
+        assert_eq!(digest.signatures.len(), 3);
+
+        aggregate_votes::<_, _, GovStore<_>>(
+            &mut state,
+            digest,
+            signing_epoch,
+            SigVerification::Enabled,
+        )
+        .expect("Test failed");
+
+        let valset_upd_keys = vote_tallies::Keys::from(&signing_epoch.next());
+        let tally = votes::storage::read(&state, &valset_upd_keys)
+            .expect("Test failed");
+        assert!(tally.seen);
+        let proof: EthereumProof<VotingPowersMap> =
+            votes::storage::read_body(&state, &valset_upd_keys)
+                .expect("Test failed");
+        assert_eq!(proof.signatures.len(), 3);
+    }
+
+    /// Test that validators appended with setup options are given the
+    /// provided keys and commission rates.
+    #[test]
+    fn test_append_validators_to_storage_with() {
+        let (mut state, _) = setup_default_storage();
+        let validator = address::testing::established_address_2();
+        let keys = TestValidatorKeys::generate();
+        let eth_hot_key = keys.eth_bridge.ref_to();
+        let commission_rate = Dec::new(1, 1).unwrap();
+
+        let all_keys = append_validators_to_storage_with(
+            &mut state,
+            vec![(
+                validator.clone(),
+                token::Amount::native_whole(100),
+                ValidatorSetupOpts {
+                    commission_rate: Some(commission_rate),
+                    keys: Some(keys),
+                    ..Default::default()
+                },
+            )],
+        );
+        assert_eq!(all_keys[&validator].eth_bridge.ref_to(), eth_hot_key);
+
+        let params = namada_proof_of_stake::storage::read_pos_params::<
+            _,
+            GovStore<_>,
+        >(&state)
+        .expect("Test failed");
+        let epoch = state.in_mem().get_current_epoch().0 + params.pipeline_len;
+        let stored_hot_key =
+            namada_proof_of_stake::storage::validator_eth_hot_key_handle(
+                &validator,
+            )
+            .get(&state, epoch, &params)
+            .expect("Test failed");
+        assert_eq!(stored_hot_key, Some(eth_hot_key));
+        let stored_rate =
+            namada_proof_of_stake::storage::validator_commission_rate_handle(
+                &validator,
+            )
+            .get(&state, epoch, &params)
+            .expect("Test failed");
+        assert_eq!(stored_rate, Some(commission_rate));
+    }
+
+    /// Test that a sequence of bridge pool roots is committed in order.
+    #[test]
+    fn test_commit_bridge_pool_roots() {
+        let (mut state, _) = setup_default_storage();
+        let roots = [
+            (KeccakHash([1; 32]), BlockHeight(2)),
+            (KeccakHash([2; 32]), BlockHeight(3)),
+            (KeccakHash([3; 32]), BlockHeight(5)),
+        ];
+        commit_bridge_pool_roots(&mut state, &roots);
+
+        assert_eq!(state.in_mem().block.height, BlockHeight(5));
+        for (root, _) in &roots {
+            assert!(
+                !state
+                    .has_key(&get_key_from_hash(root))
+                    .expect("Test failed")
+            );
+        }
+    }
+
+    /// Test that inclusion proofs of pending transfers verify against the
+    /// bridge pool root committed at the requested height.
+    #[test]
+    fn test_bridge_pool_inclusion_proof() {
+        let (mut state, _) = setup_default_storage();
+        let transfer = |amount: u64| PendingTransfer {
+            transfer: TransferToEthereum {
+                kind: TransferToEthereumKind::Erc20,
+                asset: EthAddress([1; 20]),
+                sender: address::testing::established_address_1(),
+                recipient: EthAddress([2; 20]),
+                amount: token::Amount::from_u64(amount),
+            },
+            gas_fee: GasFee {
+                token: address::testing::nam(),
+                amount: token::Amount::from_u64(1),
+                payer: address::testing::established_address_1(),
+            },
+        };
+        let transfers = [transfer(1), transfer(2)];
+        let mut pool = BridgePoolTree::default();
+        let height = state.in_mem().block.height;
+        for transfer in &transfers {
+            let key = get_pending_key(transfer);
+            state.write(&key, transfer).expect("Test failed");
+            pool.insert_key(&key, height).expect("Test failed");
+        }
+        state.commit_block().expect("Test failed");
+
+        let proof = bridge_pool_inclusion_proof(
+            &state,
+            &transfers[0].keccak256(),
+            height,
+        )
+        .expect("Test failed");
+        assert_eq!(proof.leaves, vec![transfers[0].clone()]);
+        assert!(proof.verify(pool.root()));
+        assert!(!proof.verify(KeccakHash([0; 32])));
+
+        // transfers which are not in the pool have no proof
+        assert!(
+            bridge_pool_inclusion_proof(
+                &state,
+                &transfer(3).keccak256(),
+                height
+            )
+            .is_none()
+        );
+    }
+
+    /// Test that the bridge contract can be upgraded to its next version.
+    #[test]
+    fn test_upgrade_bridge_contract() {
+        let (mut state, _) = setup_default_storage();
+        let key = crate::storage::bridge_contract_key();
+        let contract: UpgradeableContract =
+            state.read(&key).expect("Test failed").expect("Test failed");
+
+        let new_address = EthAddress([0xbb; 20]);
+        upgrade_bridge_contract(
+            &mut state,
+            new_address,
+            contract.version.next(),
+        );
+
+        let upgraded: UpgradeableContract =
+            state.read(&key).expect("Test failed").expect("Test failed");
+        assert_eq!(upgraded.address, new_address);
+        assert_eq!(upgraded.version, contract.version.next());
+    }
+
+    /// Test that downgrading the bridge contract panics.
+    #[test]
+    #[should_panic(expected = "Cannot downgrade the bridge contract")]
+    fn test_upgrade_bridge_contract_downgrade() {
+        let (mut state, _) = setup_default_storage();
+        upgrade_bridge_contract(
+            &mut state,
+            EthAddress([0xbb; 20]),
+            ContractVersion::default().next(),
+        );
+        upgrade_bridge_contract(
+            &mut state,
+            EthAddress([0xcc; 20]),
+            ContractVersion::default(),
+        );
+    }
+
+    /// Test that committing bridge pool roots at heights which are not
+    /// strictly increasing panics.
+    #[test]
+    #[should_panic(expected = "strictly increasing")]
+    fn test_commit_bridge_pool_roots_unordered() {
+        let (mut state, _) = setup_default_storage();
+        commit_bridge_pool_roots(
+            &mut state,
+            &[
+                (KeccakHash([1; 32]), BlockHeight(3)),
+                (KeccakHash([2; 32]), BlockHeight(3)),
+            ],
+        );
+    }
+
+    /// Test that whitelisting writes three keys per asset.
+    #[test]
+    fn test_whitelist_tokens() {
+        let (mut state, _) = setup_default_storage();
+        let keys_before = stored_keys_count(&state);
+
+        let token_list: HashMap<_, _> = (0..50u8)
+            .map(|i| {
+                let meta = WhitelistMeta {
+                    cap: token::Amount::from(u64::from(i) + 1),
+                    denom: 18,
+                    min_confirmations: None,
+                };
+                (EthAddress([i; 20]), meta)
+            })
+            .collect();
+        whitelist_tokens(&mut state, token_list);
+
+        assert_eq!(stored_keys_count(&state), keys_before + 150);
+        let whitelisted_key = whitelist::Key {
+            asset: EthAddress([49; 20]),
+            suffix: whitelist::KeyType::Whitelisted,
+        }
+        .into();
+        assert_eq!(
+            state.read::<bool>(&whitelisted_key).expect("Test failed"),
+            Some(true)
+        );
+    }
+
+    /// Test that whitelist metadata can only be built with valid
+    /// denominations.
+    #[test]
+    fn test_whitelist_meta_denom_validation() {
+        let cap = token::Amount::from_u64(100);
+        assert!(WhitelistMeta::new(cap, 0).is_ok());
+        assert!(WhitelistMeta::new(cap, 18).is_ok());
+        assert!(WhitelistMeta::new(cap, 77).is_ok());
+        assert!(WhitelistMeta::new(cap, 78).is_err());
+        assert!(WhitelistMeta::new(cap, u8::MAX).is_err());
+    }
+
+    /// Test that whitelisting a token with an invalid denomination
+    /// panics.
+    #[test]
+    #[should_panic(expected = "Invalid denomination 78")]
+    fn test_whitelist_tokens_invalid_denom() {
+        let (mut state, _) = setup_default_storage();
+        whitelist_tokens(
+            &mut state,
+            [(
+                EthAddress([1; 20]),
+                WhitelistMeta {
+                    cap: token::Amount::from_u64(100),
+                    denom: 78,
+                    min_confirmations: None,
+                },
+            )],
+        );
+    }
+
+    /// Test that the params built with [`EthereumBridgeParamsBuilder`]
+    /// are written to storage.
+    #[test]
+    fn test_bootstrap_with_builder() {
+        use namada_core::ethereum_events::testing::DAI_ERC20_ETH_ADDRESS;
+        use namada_core::token::DenominatedAmount;
+
+        use crate::storage::eth_bridge_queries::EthBridgeQueries;
+        use crate::storage::parameters::EthereumOracleConfig;
+
+        let mut state = TestState::default();
+        let params = EthereumBridgeParamsBuilder::new()
+            .with_min_confirmations(NonZeroU64::new(42).unwrap())
+            .with_bridge_address(EthAddress([3; 20]))
+            .with_erc20_whitelist(vec![Erc20WhitelistEntry {
+                token_address: DAI_ERC20_ETH_ADDRESS,
+                token_cap: DenominatedAmount::new(
+                    token::Amount::from(1_000u64),
+                    18u8.into(),
+                ),
+                min_confirmations: None,
+            }])
+            .with_eth_start_height(5u64.into())
+            .bootstrap(&mut state);
+
+        let config = EthereumOracleConfig::read(&state).expect("Test failed");
+        assert_eq!(config, EthereumOracleConfig::from(params));
+        assert_eq!(u64::from(NonZeroU64::from(config.min_confirmations)), 42);
+        assert_eq!(config.contracts.bridge.address, EthAddress([3; 20]));
+        assert!(
+            state
+                .ethbridge_queries()
+                .is_token_whitelisted(&DAI_ERC20_ETH_ADDRESS)
+        );
+    }
+
+    /// Test that validators and a custom bridge config are both set up
+    /// at genesis.
+    #[test]
+    fn test_setup_storage_with_validators_and_bridge_config() {
+        use namada_core::ethereum_events::testing::DAI_ERC20_ETH_ADDRESS;
+        use namada_core::token::DenominatedAmount;
+
+        use crate::storage::eth_bridge_queries::EthBridgeQueries;
+
+        let params = EthereumBridgeParamsBuilder::new()
+            .with_erc20_whitelist(vec![Erc20WhitelistEntry {
+                token_address: DAI_ERC20_ETH_ADDRESS,
+                token_cap: DenominatedAmount::new(
+                    token::Amount::from(1_000u64),
+                    18u8.into(),
+                ),
+                min_confirmations: None,
+            }])
+            .build();
+        let (validator, stake) = default_validator();
+        let (state, keys) = setup_storage_with_validators_and_bridge_config(
+            HashMap::from_iter([(validator.clone(), stake)]),
+            params,
+        );
+
+        assert!(keys.contains_key(&validator));
+        assert!(
+            state
+                .ethbridge_queries()
+                .is_token_whitelisted(&DAI_ERC20_ETH_ADDRESS)
+        );
+        let pos_params = namada_proof_of_stake::storage::read_pos_params::<
+            _,
+            GovStore<_>,
+        >(&state)
+        .expect("Test failed");
+        let total_stake = namada_proof_of_stake::get_total_consensus_stake(
+            &state,
+            Epoch(0),
+            &pos_params,
+        )
+        .expect("Test failed");
+        assert_eq!(total_stake, stake);
+    }
+
+    /// Test that the genesis block height maps to the genesis epoch.
+    #[test]
+    fn test_epoch_of_genesis_height() {
+        let (state, _) = setup_default_storage();
+        assert_eq!(
+            epoch_of_height(&state, BlockHeight::first()),
+            Epoch::default()
+        );
+    }
+
+    /// Test the number of Ethereum bridge keys written when
+    /// bootstrapping the bridge.
+    #[test]
+    fn test_bridge_keys_count_after_bootstrap() {
+        let mut state = TestState::default();
+        assert_eq!(bridge_keys_count(&state), 0);
+
+        bootstrap_ethereum_bridge(&mut state);
+        // five parameters, plus the bridge pool nonce
+        assert_eq!(bridge_keys_count(&state), 6);
+
+        // the whitelisted, cap and denomination keys of the token
+        whitelist_tokens(
+            &mut state,
+            HashMap::from_iter([(
+                EthAddress([1; 20]),
+                WhitelistMeta {
+                    cap: token::Amount::from_u64(100),
+                    denom: 18,
+                    min_confirmations: None,
+                },
+            )]),
+        );
+        assert_eq!(bridge_keys_count(&state), 6 + 3);
+    }
+
+    /// Test that storage can be initialized at a later epoch, with
+    /// the validator set stake carried over to it.
+    #[test]
+    fn test_init_storage_with_validators_at_epoch() {
+        let mut state = TestState::default();
+        let (validator, stake) = default_validator();
+        init_storage_with_validators_at_epoch(
+            &mut state,
+            HashMap::from_iter([(validator, stake)]),
+            Epoch(5),
+        );
+
+        assert_eq!(state.in_mem().get_current_epoch().0, Epoch(5));
+        let last_height = state.in_mem().get_last_block_height();
+        assert_eq!(epoch_of_height(&state, last_height), Epoch(5));
+
+        let params = namada_proof_of_stake::storage::read_pos_params::<
+            _,
+            GovStore<_>,
+        >(&state)
+        .expect("Test failed");
+        let total_stake = namada_proof_of_stake::get_total_consensus_stake(
+            &state,
+            Epoch(5),
+            &params,
+        )
+        .expect("Test failed");
+        assert_eq!(total_stake, stake);
+    }
+
+    /// Test that advancing an epoch moves the block to the next epoch,
+    /// and carries the total consensus stake over to the new pipeline
+    /// epoch.
+    #[test]
+    fn test_advance_epoch() {
+        let mut state = TestState::default();
+        let (validator, stake) = default_validator();
+        init_storage_with_validators(
+            &mut state,
+            HashMap::from_iter([(validator, stake)]),
+        );
+        let params = namada_proof_of_stake::storage::read_pos_params::<
+            _,
+            GovStore<_>,
+        >(&state)
+        .expect("Test failed");
+        let height = state.in_mem().block.height;
+
+        let new_epoch = advance_epoch(&mut state, &params);
+
+        assert_eq!(new_epoch, Epoch(1));
+        assert_eq!(state.in_mem().get_current_epoch().0, new_epoch);
+        assert_eq!(state.in_mem().block.height, height.next_height());
+        assert_eq!(epoch_of_height(&state, height), new_epoch);
+
+        let total_stake = namada_proof_of_stake::get_total_consensus_stake(
+            &state,
+            new_epoch + params.pipeline_len,
+            &params,
+        )
+        .expect("Test failed");
+        assert_eq!(total_stake, stake);
+    }
+
+    /// Test that tokens can be removed from the whitelist, either keeping
+    /// or deleting the rest of their whitelist entries.
+    #[test]
+    fn test_dewhitelist_tokens() {
+        let mut state = TestState::default();
+        let kept = EthAddress([1; 20]);
+        let deleted = EthAddress([2; 20]);
+        let meta = || WhitelistMeta {
+            cap: token::Amount::from_u64(1_000),
+            denom: 18,
+            min_confirmations: NonZeroU64::new(10),
+        };
+        whitelist_tokens(&mut state, [(kept, meta()), (deleted, meta())]);
+
+        let key = |asset, suffix| Key::from(whitelist::Key { asset, suffix });
+        let is_whitelisted = |state: &TestState, asset| {
+            state
+                .read::<bool>(&key(asset, whitelist::KeyType::Whitelisted))
+                .expect("Test failed")
+        };
+
+        dewhitelist_tokens(&mut state, &[kept], false);
+        dewhitelist_tokens(&mut state, &[deleted], true);
+
+        assert_eq!(is_whitelisted(&state, kept), Some(false));
+        assert_eq!(is_whitelisted(&state, deleted), Some(false));
+        for suffix in [
+            whitelist::KeyType::Cap,
+            whitelist::KeyType::Denomination,
+            whitelist::KeyType::MinConfirmations,
+        ] {
+            assert!(
+                state
+                    .has_key(&key(kept, suffix.clone()))
+                    .expect("Test failed")
+            );
+            assert!(
+                !state.has_key(&key(deleted, suffix)).expect("Test failed")
+            );
+        }
+
+        // tokens can be whitelisted again
+        set_token_whitelisted(&mut state, kept, true);
+        assert_eq!(is_whitelisted(&state, kept), Some(true));
+    }
+}
@@ -119,6 +119,15 @@ const MASP_CONVERT_CHECK_GAS_RAW: u64 = 188_590;
 const MASP_OUTPUT_CHECK_GAS_RAW: u64 = 204_430;
 // The cost to run the final masp check in the bundle
 const MASP_FINAL_CHECK_GAS_RAW: u64 = 43;
+// PROVISIONAL: the following costs are placeholders, not derived from any
+// benchmark. They account for the work done by the MASP VP on the state before
+// verifying the proofs, and must be replaced with benchmarked values.
+// The cost to check the anchor and nullifier of a masp spend note
+const MASP_SPEND_STATE_CHECK_GAS_RAW: u64 = 50_000;
+// The cost to check the anchor of a masp convert note
+const MASP_CONVERT_STATE_CHECK_GAS_RAW: u64 = 25_000;
+// The cost to append a masp output note to the commitment tree
+const MASP_OUTPUT_STATE_CHECK_GAS_RAW: u64 = 100_000;
 // =============================================================================
 
 // A correction factor for non-WASM-opcodes costs. We can see that the
@@ -207,6 +216,18 @@ pub const MASP_OUTPUT_CHECK_GAS: u64 =
 /// The cost to run the final masp check in the bundle
 pub const MASP_FINAL_CHECK_GAS: u64 =
     MASP_FINAL_CHECK_GAS_RAW * GAS_COST_CORRECTION;
+/// The cost to check the anchor and nullifier of a masp spend note
+/// (provisional, not benchmarked)
+pub const MASP_SPEND_STATE_CHECK_GAS: u64 =
+    MASP_SPEND_STATE_CHECK_GAS_RAW * GAS_COST_CORRECTION;
+/// The cost to check the anchor of a masp convert note (provisional, not
+/// benchmarked)
+pub const MASP_CONVERT_STATE_CHECK_GAS: u64 =
+    MASP_CONVERT_STATE_CHECK_GAS_RAW * GAS_COST_CORRECTION;
+/// The cost to append a masp output note to the commitment tree
+/// (provisional, not benchmarked)
+pub const MASP_OUTPUT_STATE_CHECK_GAS: u64 =
+    MASP_OUTPUT_STATE_CHECK_GAS_RAW * GAS_COST_CORRECTION;
 // =============================================================================
 
 /// Gas module result for functions that may fail
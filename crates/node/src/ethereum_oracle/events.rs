@@ -1,6 +1,8 @@
 pub mod eth_events {
     #![allow(dead_code)]
+    use std::collections::BTreeMap;
     use std::fmt::Debug;
+    use std::num::NonZeroU64;
     use std::str::FromStr;
 
     use ethbridge_bridge_events::{
@@ -113,6 +115,31 @@ pub mod eth_events {
             })
         }
 
+        /// Raise the number of confirmations of this event to the
+        /// minimum confirmations configured for any of the ERC20 assets
+        /// it transfers to Namada, if these are higher.
+        pub fn apply_token_min_confirmations(
+            &mut self,
+            token_min_confirmations: &BTreeMap<EthAddress, NonZeroU64>,
+        ) {
+            let EthereumEvent::TransfersToNamada { transfers, .. } =
+                &self.event
+            else {
+                return;
+            };
+            for transfer in transfers {
+                if let Some(min_confirmations) =
+                    token_min_confirmations.get(&transfer.asset)
+                {
+                    let min_confirmations: Uint256 =
+                        u64::from(*min_confirmations).into();
+                    if min_confirmations > self.confirmations {
+                        self.confirmations = min_confirmations;
+                    }
+                }
+            }
+        }
+
         /// Check if the minimum number of confirmations has been
         /// reached at the input block height.
         pub fn is_confirmed(&self, height: &Uint256) -> bool {
@@ -342,6 +369,43 @@ pub mod eth_events {
             Ok(())
         }
 
+        /// Test that the minimum confirmations configured for the assets
+        /// of a "Transfer to Namada" event are enforced, if higher than
+        /// the confirmations the event already requires.
+        #[test]
+        fn test_token_min_confirmations_enforced() {
+            let volatile = EthAddress([1; 20]);
+            let stable = EthAddress([2; 20]);
+            let token_min_confirmations = BTreeMap::from([
+                (volatile, NonZeroU64::new(250).unwrap()),
+                (stable, NonZeroU64::new(5).unwrap()),
+            ]);
+            let pending_event = |asset| PendingEvent {
+                confirmations: 100u64.into(),
+                block_height: 123u64.into(),
+                event: EthereumEvent::TransfersToNamada {
+                    nonce: 0u64.into(),
+                    transfers: vec![TransferToNamada {
+                        amount: Amount::from(1u64),
+                        asset,
+                        receiver: Address::from_str(
+                            "tnam1q87teqzjytwa9xd9qk8u558xxnrwuzdjzs7zvhzr",
+                        )
+                        .expect("Test failed"),
+                    }],
+                },
+            };
+
+            let mut event = pending_event(volatile);
+            event.apply_token_min_confirmations(&token_min_confirmations);
+            assert_eq!(event.confirmations, Uint256::from(250u64));
+
+            // lower per token values never relax the global minimum
+            let mut event = pending_event(stable);
+            event.apply_token_min_confirmations(&token_min_confirmations);
+            assert_eq!(event.confirmations, Uint256::from(100u64));
+        }
+
         /// Test decoding a "Transfer to Namada" Ethereum event.
         #[test]
         fn test_transfer_to_namada_decode() {
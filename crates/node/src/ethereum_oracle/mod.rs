@@ -535,7 +535,12 @@ async fn process_events_in_block<C: RpcClient>(
                         &log,
                         u64::from(config.min_confirmations).into(),
                     ) {
-                        Ok(event) => Some(event),
+                        Ok(mut event) => {
+                            event.apply_token_min_confirmations(
+                                &config.token_min_confirmations,
+                            );
+                            Some(event)
+                        }
                         Err(error) => {
                             tracing::error!(
                                 ?error,
@@ -1135,7 +1135,32 @@ where
                 state,
                 validator_set_update::VextDigest::singleton(ext),
                 signing_epoch,
+                // NB: the signature was verified when the vote
+                // extension was validated
+                transactions::validator_set_update::SigVerification::Disabled,
             )
+            .map(|outcome| {
+                use transactions::validator_set_update::AggregationOutcome;
+                match &outcome {
+                    AggregationOutcome::AlreadySeen => tracing::debug!(
+                        %signing_epoch,
+                        "Ignored votes on an already seen validator set update"
+                    ),
+                    AggregationOutcome::NoNewVotes => tracing::debug!(
+                        %signing_epoch,
+                        "No new validator set update votes were aggregated"
+                    ),
+                    AggregationOutcome::Updated(_) => tracing::debug!(
+                        %signing_epoch,
+                        "Aggregated validator set update votes"
+                    ),
+                    AggregationOutcome::Completed(_) => tracing::info!(
+                        %signing_epoch,
+                        "Completed the validator set update proof"
+                    ),
+                }
+                outcome.into_tx_result()
+            })
             .map_err(Error::ProtocolTxError)
         }
         EthereumTxData::EthereumEvents(_)
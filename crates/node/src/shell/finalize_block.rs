@@ -189,10 +189,10 @@ where
         {
             let updated_tree = CommitmentTree::<Node>::try_from_slice(value)
                 .into_storage_result()?;
-            let anchor_key = token::storage_key::masp_commitment_anchor_key(
+            token::record_commitment_anchor(
+                &mut self.state,
                 updated_tree.root(),
-            );
-            self.state.write(&anchor_key, ())?;
+            )?;
         }
 
         if update_for_tendermint {
@@ -401,6 +401,9 @@ where
 pub fn is_key_diff_storable(key: &namada_sdk::storage::Key) -> bool {
     !(token::storage_key::is_masp_key(key)
         && *key != token::storage_key::masp_convert_anchor_key()
+        && *key != token::storage_key::masp_convert_anchor_epoch_key()
+        && *key != token::storage_key::masp_recent_convert_anchors_key()
+        && *key != token::storage_key::masp_recent_commitment_anchors_key()
         && *key != token::storage_key::masp_token_map_key()
         && *key != token::storage_key::masp_assets_hash_key()
         && token::storage_key::is_masp_commitment_anchor_key(key).is_none()
@@ -1037,6 +1040,15 @@ where
             );
             let config = eth_bridge::oracle::config::Config {
                 min_confirmations: config.min_confirmations.into(),
+                token_min_confirmations: self
+                    .state
+                    .ethbridge_queries()
+                    .get_tokens_min_confirmations()
+                    .into_iter()
+                    .map(|(asset, min_confirmations)| {
+                        (asset, min_confirmations.into())
+                    })
+                    .collect(),
                 bridge_contract: config.contracts.bridge.address,
                 start_block,
                 active,
@@ -25,6 +25,9 @@ struct Keys {
     native_erc20: &'static str,
     /// Sub-lkey for storing the Ethereum address of the bridge contract.
     bridge_contract_address: &'static str,
+    /// Sub-key for storing the number of blocks after the start of an
+    /// epoch at which validator set update votes are cast.
+    valset_upd_vote_height_offset: &'static str,
     // ========================================
     // Core parameters
     // ========================================
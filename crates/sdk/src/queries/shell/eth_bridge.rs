@@ -846,7 +846,9 @@ mod test_ethbridge_router {
         GasFee, TransferToEthereum, TransferToEthereumKind,
     };
     use namada_core::voting_power::EthBridgeVotingPower;
-    use namada_ethereum_bridge::protocol::transactions::validator_set_update::aggregate_votes;
+    use namada_ethereum_bridge::protocol::transactions::validator_set_update::{
+        SigVerification, aggregate_votes,
+    };
     use namada_ethereum_bridge::storage::bridge_pool::{
         BridgePoolTree, get_pending_key, get_signed_root_key,
     };
@@ -981,8 +983,10 @@ mod test_ethbridge_router {
             &mut client.state,
             validator_set_update::VextDigest::singleton(vext.clone()),
             0.into(),
+            SigVerification::Disabled,
         )
-        .expect("Test failed");
+        .expect("Test failed")
+        .into_tx_result();
         assert!(!tx_result.changed_keys.is_empty());
 
         // commit the changes
@@ -34,7 +34,7 @@ use namada_systems::{parameters, trans_token};
 use crate::storage_key::{
     is_masp_conversion_key, is_masp_scheduled_reward_precision_key,
     masp_assets_hash_key, masp_conversion_key_prefix,
-    masp_scheduled_base_native_precision_key,
+    masp_recent_convert_anchors_key, masp_scheduled_base_native_precision_key,
     masp_scheduled_reward_precision_key_prefix, masp_token_map_key,
 };
 use crate::storage_key::{
@@ -578,6 +578,34 @@ where
     Ok(denom)
 }
 
+/// The number of most recent convert anchors accepted by the MASP VP,
+/// including the current one
+pub const RECENT_CONVERT_ANCHORS: usize = 2;
+
+#[cfg(any(feature = "multicore", test))]
+/// Push the given convert anchor to the front of the most recent ones, so
+/// that transactions built against the previous conversion tree remain
+/// valid right after it gets updated.
+fn record_recent_convert_anchor<S>(storage: &mut S, anchor: Hash) -> Result<()>
+where
+    S: StorageWrite + StorageRead,
+{
+    let key = masp_recent_convert_anchors_key();
+    // Seed the recent anchors with the current one, if they are missing
+    let mut recent_anchors: Vec<Hash> = match storage.read(&key)? {
+        Some(recent_anchors) => recent_anchors,
+        None => storage
+            .read(&crate::storage_key::masp_convert_anchor_key())?
+            .into_iter()
+            .collect(),
+    };
+    if recent_anchors.first() != Some(&anchor) {
+        recent_anchors.insert(0, anchor);
+        recent_anchors.truncate(RECENT_CONVERT_ANCHORS);
+    }
+    storage.write(&key, recent_anchors)
+}
+
 #[cfg(any(feature = "multicore", test))]
 /// Apply the conversion updates that are in storage to the in memory structure
 /// and delete them.
@@ -884,12 +912,16 @@ where
     storage.conversion_state_mut().tree =
         FrozenCommitmentTree::merge(&tree_parts);
     // Update the anchor in storage
+    let anchor = namada_core::hash::Hash(
+        bls12_381::Scalar::from(storage.conversion_state().tree.root())
+            .to_bytes(),
+    );
+    record_recent_convert_anchor(storage, anchor)?;
+    storage.write(&crate::storage_key::masp_convert_anchor_key(), anchor)?;
+    // Record the masp epoch this anchor corresponds to
     storage.write(
-        &crate::storage_key::masp_convert_anchor_key(),
-        namada_core::hash::Hash(
-            bls12_381::Scalar::from(storage.conversion_state().tree.root())
-                .to_bytes(),
-        ),
+        &crate::storage_key::masp_convert_anchor_epoch_key(),
+        masp_epoch,
     )?;
 
     if !masp_reward_keys.contains(&native_token) {
@@ -949,10 +981,11 @@ mod tests {
     use std::str::FromStr;
 
     use namada_core::address;
+    use namada_core::chain::Epoch;
     use namada_core::collections::HashMap;
     use namada_core::dec::testing::arb_non_negative_dec;
     use namada_core::token::testing::arb_amount;
-    use namada_state::testing::TestStorage;
+    use namada_state::testing::{TestState, TestStorage};
     use namada_trans_token::storage_key::{balance_key, minted_balance_key};
     use namada_trans_token::write_denom;
     use proptest::prelude::*;
@@ -1042,6 +1075,57 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_convert_anchor_epoch() {
+        let mut s = TestState::default();
+        namada_parameters::init_test_storage(&mut s).unwrap();
+        let native_token = s.get_native_token().unwrap();
+        write_denom(&mut s, &native_token, 6.into()).unwrap();
+        // The genesis anchor corresponds to the first masp epoch
+        assert_eq!(
+            crate::masp_convert_anchor_epoch(&s).unwrap(),
+            MaspEpoch::zero()
+        );
+
+        // Move to the third masp epoch and update the conversion tree
+        let masp_epoch_multiplier =
+            namada_parameters::read_masp_epoch_multiplier_parameter(&s)
+                .unwrap();
+        s.in_mem_mut().block.epoch = Epoch(3 * masp_epoch_multiplier);
+        update_allowed_conversions::<
+            _,
+            namada_parameters::Store<_>,
+            namada_trans_token::Store<_>,
+        >(&mut s)
+        .unwrap();
+
+        assert_eq!(
+            crate::masp_convert_anchor_epoch(&s).unwrap(),
+            MaspEpoch::new(3)
+        );
+    }
+
+    #[test]
+    fn test_recent_convert_anchors() {
+        let mut s = TestState::default();
+        let anchor = |i: u8| Hash([i; 32]);
+        s.write(&crate::storage_key::masp_convert_anchor_key(), anchor(0))
+            .unwrap();
+
+        // The current anchor seeds the recent ones
+        record_recent_convert_anchor(&mut s, anchor(1)).unwrap();
+        let recent: Vec<Hash> =
+            s.read(&masp_recent_convert_anchors_key()).unwrap().unwrap();
+        assert_eq!(recent, vec![anchor(1), anchor(0)]);
+
+        // Only the most recent anchors are kept, without duplicates
+        record_recent_convert_anchor(&mut s, anchor(2)).unwrap();
+        record_recent_convert_anchor(&mut s, anchor(2)).unwrap();
+        let recent: Vec<Hash> =
+            s.read(&masp_recent_convert_anchors_key()).unwrap().unwrap();
+        assert_eq!(recent, vec![anchor(2), anchor(1)]);
+    }
+
     pub fn tokens() -> HashMap<Address, (&'static str, Denomination)> {
         vec![
             (address::testing::nam(), ("nam", 6.into())),
@@ -17,7 +17,9 @@ use masp_primitives::consensus::MainNetwork as Network;
 #[cfg(not(feature = "mainnet"))]
 use masp_primitives::consensus::TestNetwork as Network;
 use masp_primitives::convert::AllowedConversion;
-use masp_primitives::merkle_tree::{IncrementalWitness, MerklePath};
+use masp_primitives::merkle_tree::{
+    CommitmentTree, IncrementalWitness, MerklePath,
+};
 use masp_primitives::sapling::keys::FullViewingKey;
 use masp_primitives::sapling::{Diversifier, Node, ViewingKey};
 use masp_primitives::transaction::Transaction;
@@ -51,10 +53,17 @@ pub use crate::masp::shielded_sync::dispatcher::{Dispatcher, DispatcherCache};
 pub use crate::masp::shielded_sync::{
     ShieldedSyncConfig, ShieldedSyncConfigBuilder, utils,
 };
+use crate::storage_key::{
+    masp_commitment_anchor_key, masp_commitment_tree_key,
+};
 pub use crate::validation::{
     CONVERT_NAME, ENV_VAR_MASP_PARAMS_DIR, OUTPUT_NAME, PVKs, SPEND_NAME,
     partial_deauthorize, preload_verifying_keys,
 };
+use crate::vp::{
+    MaspStorageRead, accepted_convert_anchors,
+    is_within_anchor_retention_window,
+};
 
 /// Randomness seed for MASP integration tests to build proofs with
 /// deterministic rng.
@@ -323,11 +332,97 @@ pub enum ContextSyncStatus {
     Speculative,
 }
 
+/// An anchor of a shielded transaction that the MASP VP would reject
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StaleAnchorWarning {
+    /// A spend description refers to a note commitment tree root that was
+    /// never published
+    Spend {
+        /// The anchor of the spend description
+        anchor: Node,
+        /// The root of the current note commitment tree, if any
+        current: Option<Node>,
+    },
+    /// A convert description refers to a conversion tree root other than the
+    /// current or a recent one
+    Convert {
+        /// The anchor of the convert description
+        anchor: Node,
+        /// The current conversion tree anchor, if any
+        current: Option<Node>,
+    },
+}
+
+/// Detect the spend and convert anchors of the given transaction that are not
+/// valid against the given storage, e.g. because the transaction was built
+/// right before an epoch boundary. This is advisory only: wallets can call it
+/// before broadcasting a transaction to rebuild it instead of having it
+/// rejected by the MASP VP. Each stale anchor is reported once.
+pub fn check_anchors_fresh<S: MaspStorageRead>(
+    tx: &Transaction,
+    storage: &S,
+) -> crate::Result<Vec<StaleAnchorWarning>> {
+    let mut warnings = vec![];
+    let Some(bundle) = tx.sapling_bundle() else {
+        return Ok(warnings);
+    };
+
+    if !bundle.shielded_spends.is_empty() {
+        let current = storage
+            .read_pre::<CommitmentTree<Node>>(&masp_commitment_tree_key())?
+            .map(|tree| tree.root());
+        for description in &bundle.shielded_spends {
+            if storage
+                .has_key_pre(&masp_commitment_anchor_key(description.anchor))?
+                && is_within_anchor_retention_window(
+                    storage,
+                    description.anchor,
+                )?
+            {
+                continue;
+            }
+            let warning = StaleAnchorWarning::Spend {
+                anchor: Node::from_scalar(description.anchor),
+                current,
+            };
+            if !warnings.contains(&warning) {
+                warnings.push(warning);
+            }
+        }
+    }
+
+    if !bundle.shielded_converts.is_empty() {
+        let accepted = accepted_convert_anchors(storage)?.unwrap_or_default();
+        let current = accepted.first().map(|anchor| Node::new(anchor.0));
+        for description in &bundle.shielded_converts {
+            let anchor = Node::from_scalar(description.anchor);
+            if accepted.contains(&namada_core::hash::Hash(
+                description.anchor.to_bytes(),
+            )) {
+                continue;
+            }
+            let warning = StaleAnchorWarning::Convert { anchor, current };
+            if !warnings.contains(&warning) {
+                warnings.push(warning);
+            }
+        }
+    }
+
+    Ok(warnings)
+}
+
 #[cfg(test)]
 mod tests {
     use masp_proofs::bls12_381::Bls12;
+    use namada_core::borsh::BorshSerializeExt;
 
     use super::*;
+    use crate::storage_key::{
+        masp_convert_anchor_key, masp_recent_convert_anchors_key,
+    };
+    use crate::vp::test_utils::{
+        InMemoryMaspStorage, dummy_convert, shielded_tx,
+    };
 
     /// quick and dirty test. will fail on size check
     #[test]
@@ -433,6 +528,42 @@ mod tests {
             &fake_params_paths[2].0,
         );
     }
+
+    // Convert anchors that are neither the current nor a recent one must be
+    // reported
+    #[test]
+    fn test_stale_convert_anchor_warning() {
+        use masp_primitives::bls12_381::Scalar;
+
+        let current = Scalar::from(2u64);
+        let mut storage = InMemoryMaspStorage::default();
+        storage.pre.insert(
+            masp_convert_anchor_key(),
+            namada_core::hash::Hash(current.to_bytes()).serialize_to_vec(),
+        );
+
+        // A tx anchored to the current conversion tree is fresh
+        let tx = shielded_tx(vec![], vec![dummy_convert(current)], vec![]);
+        assert!(check_anchors_fresh(&tx, &storage).unwrap().is_empty());
+
+        // A tx anchored to a previous conversion tree is stale
+        let stale = Scalar::from(1u64);
+        let tx = shielded_tx(vec![], vec![dummy_convert(stale)], vec![]);
+        assert_eq!(
+            check_anchors_fresh(&tx, &storage).unwrap(),
+            vec![StaleAnchorWarning::Convert {
+                anchor: Node::from_scalar(stale),
+                current: Some(Node::from_scalar(current)),
+            }]
+        );
+
+        // Unless it is among the recent ones still accepted by the VP
+        storage.pre.insert(
+            masp_recent_convert_anchors_key(),
+            vec![namada_core::hash::Hash(stale.to_bytes())].serialize_to_vec(),
+        );
+        assert!(check_anchors_fresh(&tx, &storage).unwrap().is_empty());
+    }
 }
 
 #[cfg(any(test, feature = "testing"))]
@@ -1185,7 +1185,7 @@ pub trait ShieldedApi<U: ShieldedUtils + MaybeSend + MaybeSync>:
                 // expiration so we set the max to mimic
                 // a never-expiring tx. We also need to
                 // remove 20 which is going to be added back by the builder
-                u32::MAX - 20
+                crate::vp::NO_EXPIRY_HEIGHT - 20
             }
         };
         let mut builder = Builder::<Network, PseudoExtendedKey>::new(
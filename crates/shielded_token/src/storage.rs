@@ -1,9 +1,12 @@
+use masp_primitives::merkle_tree::CommitmentTree;
+use masp_primitives::sapling::Node;
 use namada_core::address::{self, Address};
 use namada_core::arith::checked;
-use namada_core::masp::TokenMap;
+use namada_core::masp::{MaspEpoch, TokenMap};
 use namada_core::token;
 use namada_core::token::Amount;
 use namada_core::uint::Uint;
+use namada_state::iter_prefix_with_filter_map;
 use namada_systems::trans_token;
 
 use crate::storage_key::*;
@@ -93,6 +96,76 @@ where
     Ok(undated_balance)
 }
 
+/// Read the masp epoch that the current convert anchor corresponds to.
+pub fn masp_convert_anchor_epoch<S>(storage: &S) -> Result<MaspEpoch>
+where
+    S: StorageRead,
+{
+    let convert_anchor_epoch_key = masp_convert_anchor_epoch_key();
+    // The genesis convert anchor corresponds to the first masp epoch
+    Ok(storage
+        .read(&convert_anchor_epoch_key)?
+        .unwrap_or(MaspEpoch::zero()))
+}
+
+/// Read all the commitment tree anchors that spend descriptions are
+/// currently accepted against. These are all the anchors published so far,
+/// or only the most recent ones if an anchor retention window is configured.
+pub fn accepted_anchors<S>(storage: &S) -> Result<Vec<Node>>
+where
+    S: StorageRead,
+{
+    if storage.has_key(&masp_anchor_retention_window_key())? {
+        // Until an anchor gets recorded under the window, the current root of
+        // the tree is the only recent one
+        return match storage.read(&masp_recent_commitment_anchors_key())? {
+            Some(recent_anchors) => Ok(recent_anchors),
+            None => Ok(storage
+                .read::<CommitmentTree<Node>>(&masp_commitment_tree_key())?
+                .map(|tree| tree.root())
+                .into_iter()
+                .collect()),
+        };
+    }
+    iter_prefix_with_filter_map::<_, (), _>(
+        storage,
+        &masp_commitment_anchor_prefix(),
+        is_masp_commitment_anchor_key,
+    )?
+    .map(|anchor| anchor.map(|(anchor, ())| anchor))
+    .collect()
+}
+
+/// Publish the given note commitment tree anchor, such that spend
+/// descriptions can be built against it. If an anchor retention window is
+/// configured, the anchor is also pushed to the front of the most recent
+/// ones, of which only as many as the window are kept.
+pub fn record_commitment_anchor<S>(storage: &mut S, anchor: Node) -> Result<()>
+where
+    S: StorageRead + StorageWrite,
+{
+    storage.write(&masp_commitment_anchor_key(anchor), ())?;
+
+    let recent_anchors_key = masp_recent_commitment_anchors_key();
+    let Some(window) =
+        storage.read::<u64>(&masp_anchor_retention_window_key())?
+    else {
+        // Drop the anchors recorded under a since removed window, but leave
+        // the key untouched if there are none
+        if storage.has_key(&recent_anchors_key)? {
+            storage.delete(&recent_anchors_key)?;
+        }
+        return Ok(());
+    };
+    let mut recent_anchors: Vec<Node> =
+        storage.read(&recent_anchors_key)?.unwrap_or_default();
+    if recent_anchors.first() != Some(&anchor) {
+        recent_anchors.insert(0, anchor);
+    }
+    recent_anchors.truncate(usize::try_from(window).unwrap_or(usize::MAX));
+    storage.write(&recent_anchors_key, recent_anchors)
+}
+
 /// Read the masp token map.
 pub fn read_token_map<S>(storage: &S) -> Result<TokenMap>
 where
@@ -110,3 +183,107 @@ where
     let token_map_key = masp_token_map_key();
     storage.write(&token_map_key, token_map)
 }
+
+#[cfg(test)]
+mod tests {
+    use masp_primitives::bls12_381::Scalar;
+    use namada_state::StateRead;
+    use namada_state::testing::{TestState, TestStorage};
+
+    use super::*;
+
+    #[test]
+    fn test_accepted_anchors() {
+        let mut storage = TestStorage::default();
+        assert!(accepted_anchors(&storage).unwrap().is_empty());
+
+        let anchors: Vec<Node> = (1..=3u64)
+            .map(|i| Node::from_scalar(Scalar::from(i)))
+            .collect();
+        for anchor in &anchors {
+            storage
+                .write(&masp_commitment_anchor_key(*anchor), ())
+                .unwrap();
+        }
+        // Unrelated MASP keys are not mistaken for anchors
+        storage
+            .write(
+                &masp_convert_anchor_key(),
+                namada_core::hash::Hash::default(),
+            )
+            .unwrap();
+
+        let accepted = accepted_anchors(&storage).unwrap();
+        assert_eq!(accepted.len(), anchors.len());
+        assert!(anchors.iter().all(|anchor| accepted.contains(anchor)));
+
+        // With a retention window, only the current root of the tree is
+        // accepted until more anchors get recorded
+        let mut tree = CommitmentTree::<Node>::empty();
+        tree.append(anchors[2]).unwrap();
+        storage.write(&masp_commitment_tree_key(), &tree).unwrap();
+        storage
+            .write(&masp_anchor_retention_window_key(), 2u64)
+            .unwrap();
+        assert_eq!(accepted_anchors(&storage).unwrap(), vec![tree.root()]);
+
+        record_commitment_anchor(&mut storage, anchors[1]).unwrap();
+        record_commitment_anchor(&mut storage, anchors[0]).unwrap();
+        assert_eq!(
+            accepted_anchors(&storage).unwrap(),
+            vec![anchors[0], anchors[1]]
+        );
+    }
+
+    #[test]
+    fn test_record_commitment_anchor() {
+        let mut storage = TestStorage::default();
+        let anchor = |i: u64| Node::from_scalar(Scalar::from(i));
+        let recent_anchors = |storage: &TestStorage| {
+            storage
+                .read::<Vec<Node>>(&masp_recent_commitment_anchors_key())
+                .unwrap()
+        };
+
+        // Without a retention window, anchors are only published
+        record_commitment_anchor(&mut storage, anchor(1)).unwrap();
+        assert!(
+            storage
+                .has_key(&masp_commitment_anchor_key(anchor(1)))
+                .unwrap()
+        );
+        assert_eq!(recent_anchors(&storage), None);
+
+        // With a retention window, only the most recent anchors are kept
+        storage
+            .write(&masp_anchor_retention_window_key(), 2u64)
+            .unwrap();
+        for i in 2..=4 {
+            record_commitment_anchor(&mut storage, anchor(i)).unwrap();
+        }
+        record_commitment_anchor(&mut storage, anchor(4)).unwrap();
+        assert_eq!(recent_anchors(&storage), Some(vec![anchor(4), anchor(3)]));
+        assert!(
+            storage
+                .has_key(&masp_commitment_anchor_key(anchor(2)))
+                .unwrap()
+        );
+
+        // Removing the window drops the most recent anchors
+        storage.delete(&masp_anchor_retention_window_key()).unwrap();
+        record_commitment_anchor(&mut storage, anchor(5)).unwrap();
+        assert_eq!(recent_anchors(&storage), None);
+    }
+
+    #[test]
+    fn test_record_commitment_anchor_without_window() {
+        let mut state = TestState::default();
+        let anchor = Node::from_scalar(Scalar::from(1u64));
+        record_commitment_anchor(&mut state, anchor).unwrap();
+
+        // Without a retention window, tree updates only publish the anchor,
+        // and never touch the most recent anchors
+        assert_eq!(state.write_log().block_modifications_count(), 1);
+        assert!(state.has_key(&masp_commitment_anchor_key(anchor)).unwrap());
+    }
+}
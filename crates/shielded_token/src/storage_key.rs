@@ -1,5 +1,6 @@
 //! Shielded token storage keys
 
+use std::collections::BTreeSet;
 use std::str::FromStr;
 
 use masp_primitives::asset_type::AssetType;
@@ -7,7 +8,7 @@ use masp_primitives::bls12_381::Scalar;
 use masp_primitives::sapling::{Node, Nullifier};
 use namada_core::address::{self, Address};
 use namada_core::hash::Hash;
-use namada_core::masp::MaspEpoch;
+use namada_core::masp::{MaspEpoch, addr_taddr};
 use namada_core::storage::{self, DbKeySeg, KeySeg};
 use namada_systems::trans_token;
 
@@ -31,6 +32,16 @@ pub const MASP_NOTE_COMMITMENT_TREE_KEY: &str = "commitment_tree";
 pub const MASP_NOTE_COMMITMENT_ANCHOR_PREFIX: &str = "note_commitment_anchor";
 /// Key segment prefix for the convert anchor
 pub const MASP_CONVERT_ANCHOR_KEY: &str = "convert_anchor";
+/// Key segment for the epoch of the convert anchor
+pub const MASP_CONVERT_ANCHOR_EPOCH_KEY: &str = "convert_anchor_epoch";
+/// Key segment for the most recent convert anchors
+pub const MASP_RECENT_CONVERT_ANCHORS_KEY: &str = "recent_convert_anchors";
+/// Key segment for the most recent note commitment anchors
+pub const MASP_RECENT_COMMITMENT_ANCHORS_KEY: &str =
+    "recent_commitment_anchors";
+/// The key for the number of most recent note commitment anchors that spend
+/// descriptions are accepted against
+pub const MASP_ANCHOR_RETENTION_WINDOW_KEY: &str = "anchor_retention_window";
 /// The key for the token map
 pub const MASP_TOKEN_MAP_KEY: &str = "tokens";
 /// The key for the asset map
@@ -54,6 +65,29 @@ pub const MASP_TOTAL_REWARDS: &str = "max_total_rewards";
 pub const MASP_REWARD_PRECISION_KEY: &str = "reward_precision";
 /// The key for the base native precision
 pub const MASP_BASE_NATIVE_PRECISION_KEY: &str = "base_native_precision";
+/// The key for the extra key prefixes a masp transfer is allowed to touch
+pub const MASP_EXTRA_TRANSFER_KEYS_KEY: &str = "extra_transfer_keys";
+/// The key for the height until which shielded transactions are rejected
+pub const MASP_FROZEN_UNTIL_KEY: &str = "frozen_until";
+/// The key for the consensus branch id shielded transactions must target
+pub const MASP_CONSENSUS_BRANCH_ID_KEY: &str = "consensus_branch_id";
+/// The key for the maximum number of spend descriptions of a shielded tx
+pub const MASP_MAX_SPENDS_PER_TX_KEY: &str = "max_spends_per_tx";
+/// The key for the maximum number of convert descriptions of a shielded tx
+pub const MASP_MAX_CONVERTS_PER_TX_KEY: &str = "max_converts_per_tx";
+/// The key for the maximum number of output descriptions of a shielded tx
+pub const MASP_MAX_OUTPUTS_PER_TX_KEY: &str = "max_outputs_per_tx";
+/// The key for the maximum number of distinct tokens a shielded tx can move
+pub const MASP_MAX_TOKENS_PER_TX_KEY: &str = "max_tokens_per_tx";
+/// The key for the multiplier, in percent, of the gas charged for verifying
+/// spend descriptions
+pub const MASP_SPEND_GAS_MULTIPLIER_KEY: &str = "spend_gas_multiplier";
+/// The key for the multiplier, in percent, of the gas charged for verifying
+/// convert descriptions
+pub const MASP_CONVERT_GAS_MULTIPLIER_KEY: &str = "convert_gas_multiplier";
+/// The key for the multiplier, in percent, of the gas charged for verifying
+/// output descriptions
+pub const MASP_OUTPUT_GAS_MULTIPLIER_KEY: &str = "output_gas_multiplier";
 
 /// Obtain the nominal proportional key for the given token
 pub fn masp_kp_gain_key<TransToken: trans_token::Keys>(
@@ -230,6 +264,13 @@ pub fn is_masp_governance_key(key: &storage::Key) -> bool {
         || is_masp_scheduled_reward_precision_key(key).is_some()
         || is_masp_base_native_precision_key(key)
         || is_masp_scheduled_base_native_precision_key(key).is_some()
+        || is_masp_extra_transfer_keys_key(key)
+        || is_masp_frozen_until_key(key)
+        || is_masp_consensus_branch_id_key(key)
+        || is_masp_max_descriptions_key(key)
+        || is_masp_max_tokens_per_tx_key(key)
+        || is_masp_gas_multiplier_key(key)
+        || is_masp_anchor_retention_window_key(key)
 }
 
 /// Check if the given storage key is allowed to be touched by a masp transfer
@@ -240,6 +281,90 @@ pub fn is_masp_transfer_key(key: &storage::Key) -> bool {
         || is_masp_undated_balance_key(key).is_some()
 }
 
+/// Check if the given storage key is allowed to be touched by a masp transfer,
+/// either by default or by falling under one of the given extra key prefixes.
+/// Governance keys are never allowed, even if under an extra prefix.
+pub fn is_masp_extended_transfer_key(
+    key: &storage::Key,
+    extra_prefixes: &BTreeSet<storage::Key>,
+) -> bool {
+    is_masp_transfer_key(key)
+        || (!is_masp_governance_key(key)
+            && extra_prefixes
+                .iter()
+                .any(|prefix| key.split_prefix(prefix).is_some()))
+}
+
+/// Check if the given storage key is the key of the extra key prefixes a masp
+/// transfer is allowed to touch
+pub fn is_masp_extra_transfer_keys_key(key: &storage::Key) -> bool {
+    matches!(&key.segments[..],
+    [DbKeySeg::AddressSeg(addr),
+             DbKeySeg::StringSeg(prefix),
+        ] if *addr == address::MASP && prefix == MASP_EXTRA_TRANSFER_KEYS_KEY)
+}
+
+/// Check if the given storage key is the key of the height until which
+/// shielded transactions are rejected
+pub fn is_masp_frozen_until_key(key: &storage::Key) -> bool {
+    matches!(&key.segments[..],
+    [DbKeySeg::AddressSeg(addr),
+             DbKeySeg::StringSeg(prefix),
+        ] if *addr == address::MASP && prefix == MASP_FROZEN_UNTIL_KEY)
+}
+
+/// Check if the given storage key is the key of the consensus branch id
+/// shielded transactions must target
+pub fn is_masp_consensus_branch_id_key(key: &storage::Key) -> bool {
+    matches!(&key.segments[..],
+    [DbKeySeg::AddressSeg(addr),
+             DbKeySeg::StringSeg(prefix),
+        ] if *addr == address::MASP && prefix == MASP_CONSENSUS_BRANCH_ID_KEY)
+}
+
+/// Check if the given storage key is the key of the maximum number of spend,
+/// convert or output descriptions of a shielded transaction
+pub fn is_masp_max_descriptions_key(key: &storage::Key) -> bool {
+    matches!(&key.segments[..],
+    [DbKeySeg::AddressSeg(addr),
+             DbKeySeg::StringSeg(prefix),
+        ] if *addr == address::MASP
+            && (prefix == MASP_MAX_SPENDS_PER_TX_KEY
+                || prefix == MASP_MAX_CONVERTS_PER_TX_KEY
+                || prefix == MASP_MAX_OUTPUTS_PER_TX_KEY))
+}
+
+/// Check if the given storage key is the key of the maximum number of
+/// distinct tokens a shielded transaction can move
+pub fn is_masp_max_tokens_per_tx_key(key: &storage::Key) -> bool {
+    matches!(&key.segments[..],
+    [DbKeySeg::AddressSeg(addr),
+             DbKeySeg::StringSeg(prefix),
+        ] if *addr == address::MASP && prefix == MASP_MAX_TOKENS_PER_TX_KEY)
+}
+
+/// Check if the given storage key is the key of the gas multiplier of spend,
+/// convert or output descriptions verification
+pub fn is_masp_gas_multiplier_key(key: &storage::Key) -> bool {
+    matches!(&key.segments[..],
+    [DbKeySeg::AddressSeg(addr),
+             DbKeySeg::StringSeg(prefix),
+        ] if *addr == address::MASP
+            && (prefix == MASP_SPEND_GAS_MULTIPLIER_KEY
+                || prefix == MASP_CONVERT_GAS_MULTIPLIER_KEY
+                || prefix == MASP_OUTPUT_GAS_MULTIPLIER_KEY))
+}
+
+/// Check if the given storage key is the key of the note commitment anchor
+/// retention window
+pub fn is_masp_anchor_retention_window_key(key: &storage::Key) -> bool {
+    matches!(&key.segments[..],
+    [DbKeySeg::AddressSeg(addr),
+             DbKeySeg::StringSeg(prefix),
+        ] if *addr == address::MASP
+            && prefix == MASP_ANCHOR_RETENTION_WINDOW_KEY)
+}
+
 /// Check if the given storage key is a masp commitment tree key
 pub fn is_masp_commitment_tree_key(key: &storage::Key) -> bool {
     matches!(&key.segments[..],
@@ -354,6 +479,13 @@ pub fn masp_commitment_anchor_key(anchor: impl Into<Scalar>) -> storage::Key {
         .expect("Cannot obtain a storage key")
 }
 
+/// Get the key prefix of all the masp commitment tree anchors
+pub fn masp_commitment_anchor_prefix() -> storage::Key {
+    storage::Key::from(address::MASP.to_db_key())
+        .push(&MASP_NOTE_COMMITMENT_ANCHOR_PREFIX.to_owned())
+        .expect("Cannot obtain a storage key")
+}
+
 /// Get the key for the masp convert tree anchor
 pub fn masp_convert_anchor_key() -> storage::Key {
     storage::Key::from(address::MASP.to_db_key())
@@ -361,6 +493,34 @@ pub fn masp_convert_anchor_key() -> storage::Key {
         .expect("Cannot obtain a storage key")
 }
 
+/// Get the key for the masp epoch of the convert tree anchor
+pub fn masp_convert_anchor_epoch_key() -> storage::Key {
+    storage::Key::from(address::MASP.to_db_key())
+        .push(&MASP_CONVERT_ANCHOR_EPOCH_KEY.to_owned())
+        .expect("Cannot obtain a storage key")
+}
+
+/// Get the key for the most recent masp convert tree anchors
+pub fn masp_recent_convert_anchors_key() -> storage::Key {
+    storage::Key::from(address::MASP.to_db_key())
+        .push(&MASP_RECENT_CONVERT_ANCHORS_KEY.to_owned())
+        .expect("Cannot obtain a storage key")
+}
+
+/// Get the key for the most recent masp note commitment tree anchors
+pub fn masp_recent_commitment_anchors_key() -> storage::Key {
+    storage::Key::from(address::MASP.to_db_key())
+        .push(&MASP_RECENT_COMMITMENT_ANCHORS_KEY.to_owned())
+        .expect("Cannot obtain a storage key")
+}
+
+/// Get the key for the note commitment anchor retention window
+pub fn masp_anchor_retention_window_key() -> storage::Key {
+    storage::Key::from(address::MASP.to_db_key())
+        .push(&MASP_ANCHOR_RETENTION_WINDOW_KEY.to_owned())
+        .expect("Cannot obtain a storage key")
+}
+
 /// Get the key for the masp token map
 pub fn masp_token_map_key() -> storage::Key {
     storage::Key::from(address::MASP.to_db_key())
@@ -375,6 +535,77 @@ pub fn masp_base_native_precision_key() -> storage::Key {
         .expect("Cannot obtain a storage key")
 }
 
+/// Get the key for the extra key prefixes a masp transfer is allowed to touch
+pub fn masp_extra_transfer_keys_key() -> storage::Key {
+    storage::Key::from(address::MASP.to_db_key())
+        .push(&MASP_EXTRA_TRANSFER_KEYS_KEY.to_owned())
+        .expect("Cannot obtain a storage key")
+}
+
+/// Get the key for the height until which shielded transactions are rejected
+pub fn masp_frozen_until_key() -> storage::Key {
+    storage::Key::from(address::MASP.to_db_key())
+        .push(&MASP_FROZEN_UNTIL_KEY.to_owned())
+        .expect("Cannot obtain a storage key")
+}
+
+/// Get the key for the consensus branch id shielded transactions must target
+pub fn masp_consensus_branch_id_key() -> storage::Key {
+    storage::Key::from(address::MASP.to_db_key())
+        .push(&MASP_CONSENSUS_BRANCH_ID_KEY.to_owned())
+        .expect("Cannot obtain a storage key")
+}
+
+/// Get the key for the maximum number of spend descriptions of a shielded tx
+pub fn masp_max_spends_per_tx_key() -> storage::Key {
+    storage::Key::from(address::MASP.to_db_key())
+        .push(&MASP_MAX_SPENDS_PER_TX_KEY.to_owned())
+        .expect("Cannot obtain a storage key")
+}
+
+/// Get the key for the maximum number of convert descriptions of a shielded tx
+pub fn masp_max_converts_per_tx_key() -> storage::Key {
+    storage::Key::from(address::MASP.to_db_key())
+        .push(&MASP_MAX_CONVERTS_PER_TX_KEY.to_owned())
+        .expect("Cannot obtain a storage key")
+}
+
+/// Get the key for the maximum number of output descriptions of a shielded tx
+pub fn masp_max_outputs_per_tx_key() -> storage::Key {
+    storage::Key::from(address::MASP.to_db_key())
+        .push(&MASP_MAX_OUTPUTS_PER_TX_KEY.to_owned())
+        .expect("Cannot obtain a storage key")
+}
+
+/// Get the key for the maximum number of distinct tokens a shielded tx can
+/// move
+pub fn masp_max_tokens_per_tx_key() -> storage::Key {
+    storage::Key::from(address::MASP.to_db_key())
+        .push(&MASP_MAX_TOKENS_PER_TX_KEY.to_owned())
+        .expect("Cannot obtain a storage key")
+}
+
+/// Get the key for the gas multiplier of spend descriptions verification
+pub fn masp_spend_gas_multiplier_key() -> storage::Key {
+    storage::Key::from(address::MASP.to_db_key())
+        .push(&MASP_SPEND_GAS_MULTIPLIER_KEY.to_owned())
+        .expect("Cannot obtain a storage key")
+}
+
+/// Get the key for the gas multiplier of convert descriptions verification
+pub fn masp_convert_gas_multiplier_key() -> storage::Key {
+    storage::Key::from(address::MASP.to_db_key())
+        .push(&MASP_CONVERT_GAS_MULTIPLIER_KEY.to_owned())
+        .expect("Cannot obtain a storage key")
+}
+
+/// Get the key for the gas multiplier of output descriptions verification
+pub fn masp_output_gas_multiplier_key() -> storage::Key {
+    storage::Key::from(address::MASP.to_db_key())
+        .push(&MASP_OUTPUT_GAS_MULTIPLIER_KEY.to_owned())
+        .expect("Cannot obtain a storage key")
+}
+
 /// Get the key for the masp assets' hash
 pub fn masp_assets_hash_key() -> storage::Key {
     storage::Key::from(address::MASP.to_db_key())
@@ -388,3 +619,31 @@ pub fn masp_total_rewards() -> storage::Key {
         .push(&MASP_TOTAL_REWARDS.to_owned())
         .expect("Cannot obtain a storage key")
 }
+
+/// Get the hash identifying the given address in the transparent bundle of
+/// MASP transactions, i.e. `Ripemd160(Sha256(data))` where `data` is the
+/// Borsh encoding of [`namada_core::masp::TAddrData::Addr`] of the address.
+pub fn transparent_address_hash(addr: &Address) -> [u8; 20] {
+    addr_taddr(addr.clone()).0
+}
+
+#[cfg(test)]
+mod tests {
+    use namada_core::address::EstablishedAddress;
+
+    use super::*;
+
+    /// Test the transparent address hash against a known vector, which
+    /// clients can use to cross-check their own derivation.
+    #[test]
+    fn test_transparent_address_hash() {
+        let addr = Address::Established(EstablishedAddress::from([1; 20]));
+        assert_eq!(
+            transparent_address_hash(&addr),
+            [
+                0xac, 0xd4, 0xfc, 0x30, 0xe1, 0x2d, 0xdf, 0x81, 0x51, 0x11,
+                0x8e, 0x6d, 0x40, 0x8a, 0x3e, 0xbe, 0x76, 0xdb, 0x49, 0x53,
+            ]
+        );
+    }
+}
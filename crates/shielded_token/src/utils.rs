@@ -1,15 +1,19 @@
 //! MASP utilities
 
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
 
+use masp_primitives::asset_type::AssetType;
 use masp_primitives::merkle_tree::CommitmentTree;
-use masp_primitives::sapling::Node;
+use masp_primitives::sapling::{Node, Nullifier};
 use masp_primitives::transaction::Transaction;
+use namada_core::address::Address;
+use namada_core::masp::AssetData;
+use namada_core::token::{Denomination, MaspDigitPos};
 
 use crate::storage_key::{
     is_masp_transfer_key, masp_commitment_tree_key, masp_nullifier_key,
 };
-use crate::{Error, Key, Result, StorageRead, StorageWrite};
+use crate::{ConversionState, Error, Key, Result, StorageRead, StorageWrite};
 
 // Writes the nullifiers of the provided masp transaction to storage
 fn reveal_nullifiers(
@@ -81,3 +85,177 @@ pub fn handle_masp_tx(
 pub fn is_masp_transfer(changed_keys: &BTreeSet<Key>) -> bool {
     changed_keys.iter().any(is_masp_transfer_key)
 }
+
+/// Get the nullifiers of the provided transaction that were already revealed
+/// in storage, in the order of the spend descriptions.
+///
+/// An empty result means that none of the notes spent by the transaction were
+/// spent before. This can be used to cheaply reject a transaction before
+/// running the MASP VP.
+pub fn nullifiers_already_spent<S>(
+    storage: &S,
+    transaction: &Transaction,
+) -> Result<Vec<Nullifier>>
+where
+    S: StorageRead,
+{
+    let mut spent = vec![];
+    for description in transaction
+        .sapling_bundle()
+        .map_or(&vec![], |bundle| &bundle.shielded_spends)
+    {
+        if storage.has_key(&masp_nullifier_key(&description.nullifier))? {
+            spent.push(description.nullifier);
+        }
+    }
+    Ok(spent)
+}
+
+/// Map an asset type back to the token, denomination, digit position and
+/// epoch it was derived from.
+///
+/// Asset types carrying an epoch are resolved from the conversion state,
+/// whereas asset types lacking an epoch are resolved from `undated_tokens`,
+/// which maps them to their decodings.
+pub fn resolve_asset_type(
+    conversion_state: &ConversionState,
+    undated_tokens: &BTreeMap<AssetType, (Address, Denomination, MaspDigitPos)>,
+    asset_type: &AssetType,
+) -> Option<AssetData> {
+    if let Some(asset) = conversion_state.assets.get(asset_type) {
+        return Some(AssetData {
+            token: asset.token.clone(),
+            denom: asset.denom,
+            position: asset.digit_pos,
+            epoch: Some(asset.epoch),
+        });
+    }
+    undated_tokens
+        .get(asset_type)
+        .map(|(token, denom, digit)| AssetData {
+            token: token.clone(),
+            denom: *denom,
+            position: *digit,
+            epoch: None,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use masp_primitives::transaction::components::I128Sum;
+    use namada_core::address::testing::nam;
+    use namada_core::masp::{MaspEpoch, encode_asset_type};
+    use namada_state::testing::TestStorage;
+
+    use super::*;
+    use crate::ConversionLeaf;
+
+    // Only the nullifiers revealed in storage are reported as spent
+    #[test]
+    fn test_nullifiers_already_spent() {
+        use crate::vp::test_utils::{dummy_spend, shielded_tx};
+
+        let anchor = masp_primitives::bls12_381::Scalar::from(1u64);
+        let transaction = shielded_tx(
+            vec![dummy_spend(anchor, 1), dummy_spend(anchor, 2)],
+            vec![],
+            vec![],
+        );
+
+        let mut storage = TestStorage::default();
+        assert!(
+            nullifiers_already_spent(&storage, &transaction)
+                .unwrap()
+                .is_empty()
+        );
+
+        storage
+            .write(&masp_nullifier_key(&Nullifier([2; 32])), ())
+            .unwrap();
+        assert_eq!(
+            nullifiers_already_spent(&storage, &transaction).unwrap(),
+            vec![Nullifier([2; 32])]
+        );
+    }
+
+    // Dated asset types are resolved from the conversion state
+    #[test]
+    fn test_resolve_epoched_asset_type() {
+        let denom = Denomination(6);
+        let digit = MaspDigitPos::One;
+        let epoch = MaspEpoch::new(3);
+        let asset_type =
+            encode_asset_type(nam(), denom, digit, Some(epoch)).unwrap();
+        let mut conversion_state = ConversionState::default();
+        conversion_state.assets.insert(
+            asset_type,
+            ConversionLeaf {
+                token: nam(),
+                denom,
+                digit_pos: digit,
+                epoch,
+                conversion: I128Sum::zero().into(),
+                leaf_pos: 0,
+            },
+        );
+
+        assert_eq!(
+            resolve_asset_type(
+                &conversion_state,
+                &BTreeMap::new(),
+                &asset_type
+            ),
+            Some(AssetData {
+                token: nam(),
+                denom,
+                position: digit,
+                epoch: Some(epoch),
+            })
+        );
+        // The resolution is consistent with the encoding
+        assert_eq!(
+            resolve_asset_type(
+                &conversion_state,
+                &BTreeMap::new(),
+                &asset_type
+            )
+            .unwrap()
+            .encode()
+            .unwrap(),
+            asset_type
+        );
+    }
+
+    // Undated asset types are resolved from the provided decodings
+    #[test]
+    fn test_resolve_unepoched_asset_type() {
+        let denom = Denomination(6);
+        let digit = MaspDigitPos::Two;
+        let asset_type = encode_asset_type(nam(), denom, digit, None).unwrap();
+        let undated_tokens =
+            BTreeMap::from([(asset_type, (nam(), denom, digit))]);
+        let conversion_state = ConversionState::default();
+
+        assert_eq!(
+            resolve_asset_type(&conversion_state, &undated_tokens, &asset_type),
+            Some(AssetData {
+                token: nam(),
+                denom,
+                position: digit,
+                epoch: None,
+            })
+        );
+        // Unknown asset types cannot be resolved
+        let dated_asset_type =
+            encode_asset_type(nam(), denom, digit, Some(MaspEpoch::new(1)))
+                .unwrap();
+        assert_eq!(
+            resolve_asset_type(
+                &conversion_state,
+                &undated_tokens,
+                &dated_asset_type
+            ),
+            None
+        );
+    }
+}
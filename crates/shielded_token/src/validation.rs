@@ -118,9 +118,11 @@ fn load_pvks() -> &'static PVKs {
     &VERIFIYING_KEYS
 }
 
-/// Verify a shielded transaction.
+/// Verify a shielded transaction, charging the gas costs of the verification
+/// of its descriptions scaled by the given multipliers.
 pub fn verify_shielded_tx<F>(
     transaction: &Transaction,
+    gas_multipliers: &VerifyGasMultipliers,
     consume_verify_gas: F,
 ) -> Result<()>
 where
@@ -163,7 +165,11 @@ where
     let mut ctx = testing::MockBatchValidator::default();
 
     // Charge gas before check bundle
-    charge_masp_check_bundle_gas(sapling_bundle, &consume_verify_gas)?;
+    charge_masp_check_bundle_gas(
+        sapling_bundle,
+        gas_multipliers,
+        &consume_verify_gas,
+    )?;
 
     if !ctx.check_bundle(sapling_bundle.to_owned(), sighash.as_ref().to_owned())
     {
@@ -173,7 +179,11 @@ where
     tracing::debug!("passed check bundle");
 
     // Charge gas before final validation
-    charge_masp_validate_gas(sapling_bundle, consume_verify_gas)?;
+    charge_masp_validate_gas(
+        sapling_bundle,
+        gas_multipliers,
+        consume_verify_gas,
+    )?;
     if !ctx.validate(spend_vk, convert_vk, output_vk, OsRng) {
         return Err(Error::new_const("Invalid proofs or signatures"));
     }
@@ -213,24 +223,59 @@ pub fn partial_deauthorize(
     ))
 }
 
+/// The range of the multipliers, in percent, of the gas charged for verifying
+/// the descriptions of shielded transactions. Cheaper verification would
+/// open the door to flooding blocks with expensive proofs, while the upper
+/// bound keeps the scaled costs far from overflowing.
+pub const VERIFY_GAS_MULTIPLIER_RANGE: std::ops::RangeInclusive<u64> =
+    50..=1_000;
+
+/// Multipliers, in percent, of the gas charged for verifying each kind of
+/// description of a shielded transaction. A multiplier of 100 charges the
+/// default gas costs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VerifyGasMultipliers {
+    /// Multiplier of the gas charged for spend descriptions
+    pub spend: u64,
+    /// Multiplier of the gas charged for convert descriptions
+    pub convert: u64,
+    /// Multiplier of the gas charged for output descriptions
+    pub output: u64,
+}
+
+impl Default for VerifyGasMultipliers {
+    fn default() -> Self {
+        Self {
+            spend: 100,
+            convert: 100,
+            output: 100,
+        }
+    }
+}
+
+// Scale the gas cost of `count` descriptions by the given multiplier
+fn scaled_gas(count: usize, unit_cost: u64, multiplier: u64) -> Result<Gas> {
+    Ok(checked!((count as u64) * unit_cost * multiplier / 100)?.into())
+}
+
 // Charge gas for the final validation, taking advtange of concurrency for
 // proofs verification but not for signatures
-fn charge_masp_validate_gas<F>(
+pub(crate) fn charge_masp_validate_gas<F>(
     sapling_bundle: &SaplingBundle<SaplingAuthorized>,
+    multipliers: &VerifyGasMultipliers,
     consume_verify_gas: F,
 ) -> Result<()>
 where
     F: Fn(Gas) -> Result<()>,
 {
     // Signatures gas
-    consume_verify_gas(
-        checked!(
-            // Add one for the binding signature
-            ((sapling_bundle.shielded_spends.len() as u64) + 1)
-                * namada_gas::MASP_VERIFY_SIG_GAS
-        )?
-        .into(),
-    )?;
+    consume_verify_gas(scaled_gas(
+        sapling_bundle.shielded_spends.len(),
+        namada_gas::MASP_VERIFY_SIG_GAS,
+        multipliers.spend,
+    )?)?;
+    // The binding signature is not tied to any description
+    consume_verify_gas(namada_gas::MASP_VERIFY_SIG_GAS.into())?;
 
     // If at least one note is present charge the fixed costs. Then charge the
     // variable cost for every other note, amortized on the fixed expected
@@ -238,73 +283,77 @@ where
     if let Some(remaining_notes) =
         sapling_bundle.shielded_spends.len().checked_sub(1)
     {
-        consume_verify_gas(namada_gas::MASP_FIXED_SPEND_GAS.into())?;
-        consume_verify_gas(
-            checked!(
-                namada_gas::MASP_VARIABLE_SPEND_GAS * remaining_notes as u64
-            )?
-            .into(),
-        )?;
+        consume_verify_gas(scaled_gas(
+            1,
+            namada_gas::MASP_FIXED_SPEND_GAS,
+            multipliers.spend,
+        )?)?;
+        consume_verify_gas(scaled_gas(
+            remaining_notes,
+            namada_gas::MASP_VARIABLE_SPEND_GAS,
+            multipliers.spend,
+        )?)?;
     }
 
     if let Some(remaining_notes) =
         sapling_bundle.shielded_converts.len().checked_sub(1)
     {
-        consume_verify_gas(namada_gas::MASP_FIXED_CONVERT_GAS.into())?;
-        consume_verify_gas(
-            checked!(
-                namada_gas::MASP_VARIABLE_CONVERT_GAS * remaining_notes as u64
-            )?
-            .into(),
-        )?;
+        consume_verify_gas(scaled_gas(
+            1,
+            namada_gas::MASP_FIXED_CONVERT_GAS,
+            multipliers.convert,
+        )?)?;
+        consume_verify_gas(scaled_gas(
+            remaining_notes,
+            namada_gas::MASP_VARIABLE_CONVERT_GAS,
+            multipliers.convert,
+        )?)?;
     }
 
     if let Some(remaining_notes) =
         sapling_bundle.shielded_outputs.len().checked_sub(1)
     {
-        consume_verify_gas(namada_gas::MASP_FIXED_OUTPUT_GAS.into())?;
-        consume_verify_gas(
-            checked!(
-                namada_gas::MASP_VARIABLE_OUTPUT_GAS * remaining_notes as u64
-            )?
-            .into(),
-        )?;
+        consume_verify_gas(scaled_gas(
+            1,
+            namada_gas::MASP_FIXED_OUTPUT_GAS,
+            multipliers.output,
+        )?)?;
+        consume_verify_gas(scaled_gas(
+            remaining_notes,
+            namada_gas::MASP_VARIABLE_OUTPUT_GAS,
+            multipliers.output,
+        )?)?;
     }
 
     Ok(())
 }
 
 // Charge gas for the check_bundle operation which does not leverage concurrency
-fn charge_masp_check_bundle_gas<F>(
+pub(crate) fn charge_masp_check_bundle_gas<F>(
     sapling_bundle: &SaplingBundle<SaplingAuthorized>,
+    multipliers: &VerifyGasMultipliers,
     consume_verify_gas: F,
 ) -> Result<()>
 where
     F: Fn(Gas) -> Result<()>,
 {
-    consume_verify_gas(
-        checked!(
-            (sapling_bundle.shielded_spends.len() as u64)
-                * namada_gas::MASP_SPEND_CHECK_GAS
-        )?
-        .into(),
-    )?;
-
-    consume_verify_gas(
-        checked!(
-            (sapling_bundle.shielded_converts.len() as u64)
-                * namada_gas::MASP_CONVERT_CHECK_GAS
-        )?
-        .into(),
-    )?;
-
-    consume_verify_gas(
-        checked!(
-            (sapling_bundle.shielded_outputs.len() as u64)
-                * namada_gas::MASP_OUTPUT_CHECK_GAS
-        )?
-        .into(),
-    )
+    consume_verify_gas(scaled_gas(
+        sapling_bundle.shielded_spends.len(),
+        namada_gas::MASP_SPEND_CHECK_GAS,
+        multipliers.spend,
+    )?)?;
+
+    consume_verify_gas(scaled_gas(
+        sapling_bundle.shielded_converts.len(),
+        namada_gas::MASP_CONVERT_CHECK_GAS,
+        multipliers.convert,
+    )?)?;
+
+    consume_verify_gas(scaled_gas(
+        sapling_bundle.shielded_outputs.len(),
+        namada_gas::MASP_OUTPUT_CHECK_GAS,
+        multipliers.output,
+    )?)
 }
 
 #[cfg(any(test, feature = "testing"))]
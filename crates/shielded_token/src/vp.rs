@@ -6,36 +6,205 @@ use std::marker::PhantomData;
 
 use borsh::BorshDeserialize;
 use masp_primitives::asset_type::AssetType;
+use masp_primitives::bls12_381::Scalar;
+use masp_primitives::consensus::BranchId;
 use masp_primitives::merkle_tree::CommitmentTree;
-use masp_primitives::sapling::Node;
+use masp_primitives::sapling::{Node, Nullifier};
 use masp_primitives::transaction::components::transparent::Authorization;
 use masp_primitives::transaction::components::{
     I128Sum, TxIn, TxOut, ValueSum,
 };
-use masp_primitives::transaction::{Transaction, TransparentAddress};
+use masp_primitives::transaction::{
+    Transaction, TransparentAddress, TxVersion,
+};
 use namada_core::address::{self, Address};
 use namada_core::arith::{CheckedAdd, CheckedSub, checked};
 use namada_core::booleans::BoolResultUnitExt;
+use namada_core::chain::BlockHeight;
 use namada_core::collections::HashSet;
-use namada_core::masp::{MaspEpoch, TAddrData, addr_taddr, encode_asset_type};
+use namada_core::masp::{
+    AssetData, MaspEpoch, TAddrData, addr_taddr, encode_asset_type,
+};
 use namada_core::storage::Key;
 use namada_core::token;
 use namada_core::token::{Amount, MaspDigitPos};
 use namada_core::uint::I320;
+use namada_gas::Gas;
 use namada_state::{
     ConversionState, OptionExt, ReadConversionState, ResultExt,
 };
 use namada_systems::{governance, ibc, parameters, trans_token};
 use namada_tx::BatchedTxRef;
 use namada_vp_env::{Error, Result, VpEnv};
+use thiserror::Error;
 
 use crate::storage_key::{
-    is_masp_key, is_masp_nullifier_key, is_masp_transfer_key,
-    is_masp_undated_balance_key, masp_commitment_anchor_key,
-    masp_commitment_tree_key, masp_convert_anchor_key, masp_nullifier_key,
-    masp_undated_balance_key,
+    is_masp_anchor_retention_window_key, is_masp_extended_transfer_key,
+    is_masp_gas_multiplier_key, is_masp_key, is_masp_nullifier_key,
+    is_masp_undated_balance_key, masp_anchor_retention_window_key,
+    masp_commitment_anchor_key, masp_commitment_tree_key,
+    masp_consensus_branch_id_key, masp_convert_anchor_key,
+    masp_convert_gas_multiplier_key, masp_extra_transfer_keys_key,
+    masp_frozen_until_key, masp_max_converts_per_tx_key,
+    masp_max_outputs_per_tx_key, masp_max_spends_per_tx_key,
+    masp_max_tokens_per_tx_key, masp_nullifier_key,
+    masp_output_gas_multiplier_key, masp_recent_commitment_anchors_key,
+    masp_recent_convert_anchors_key, masp_spend_gas_multiplier_key,
+    masp_undated_balance_key, transparent_address_hash,
 };
-use crate::validation::verify_shielded_tx;
+use crate::utils::resolve_asset_type;
+use crate::validation::{
+    VERIFY_GAS_MULTIPLIER_RANGE, VerifyGasMultipliers, verify_shielded_tx,
+};
+
+#[allow(missing_docs)]
+#[derive(Error, Debug)]
+pub enum MaspVpError {
+    #[error(
+        "MASP double spending attempt, the nullifier {:?} has already been \
+         revealed previously",
+        nullifier_bytes(.0)
+    )]
+    DoubleSpend(Nullifier),
+    #[error(
+        "The MASP nullifier {:?} was not committed, possibly because of a \
+         temporary write or a delete",
+        nullifier_bytes(.0)
+    )]
+    UncommittedNullifier(Nullifier),
+    #[error(
+        "The MASP nullifier {:?} was committed with associated data",
+        nullifier_bytes(.0)
+    )]
+    NullifierWithData(Nullifier),
+    #[error("MASP transaction targets the wrong consensus branch")]
+    WrongConsensusBranch { expected: u32, found: u32 },
+    #[error("MASP transaction has an unsupported version")]
+    UnsupportedTxVersion,
+    #[error(
+        "MASP transaction has {count} {kind} descriptions, exceeding the \
+         limit of {limit}"
+    )]
+    TooManyDescriptions {
+        kind: &'static str,
+        count: usize,
+        limit: u64,
+    },
+    #[error("MASP transaction is expired")]
+    ExpiredTx {
+        block_height: BlockHeight,
+        expiry_height: BlockHeight,
+    },
+    #[error(
+        "Spend descriptions disagree with the anchor declared by the sapling \
+         bundle"
+    )]
+    InconsistentSpendAnchors,
+    #[error("Spend description refers to an invalid anchor")]
+    InvalidSpendAnchor,
+    #[error(
+        "Spend description refers to an anchor that only exists in the \
+         post-state"
+    )]
+    PostStateSpendAnchor,
+    #[error(
+        "Spend description refers to an anchor outside of the retention window"
+    )]
+    ExpiredSpendAnchor,
+    #[error("The note commitment anchor retention window must not be zero")]
+    ZeroAnchorRetentionWindow,
+    #[error(
+        "MASP verification gas multiplier {0} is out of the range {}..={}",
+        VERIFY_GAS_MULTIPLIER_RANGE.start(),
+        VERIFY_GAS_MULTIPLIER_RANGE.end()
+    )]
+    GasMultiplierOutOfRange(u64),
+    #[error("Convert description refers to an invalid anchor")]
+    InvalidConvertAnchor,
+    #[error("Conversion tree not initialized in storage")]
+    UninitializedConvertTree,
+    #[error("The note commitment tree was incorrectly updated")]
+    CommitmentTreeMismatch,
+    #[error("The note commitment tree changed without any output descriptions")]
+    CommitmentTreeChangedWithoutOutputs,
+    #[error(
+        "MASP transaction moves {count} distinct tokens, exceeding the limit \
+         of {limit}"
+    )]
+    TooManyTokens { count: usize, limit: u64 },
+    #[error("Transparent bundle contains a zero-value entry")]
+    ZeroValueTransparentEntry,
+    #[error(
+        "Transparent bundle value {value} at digit {position:?} is out of the \
+         range of token amounts"
+    )]
+    TransparentValueOutOfRange { value: u64, position: MaspDigitPos },
+    #[error("{}", pool_imbalance_message(.deficits, .surpluses))]
+    TransparentPoolImbalance {
+        deficits: Vec<String>,
+        surpluses: Vec<String>,
+    },
+    #[error("{}", balance_mismatch_message(.undated, .mismatches))]
+    BalanceMismatch {
+        undated: bool,
+        mismatches: Vec<String>,
+    },
+    #[error("{}", unrecognized_assets_message(.entries))]
+    UnrecognizedTransparentAssets { entries: Vec<String> },
+    #[error(
+        "Transparent bundle asset type {0} has no corresponding Sapling value \
+         balance"
+    )]
+    OrphanTransparentAsset(AssetType),
+}
+
+impl From<MaspVpError> for Error {
+    fn from(value: MaspVpError) -> Self {
+        Error::new(value)
+    }
+}
+
+// The bytes of a nullifier, as rendered in double spending errors
+fn nullifier_bytes(nullifier: &Nullifier) -> &[u8; 32] {
+    &nullifier.0
+}
+
+// Describe an unbalanced transparent pool, reporting deficits first
+fn pool_imbalance_message(deficits: &[String], surpluses: &[String]) -> String {
+    if !deficits.is_empty() {
+        format!(
+            "Transparent transaction value pool must be nonnegative. \
+             Violation may be caused by transaction being constructed in \
+             previous epoch. Maybe try again. Unbalanced asset types: {}",
+            deficits.join(", ")
+        )
+    } else {
+        format!(
+            "Transaction fees cannot be left on the MASP balance. Unbalanced \
+             asset types: {}",
+            surpluses.join(", ")
+        )
+    }
+}
+
+// Describe the tokens whose balance changes disagree with the Sapling value
+// balance
+fn balance_mismatch_message(undated: &bool, mismatches: &[String]) -> String {
+    let undated = if *undated { "undated " } else { "" };
+    format!(
+        "MASP {undated}balance change not equal to {undated}Sapling value \
+         balance. Mismatched tokens: {}",
+        mismatches.join(", ")
+    )
+}
+
+// List the transparent entries whose asset types could not be recognized
+fn unrecognized_assets_message(entries: &[String]) -> String {
+    format!(
+        "Some transparent assets could not be recognized: {}",
+        entries.join(", ")
+    )
+}
 
 /// MASP VP
 pub struct MaspVp<'ctx, CTX, Params, Gov, Ibc, TransToken, Transfer> {
@@ -60,6 +229,8 @@ struct ChangedBalances {
     undated_pre: ValueSum<Address, Amount>,
     // Undated MASP balances after the tx
     undated_post: ValueSum<Address, Amount>,
+    // Tokens whose undated asset types have already been derived
+    undated_derived: BTreeSet<Address>,
 }
 
 // Default is manually implemented due to imperfect derive
@@ -72,6 +243,7 @@ impl Default for ChangedBalances {
             post: Default::default(),
             undated_pre: ValueSum::zero(),
             undated_post: ValueSum::zero(),
+            undated_derived: Default::default(),
         }
     }
 }
@@ -96,92 +268,89 @@ where
         keys_changed: &BTreeSet<Key>,
         verifiers: &BTreeSet<Address>,
     ) -> Result<()> {
-        // Allow any changes to be done by a governance proposal
+        // The balance keys of the MASP are also MASP keys
+        let masp_keys_changed: Vec<&Key> =
+            keys_changed.iter().filter(|key| is_masp_key(key)).collect();
+
+        // Changing no MASP keys at all is fine, even if the MASP was otherwise
+        // listed as a verifier of the tx
+        if masp_keys_changed.is_empty() {
+            return Ok(());
+        }
+
+        // Allow any changes to be done by a governance proposal, as long as
+        // the MASP parameters are left with values that shielded txs can be
+        // validated against
         if Gov::is_proposal_accepted(
             &ctx.pre(),
             tx_data.tx.data(tx_data.cmt).unwrap_or_default().as_ref(),
         )? {
-            return Ok(());
+            return valid_parameter_changes(&ctx, &masp_keys_changed);
         }
 
-        let masp_keys_changed: Vec<&Key> =
-            keys_changed.iter().filter(|key| is_masp_key(key)).collect();
-        let masp_transfer_changes = masp_keys_changed
-            .iter()
-            .all(|key| is_masp_transfer_key(key));
-
-        if masp_keys_changed.is_empty() {
-            // Changing no MASP keys at all is fine
-            Ok(())
-        } else if masp_transfer_changes {
+        if Self::only_transfer_keys_changed(ctx, &masp_keys_changed)? {
+            // No shielded transactions are accepted during a migration window
+            Self::ensure_not_frozen(ctx)?;
             // The MASP transfer keys can only be changed by a valid Transaction
             Self::is_valid_masp_transfer(ctx, tx_data, keys_changed, verifiers)
         } else {
-            return Err(Error::new_const(
+            Err(Error::new_const(
                 "A governance proposal is required to modify MASP \
                  non-transfer keys",
-            ));
+            ))
         }
     }
 
-    // Check that the transaction correctly revealed the nullifiers, if needed
-    fn valid_nullifiers_reveal(
-        ctx: &'ctx CTX,
+    /// Get the tokens whose balances are changed by a MASP transaction,
+    /// either on the side of the MASP or on that of its counterparts,
+    /// without validating the transaction.
+    pub fn tokens_in_transfer(
         keys_changed: &BTreeSet<Key>,
-        transaction: &Transaction,
-    ) -> Result<()> {
-        // Support set to check that a nullifier was not revealed more
-        // than once in the same tx
-        let mut revealed_nullifiers = HashSet::new();
-
-        for description in transaction
-            .sapling_bundle()
-            .map_or(&vec![], |bundle| &bundle.shielded_spends)
-        {
-            let nullifier_key = masp_nullifier_key(&description.nullifier);
-            if ctx.has_key_pre(&nullifier_key)?
-                || revealed_nullifiers.contains(&nullifier_key)
-            {
-                let error = Error::new_alloc(format!(
-                    "MASP double spending attempt, the nullifier {:?} has \
-                     already been revealed previously",
-                    description.nullifier.0,
-                ));
-                tracing::debug!("{error}");
-                return Err(error);
-            }
+    ) -> Result<BTreeSet<Address>> {
+        let balance_tokens = Self::changed_balance_accounts(keys_changed)
+            .map(|[token, _owner]| token.clone());
+        let undated_tokens =
+            keys_changed.iter().filter_map(is_masp_undated_balance_key);
+        Ok(balance_tokens.chain(undated_tokens).collect())
+    }
 
-            // Check that the nullifier is indeed committed (no temp write
-            // and no delete) and carries no associated data (the latter not
-            // strictly necessary for validation, but we don't expect any
-            // value for this key anyway)
-            ctx.read_bytes_post(&nullifier_key)?
-                .is_some_and(|value| value.is_empty())
-                .ok_or_else(|| {
-                    Error::new_const(
-                        "The nullifier should have been committed with no \
-                         associated data",
-                    )
-                })?;
+    // Get the token and owner of every balance changed by a transaction
+    fn changed_balance_accounts(
+        keys_changed: &BTreeSet<Key>,
+    ) -> impl Iterator<Item = [&Address; 2]> {
+        keys_changed
+            .iter()
+            .filter_map(TransToken::is_any_token_balance_key)
+    }
 
-            revealed_nullifiers.insert(nullifier_key);
-        }
+    // Check that all the given MASP keys can be changed by a transfer. On top
+    // of the default transfer keys, this includes any key falling under the
+    // extra key prefixes configured in storage.
+    fn only_transfer_keys_changed(
+        ctx: &'ctx CTX,
+        masp_keys_changed: &[&Key],
+    ) -> Result<bool> {
+        let extra_prefixes: BTreeSet<Key> = ctx
+            .read_pre(&masp_extra_transfer_keys_key())?
+            .unwrap_or_default();
+        Ok(masp_keys_changed
+            .iter()
+            .all(|key| is_masp_extended_transfer_key(key, &extra_prefixes)))
+    }
 
-        // Check that no unneeded nullifier has been revealed
-        for nullifier_key in
-            keys_changed.iter().filter(|key| is_masp_nullifier_key(key))
-        {
-            if !revealed_nullifiers.contains(nullifier_key) {
-                let error = Error::new_alloc(format!(
-                    "An unexpected MASP nullifier key {nullifier_key} has \
-                     been revealed by the transaction"
-                ));
-                tracing::debug!("{error}");
-                return Err(error);
-            }
-        }
+    /// Get the nullifiers revealed by the given shielded transaction, in the
+    /// order of its spend descriptions. Fails on the same double spends that
+    /// the VP rejects.
+    pub fn revealed_nullifiers(
+        ctx: &'ctx CTX,
+        shielded_tx: &Transaction,
+    ) -> Result<Vec<Nullifier>> {
+        reveal_nullifiers(&ctx, shielded_tx)
+    }
 
-        Ok(())
+    // Check that the MASP is not frozen at the current block height
+    fn ensure_not_frozen(ctx: &'ctx CTX) -> Result<()> {
+        valid_not_frozen(&ctx, ctx.get_block_height()?)
     }
 
     // Store the undated balances before and after this tx is applied.
@@ -219,103 +388,6 @@ where
         Ok(result)
     }
 
-    // Check that a transaction carrying output descriptions correctly updates
-    // the tree and anchor in storage
-    fn valid_note_commitment_update(
-        ctx: &'ctx CTX,
-        transaction: &Transaction,
-    ) -> Result<()> {
-        // Check that the merkle tree in storage has been correctly updated with
-        // the output descriptions cmu
-        let tree_key = masp_commitment_tree_key();
-        let mut previous_tree: CommitmentTree<Node> = ctx
-            .read_pre(&tree_key)?
-            .ok_or(Error::new_const("Cannot read storage"))?;
-        let post_tree: CommitmentTree<Node> = ctx
-            .read_post(&tree_key)?
-            .ok_or(Error::new_const("Cannot read storage"))?;
-
-        // Based on the output descriptions of the transaction, update the
-        // previous tree in storage
-        for description in transaction
-            .sapling_bundle()
-            .map_or(&vec![], |bundle| &bundle.shielded_outputs)
-        {
-            previous_tree
-                .append(Node::from_scalar(description.cmu))
-                .map_err(|()| {
-                    Error::new_const("Failed to update the commitment tree")
-                })?;
-        }
-        // Check that the updated previous tree matches the actual post tree.
-        // This verifies that all and only the necessary notes have been
-        // appended to the tree
-        if previous_tree != post_tree {
-            let error = Error::new_const(
-                "The note commitment tree was incorrectly updated",
-            );
-            tracing::debug!("{error}");
-            return Err(error);
-        }
-
-        Ok(())
-    }
-
-    // Check that the spend descriptions anchors of a transaction are valid
-    fn valid_spend_descriptions_anchor(
-        ctx: &'ctx CTX,
-        transaction: &Transaction,
-    ) -> Result<()> {
-        for description in transaction
-            .sapling_bundle()
-            .map_or(&vec![], |bundle| &bundle.shielded_spends)
-        {
-            let anchor_key = masp_commitment_anchor_key(description.anchor);
-
-            // Check if the provided anchor was published before
-            if !ctx.has_key_pre(&anchor_key)? {
-                let error = Error::new_const(
-                    "Spend description refers to an invalid anchor",
-                );
-                tracing::debug!("{error}");
-                return Err(error);
-            }
-        }
-
-        Ok(())
-    }
-
-    // Check that the convert descriptions anchors of a transaction are valid
-    fn valid_convert_descriptions_anchor(
-        ctx: &'ctx CTX,
-        transaction: &Transaction,
-    ) -> Result<()> {
-        if let Some(bundle) = transaction.sapling_bundle() {
-            if !bundle.shielded_converts.is_empty() {
-                let anchor_key = masp_convert_anchor_key();
-                let expected_anchor = ctx
-                    .read_pre::<namada_core::hash::Hash>(&anchor_key)?
-                    .ok_or(Error::new_const("Cannot read storage"))?;
-
-                for description in &bundle.shielded_converts {
-                    // Check if the provided anchor matches the current
-                    // conversion tree's one
-                    if namada_core::hash::Hash(description.anchor.to_bytes())
-                        != expected_anchor
-                    {
-                        let error = Error::new_const(
-                            "Convert description refers to an invalid anchor",
-                        );
-                        tracing::debug!("{error}");
-                        return Err(error);
-                    }
-                }
-            }
-        }
-
-        Ok(())
-    }
-
     // Apply the balance change to the changed balances structure
     fn apply_balance_change(
         ctx: &'ctx CTX,
@@ -326,7 +398,7 @@ where
             "No denomination found in storage for the given token",
         )?;
         // Record the token without an epoch to facilitate later decoding
-        undated_tokens(token, denom, &mut result.undated_tokens)?;
+        record_undated_tokens(token, denom, &mut result)?;
         let counterpart_balance_key =
             TransToken::balance_key(token, counterpart);
         let pre_balance: Amount =
@@ -334,7 +406,8 @@ where
         let post_balance: Amount =
             ctx.read_post(&counterpart_balance_key)?.unwrap_or_default();
         // Public keys must be the hash of the sources/targets
-        let addr_hash = addr_taddr(counterpart.clone());
+        let addr_hash =
+            TransparentAddress(transparent_address_hash(counterpart));
         // Enable the decoding of these counterpart addresses
         result
             .decoder
@@ -370,9 +443,8 @@ where
         tx_data: &[u8],
     ) -> Result<ChangedBalances> {
         // Get the changed balance keys
-        let mut counterparts_balances = keys_changed
-            .iter()
-            .filter_map(TransToken::is_any_token_balance_key);
+        let mut counterparts_balances =
+            Self::changed_balance_accounts(keys_changed);
 
         // Apply the balance changes to the changed balances structure
         let changed_balances = counterparts_balances
@@ -398,6 +470,7 @@ where
             post,
             undated_pre,
             undated_post,
+            undated_derived,
         } = changed_balances;
         let ibc::ChangedBalances { decoder, pre, post } =
             Ibc::apply_ibc_packet::<Transfer>(
@@ -413,6 +486,7 @@ where
             post,
             undated_pre,
             undated_post,
+            undated_derived,
         })
     }
 
@@ -460,13 +534,9 @@ where
                 })?
         };
 
-        if u64::from(ctx.get_block_height()?)
-            > u64::from(shielded_tx.expiry_height())
-        {
-            let error = Error::new_const("MASP transaction is expired");
-            tracing::debug!("{error}");
-            return Err(error);
-        }
+        // Reject txs built for another network upgrade, carrying too many
+        // descriptions or expired before doing any expensive work on them
+        valid_masp_tx_upfront(&ctx, ctx.get_block_height()?, &shielded_tx)?;
 
         // Check the validity of the keys and get the transfer data
         let changed_balances = Self::validate_state_and_get_transfer_data(
@@ -475,6 +545,9 @@ where
             &tx_data,
         )?;
 
+        // Bound the work done below for each of the tokens being moved
+        valid_token_count(&ctx, &changed_balances)?;
+
         // Some constants that will be used repeatedly
         let zero = ValueSum::zero();
         let masp_address_hash = addr_taddr(address::MASP);
@@ -506,10 +579,12 @@ where
         // nullifier is being revealed by the tx
         // 4. The transaction must correctly update the note commitment tree
         // in storage with the new output descriptions
-        Self::valid_spend_descriptions_anchor(ctx, &shielded_tx)?;
-        Self::valid_convert_descriptions_anchor(ctx, &shielded_tx)?;
-        Self::valid_nullifiers_reveal(ctx, keys_changed, &shielded_tx)?;
-        Self::valid_note_commitment_update(ctx, &shielded_tx)?;
+        // Gas is charged upfront for the work these checks do on the bundle
+        ctx.charge_gas(masp_state_checks_gas(&shielded_tx))?;
+        valid_spend_descriptions_anchor(&ctx, &shielded_tx)?;
+        valid_convert_descriptions_anchor(&ctx, &shielded_tx)?;
+        valid_nullifiers_reveal(&ctx, keys_changed, &shielded_tx)?;
+        valid_note_commitment_update(&ctx, keys_changed, &shielded_tx)?;
 
         // Checks on the transparent bundle, if present
         let mut changed_bals_minus_txn = changed_balances.clone();
@@ -635,398 +710,2411 @@ where
         }
 
         // Verify the proofs
-        verify_shielded_tx(&shielded_tx, |gas| ctx.charge_gas(gas))
+        let gas_multipliers = verify_gas_multipliers(&ctx)?;
+        verify_shielded_tx(&shielded_tx, &gas_multipliers, |gas| {
+            ctx.charge_gas(gas)
+        })
     }
 }
 
-// Make a map to help recognize asset types lacking an epoch
-fn undated_tokens(
-    token: &Address,
-    denom: token::Denomination,
-    tokens: &mut BTreeMap<
-        AssetType,
-        (Address, token::Denomination, MaspDigitPos),
-    >,
+/// Compute the gas to charge for the checks run by the MASP VP on the
+/// sapling bundle of `tx` against the state, i.e. the anchor and
+/// nullifier checks and the note commitment tree update. Proofs
+/// verification is charged separately, by [`verify_shielded_tx`].
+pub fn masp_state_checks_gas(tx: &Transaction) -> Gas {
+    let (spends, converts, outputs) = tx
+        .sapling_bundle()
+        .map(|bundle| {
+            (
+                bundle.shielded_spends.len(),
+                bundle.shielded_converts.len(),
+                bundle.shielded_outputs.len(),
+            )
+        })
+        .unwrap_or_default();
+    state_checks_gas(spends, converts, outputs)
+}
+
+// Gas for the state checks of a bundle with the given number of descriptions
+fn state_checks_gas(spends: usize, converts: usize, outputs: usize) -> Gas {
+    let cost = |count: usize, unit_cost: u64| {
+        u64::try_from(count)
+            .unwrap_or(u64::MAX)
+            .saturating_mul(unit_cost)
+    };
+    cost(spends, namada_gas::MASP_SPEND_STATE_CHECK_GAS)
+        .saturating_add(cost(
+            converts,
+            namada_gas::MASP_CONVERT_STATE_CHECK_GAS,
+        ))
+        .saturating_add(cost(outputs, namada_gas::MASP_OUTPUT_STATE_CHECK_GAS))
+        .into()
+}
+
+/// Minimal read access to the storage before and after a transaction, as
+/// required by the structural checks on shielded transactions. Implementors
+/// only need to provide the raw bytes of the relevant storage values, such
+/// that these checks can also run outside of the ledger (e.g. in light
+/// clients).
+pub trait MaspStorageRead {
+    /// Read the raw bytes of the given key before the tx
+    fn read_bytes_pre(&self, key: &Key) -> Result<Option<Vec<u8>>>;
+
+    /// Read the raw bytes of the given key after the tx
+    fn read_bytes_post(&self, key: &Key) -> Result<Option<Vec<u8>>>;
+
+    /// Check if the given key is present before the tx
+    fn has_key_pre(&self, key: &Key) -> Result<bool> {
+        Ok(self.read_bytes_pre(key)?.is_some())
+    }
+
+    /// Check if the given key is present after the tx
+    fn has_key_post(&self, key: &Key) -> Result<bool> {
+        Ok(self.read_bytes_post(key)?.is_some())
+    }
+
+    /// Read and decode the value of the given key before the tx
+    fn read_pre<T: BorshDeserialize>(&self, key: &Key) -> Result<Option<T>> {
+        self.read_bytes_pre(key)?
+            .map(|bytes| T::try_from_slice(&bytes).into_storage_result())
+            .transpose()
+    }
+
+    /// Read and decode the value of the given key after the tx
+    fn read_post<T: BorshDeserialize>(&self, key: &Key) -> Result<Option<T>> {
+        self.read_bytes_post(key)?
+            .map(|bytes| T::try_from_slice(&bytes).into_storage_result())
+            .transpose()
+    }
+}
+
+impl<'ctx, CTX> MaspStorageRead for &'ctx CTX
+where
+    CTX: VpEnv<'ctx>,
+{
+    fn read_bytes_pre(&self, key: &Key) -> Result<Option<Vec<u8>>> {
+        VpEnv::read_bytes_pre(*self, key)
+    }
+
+    fn read_bytes_post(&self, key: &Key) -> Result<Option<Vec<u8>>> {
+        VpEnv::read_bytes_post(*self, key)
+    }
+
+    fn has_key_pre(&self, key: &Key) -> Result<bool> {
+        VpEnv::has_key_pre(*self, key)
+    }
+
+    fn has_key_post(&self, key: &Key) -> Result<bool> {
+        VpEnv::has_key_post(*self, key)
+    }
+
+    fn read_pre<T: BorshDeserialize>(&self, key: &Key) -> Result<Option<T>> {
+        VpEnv::read_pre(*self, key)
+    }
+
+    fn read_post<T: BorshDeserialize>(&self, key: &Key) -> Result<Option<T>> {
+        VpEnv::read_post(*self, key)
+    }
+}
+
+/// Check that the transaction targets the consensus branch expected by the
+/// network and a supported transaction version. The expected branch id is
+/// read from storage, falling back to the MASP branch if none was set.
+pub fn valid_consensus_branch<S: MaspStorageRead>(
+    state: &S,
+    transaction: &Transaction,
 ) -> Result<()> {
-    for digit in MaspDigitPos::iter() {
-        let asset_type = encode_asset_type(token.clone(), denom, digit, None)
-            .wrap_err("unable to create asset type")?;
-        tokens.insert(asset_type, (token.clone(), denom, digit));
+    let expected: u32 = state
+        .read_pre(&masp_consensus_branch_id_key())?
+        .unwrap_or_else(|| u32::from(BranchId::MASP));
+    let found = u32::from(transaction.consensus_branch_id());
+    if found != expected {
+        let error =
+            Error::from(MaspVpError::WrongConsensusBranch { expected, found });
+        tracing::debug!("{error}");
+        return Err(error);
+    }
+    if transaction.version() != TxVersion::MASPv5 {
+        let error = Error::from(MaspVpError::UnsupportedTxVersion);
+        tracing::debug!("{error}");
+        return Err(error);
     }
     Ok(())
 }
 
-fn validate_transparent_input<A: Authorization>(
-    vin: &TxIn<A>,
-    changed_balances: &mut ChangedBalances,
-    transparent_tx_pool: &mut I128Sum,
-    epoch: MaspEpoch,
-    conversion_state: &ConversionState,
-    authorizers: &mut BTreeSet<TransparentAddress>,
+/// Check that the MASP is not frozen at the given block height. The MASP is
+/// frozen until the height configured in storage, if any.
+pub fn valid_not_frozen<S: MaspStorageRead>(
+    state: &S,
+    block_height: BlockHeight,
 ) -> Result<()> {
-    // A decrease in the balance of an account needs to be
-    // authorized by the account of this transparent input
-    authorizers.insert(vin.address);
-    // Non-masp sources add to the transparent tx pool
-    *transparent_tx_pool = transparent_tx_pool
-        .checked_add(
-            &I128Sum::from_nonnegative(vin.asset_type, i128::from(vin.value))
-                .ok()
-                .ok_or_err_msg("invalid value or asset type for amount")?,
-        )
-        .ok_or_err_msg("Overflow in input sum")?;
-
-    let bal_ref = changed_balances
-        .pre
-        .entry(vin.address)
-        .or_insert(ValueSum::zero());
-
-    match conversion_state.assets.get(&vin.asset_type) {
-        // Note how the asset's epoch must be equal to the present: users
-        // must never be allowed to backdate transparent inputs to a
-        // transaction for they would then be able to claim rewards while
-        // locking their assets for negligible time periods.
-        Some(asset) if asset.epoch == epoch => {
-            let amount = token::Amount::from_masp_denominated(
-                vin.value,
-                asset.digit_pos,
-            );
-            *bal_ref = bal_ref
-                .checked_sub(&ValueSum::from_pair(asset.token.clone(), amount))
-                .ok_or_else(|| {
-                    Error::new_const("Underflow in bundle balance")
-                })?;
-        }
-        // Maybe the asset type has no attached epoch
-        None if changed_balances
-            .undated_tokens
-            .contains_key(&vin.asset_type) =>
-        {
-            let (token, denom, digit) =
-                &changed_balances.undated_tokens[&vin.asset_type];
-            // Determine what the asset type would be if it were dated
-            let dated_asset_type =
-                encode_asset_type(token.clone(), *denom, *digit, Some(epoch))
-                    .wrap_err("unable to create asset type")?;
-            if conversion_state.assets.contains_key(&dated_asset_type) {
-                // If such a dated asset type is available in the
-                // conversion tree, then we must reject the undated
-                // variant
-                let error =
-                    Error::new_const("epoch is missing from asset type");
-                tracing::debug!("{error}");
-                return Err(error);
-            } else {
-                // Otherwise note the contribution to this transparent input.
-                // This branch represents the case of an asset not being part
-                // of the conversion tree: the asset can carry no epoch at all
-                // or any epoch (even a future one). Given the way we construct
-                // conversions it's not an issue if we later add it to the
-                // conversion tree: if the epoch preceeds the one at which we
-                // start computing rewards or is missing, then this asset will
-                // not be entitled. If it had instead been constructed with a
-                // future epoch that matches or follows the one at which we
-                // start giving out rewards, then it will be entitled (and
-                // there's no issue with that since it was clearly in the pool
-                // even before that time)
-                let amount =
-                    token::Amount::from_masp_denominated(vin.value, *digit);
-                *bal_ref = bal_ref
-                    .checked_sub(&ValueSum::from_pair(token.clone(), amount))
-                    .ok_or_else(|| {
-                        Error::new_const("Underflow in bundle balance")
-                    })?;
-            }
-        }
-        // unrecognized asset
-        _ => {
-            let error = Error::new_const("Unable to decode asset type");
+    let frozen_until: Option<BlockHeight> =
+        state.read_pre(&masp_frozen_until_key())?;
+    match frozen_until {
+        Some(frozen_until) if block_height < frozen_until => {
+            let error = Error::new_alloc(format!(
+                "MASP is frozen for migration until height {frozen_until}"
+            ));
             tracing::debug!("{error}");
-            return Err(error);
+            Err(error)
         }
-    };
-    Ok(())
+        _ => Ok(()),
+    }
 }
 
-fn validate_transparent_output(
-    out: &TxOut,
-    changed_balances: &mut ChangedBalances,
-    transparent_tx_pool: &mut I128Sum,
-    epoch: MaspEpoch,
-    conversion_state: &ConversionState,
+/// Run the checks that reject a shielded transaction before any expensive
+/// work is done on it, i.e. that it targets the expected consensus branch,
+/// carries no more descriptions than allowed and has not expired at the
+/// given block height.
+pub fn valid_masp_tx_upfront<S: MaspStorageRead>(
+    state: &S,
+    block_height: BlockHeight,
+    transaction: &Transaction,
 ) -> Result<()> {
-    // Non-masp destinations subtract from transparent tx pool
-    *transparent_tx_pool = transparent_tx_pool
-        .checked_sub(
-            &I128Sum::from_nonnegative(out.asset_type, i128::from(out.value))
-                .ok()
-                .ok_or_err_msg("invalid value or asset type for amount")?,
-        )
-        .ok_or_err_msg("Underflow in output subtraction")?;
-
-    let bal_ref = changed_balances
-        .post
-        .entry(out.address)
-        .or_insert(ValueSum::zero());
+    valid_consensus_branch(state, transaction)?;
+    valid_description_counts(state, transaction)?;
+    valid_expiry(block_height, u32::from(transaction.expiry_height()))
+}
 
-    match conversion_state.assets.get(&out.asset_type) {
-        Some(asset) if asset.epoch <= epoch => {
-            let amount = token::Amount::from_masp_denominated(
-                out.value,
-                asset.digit_pos,
-            );
-            *bal_ref = bal_ref
-                .checked_sub(&ValueSum::from_pair(asset.token.clone(), amount))
-                .ok_or_else(|| {
-                    Error::new_const("Underflow in bundle balance")
-                })?;
-        }
-        // Maybe the asset type has no attached epoch
-        None if changed_balances
-            .undated_tokens
-            .contains_key(&out.asset_type) =>
-        {
-            // Otherwise note the contribution to this transparent output
-            let (token, _denom, digit) =
-                &changed_balances.undated_tokens[&out.asset_type];
-            let amount =
-                token::Amount::from_masp_denominated(out.value, *digit);
-            *bal_ref = bal_ref
-                .checked_sub(&ValueSum::from_pair(token.clone(), amount))
-                .ok_or_else(|| {
-                    Error::new_const("Underflow in bundle balance")
-                })?;
-        }
-        // unrecognized asset
-        _ => {
-            let error = Error::new_const("Unable to decode asset type");
+/// Check that the transaction does not carry more spend, convert and output
+/// descriptions than the limits configured in storage. Descriptions of a kind
+/// without a configured limit are not bounded.
+pub fn valid_description_counts<S: MaspStorageRead>(
+    state: &S,
+    transaction: &Transaction,
+) -> Result<()> {
+    let Some(bundle) = transaction.sapling_bundle() else {
+        return Ok(());
+    };
+    for (kind, count, limit_key) in [
+        (
+            "spend",
+            bundle.shielded_spends.len(),
+            masp_max_spends_per_tx_key(),
+        ),
+        (
+            "convert",
+            bundle.shielded_converts.len(),
+            masp_max_converts_per_tx_key(),
+        ),
+        (
+            "output",
+            bundle.shielded_outputs.len(),
+            masp_max_outputs_per_tx_key(),
+        ),
+    ] {
+        let Some(limit) = state.read_pre::<u64>(&limit_key)? else {
+            continue;
+        };
+        if u64::try_from(count).unwrap_or(u64::MAX) > limit {
+            let error = Error::from(MaspVpError::TooManyDescriptions {
+                kind,
+                count,
+                limit,
+            });
             tracing::debug!("{error}");
             return Err(error);
         }
-    };
+    }
     Ok(())
 }
 
-// Update the transaction value pool and also ensure that the Transaction is
-// consistent with the balance changes. I.e. the transparent inputs are not more
-// than the initial balances and that the transparent outputs are not more than
-// the final balances. Also ensure that the sapling value balance is exactly 0.
-fn validate_transparent_bundle(
-    shielded_tx: &Transaction,
-    changed_balances: &mut ChangedBalances,
-    epoch: MaspEpoch,
-    conversion_state: &ConversionState,
-    authorizers: &mut BTreeSet<TransparentAddress>,
+/// Read the multipliers of the gas charged for verifying the spend, convert
+/// and output descriptions of shielded transactions from storage.
+/// Descriptions of a kind without a configured multiplier are charged the
+/// default gas costs.
+pub fn verify_gas_multipliers<S: MaspStorageRead>(
+    state: &S,
+) -> Result<VerifyGasMultipliers> {
+    let defaults = VerifyGasMultipliers::default();
+    Ok(VerifyGasMultipliers {
+        spend: state
+            .read_pre(&masp_spend_gas_multiplier_key())?
+            .unwrap_or(defaults.spend),
+        convert: state
+            .read_pre(&masp_convert_gas_multiplier_key())?
+            .unwrap_or(defaults.convert),
+        output: state
+            .read_pre(&masp_output_gas_multiplier_key())?
+            .unwrap_or(defaults.output),
+    })
+}
+
+// Check that the balance changes of a transaction do not involve more distinct
+// tokens than the limit configured in storage, if any
+fn valid_token_count<S: MaspStorageRead>(
+    state: &S,
+    changed_balances: &ChangedBalances,
 ) -> Result<()> {
-    // The Sapling value balance adds to the transparent tx pool
-    let mut transparent_tx_pool = shielded_tx.sapling_value_balance();
+    let Some(limit) = state.read_pre::<u64>(&masp_max_tokens_per_tx_key())?
+    else {
+        return Ok(());
+    };
+    let tokens: BTreeSet<&Address> = changed_balances
+        .pre
+        .values()
+        .chain(changed_balances.post.values())
+        .chain([
+            &changed_balances.undated_pre,
+            &changed_balances.undated_post,
+        ])
+        .flat_map(|balances| balances.components().map(|(token, _)| token))
+        .collect();
+    let count = tokens.len();
+    if u64::try_from(count).unwrap_or(u64::MAX) > limit {
+        let error = Error::from(MaspVpError::TooManyTokens { count, limit });
+        tracing::debug!("{error}");
+        return Err(error);
+    }
+    Ok(())
+}
 
-    if let Some(transp_bundle) = shielded_tx.transparent_bundle() {
-        for vin in transp_bundle.vin.iter() {
-            validate_transparent_input(
-                vin,
-                changed_balances,
-                &mut transparent_tx_pool,
-                epoch,
-                conversion_state,
-                authorizers,
-            )?;
-        }
+/// Expiry height of shielded transactions that never expire. This is the
+/// height set by the MASP builder when no expiration is requested.
+pub const NO_EXPIRY_HEIGHT: u32 = u32::MAX;
 
-        for out in transp_bundle.vout.iter() {
-            validate_transparent_output(
-                out,
-                changed_balances,
-                &mut transparent_tx_pool,
-                epoch,
-                conversion_state,
-            )?;
-        }
+// Check that a shielded transaction has not expired at the given block height
+fn valid_expiry(block_height: BlockHeight, expiry_height: u32) -> Result<()> {
+    if expiry_height == NO_EXPIRY_HEIGHT {
+        return Ok(());
+    }
+    let expiry_height = BlockHeight(u64::from(expiry_height));
+    if block_height > expiry_height {
+        let error = Error::from(MaspVpError::ExpiredTx {
+            block_height,
+            expiry_height,
+        });
+        tracing::debug!("{error}");
+        return Err(error);
     }
+    Ok(())
+}
 
-    // Ensure that the shielded transaction exactly balances
-    match transparent_tx_pool.partial_cmp(&I128Sum::zero()) {
-        None | Some(Ordering::Less) => {
-            let error = Error::new_const(
-                "Transparent transaction value pool must be nonnegative. \
-                 Violation may be caused by transaction being constructed in \
-                 previous epoch. Maybe try again.",
-            );
+/// Check that the transaction correctly revealed the nullifiers, if needed.
+/// Returns the keys of the revealed nullifiers.
+pub fn valid_nullifiers_reveal<S: MaspStorageRead>(
+    state: &S,
+    keys_changed: &BTreeSet<Key>,
+    transaction: &Transaction,
+) -> Result<HashSet<Key>> {
+    let revealed_nullifiers: HashSet<Key> =
+        reveal_nullifiers(state, transaction)?
+            .iter()
+            .map(masp_nullifier_key)
+            .collect();
+
+    // Check that no unneeded nullifier has been revealed
+    for nullifier_key in
+        keys_changed.iter().filter(|key| is_masp_nullifier_key(key))
+    {
+        if !revealed_nullifiers.contains(nullifier_key) {
+            let error = Error::new_alloc(format!(
+                "An unexpected MASP nullifier key {nullifier_key} has been \
+                 revealed by the transaction"
+            ));
             tracing::debug!("{error}");
-            // The remaining value in the transparent transaction value pool
-            // MUST be nonnegative.
-            Err(error)
+            return Err(error);
         }
-        Some(Ordering::Greater) => {
-            let error = Error::new_const(
-                "Transaction fees cannot be left on the MASP balance.",
-            );
+    }
+
+    Ok(revealed_nullifiers)
+}
+
+// Collect the nullifiers revealed by the spend descriptions of a
+// transaction, checking that each of them is committed and revealed only
+// once. This takes a single post-state read per spend description: VPs have
+// no way to fetch several keys in one round-trip, and iterating the nullifier
+// prefix would visit every nullifier ever revealed.
+fn reveal_nullifiers<S: MaspStorageRead>(
+    state: &S,
+    transaction: &Transaction,
+) -> Result<Vec<Nullifier>> {
+    let nullifiers = unrevealed_nullifiers(state, transaction)?;
+
+    for nullifier in &nullifiers {
+        // Check that the nullifier is indeed committed (no temp write
+        // and no delete) and carries no associated data (the latter not
+        // strictly necessary for validation, but we don't expect any
+        // value for this key anyway)
+        let error =
+            match state.read_bytes_post(&masp_nullifier_key(nullifier))? {
+                None => MaspVpError::UncommittedNullifier(*nullifier),
+                Some(value) if !value.is_empty() => {
+                    MaspVpError::NullifierWithData(*nullifier)
+                }
+                Some(_) => continue,
+            };
+        let error = Error::from(error);
+        tracing::debug!("{error}");
+        return Err(error);
+    }
+
+    Ok(nullifiers)
+}
+
+// Collect the nullifiers revealed by the spend descriptions of a
+// transaction, checking against the pre-state that none of them was revealed
+// before, be it by an earlier tx or earlier in this one
+fn unrevealed_nullifiers<S: MaspStorageRead>(
+    state: &S,
+    transaction: &Transaction,
+) -> Result<Vec<Nullifier>> {
+    // Support set to check that a nullifier was not revealed more
+    // than once in the same tx
+    let mut revealed_nullifiers = HashSet::new();
+    let mut nullifiers = vec![];
+
+    for description in transaction
+        .sapling_bundle()
+        .map_or(&vec![], |bundle| &bundle.shielded_spends)
+    {
+        let nullifier_key = masp_nullifier_key(&description.nullifier);
+        if state.has_key_pre(&nullifier_key)?
+            || !revealed_nullifiers.insert(nullifier_key)
+        {
+            let error =
+                Error::from(MaspVpError::DoubleSpend(description.nullifier));
             tracing::debug!("{error}");
-            Err(error)
+            return Err(error);
         }
-        _ => Ok(()),
+        nullifiers.push(description.nullifier);
+    }
+
+    Ok(nullifiers)
+}
+
+/// The chain data, besides its state, that a shielded transaction is
+/// validated against off-chain
+#[derive(Debug, Clone, Copy)]
+pub struct OfflineMaspContext<'a> {
+    /// Height of the block the transaction is expected to be included in
+    pub block_height: BlockHeight,
+    /// MASP epoch of that block
+    pub masp_epoch: MaspEpoch,
+    /// Conversion state of the MASP at that epoch
+    pub conversion_state: &'a ConversionState,
+    /// Denominations of the tokens whose undated asset types the transparent
+    /// bundle of the transaction may refer to
+    pub tokens: &'a BTreeMap<Address, token::Denomination>,
+}
+
+/// Run the checks of the MASP VP on a shielded transaction which only depend
+/// on the state before it gets applied, e.g. to let clients pre-validate a
+/// transaction against a snapshot of the chain before broadcasting it. The
+/// checks run in the same sequence as in the VP and the transaction is
+/// rejected with the same errors.
+///
+/// The checks on the state changes of the transaction, such as the update of
+/// the note commitment tree and the transparent balance changes, are left to
+/// the VP: the transparent bundle is only checked on its own. A snapshot
+/// should serve the same data as its pre- and post-state.
+pub fn validate_masp_tx_state_offline<S: MaspStorageRead>(
+    state: &S,
+    context: &OfflineMaspContext<'_>,
+    transaction: &Transaction,
+) -> Result<()> {
+    valid_not_frozen(state, context.block_height)?;
+    valid_masp_tx_upfront(state, context.block_height, transaction)?;
+
+    // Without any balance changes, the tokens moved by the tx are those of
+    // its transparent bundle
+    let mut changed_balances = ChangedBalances::default();
+    for (token, denom) in context.tokens {
+        record_undated_tokens(token, *denom, &mut changed_balances)?;
     }
+    let BundleDebits { inputs, outputs } = transparent_bundle_debits(
+        transaction,
+        &changed_balances.undated_tokens,
+        context.masp_epoch,
+        context.conversion_state,
+        &mut BTreeSet::new(),
+    )?;
+    changed_balances.pre = inputs;
+    changed_balances.post = outputs;
+    valid_token_count(state, &changed_balances)?;
+
+    valid_spend_descriptions_anchor(state, transaction)?;
+    valid_convert_descriptions_anchor(state, transaction)?;
+    // Without any state changes, the nullifiers can only be checked against
+    // the ones revealed before and those revealed earlier in this tx
+    unrevealed_nullifiers(state, transaction)?;
+    Ok(())
 }
 
-// Apply the given Sapling value balance component to the accumulator
-fn apply_balance_component(
-    acc: &ValueSum<Address, I320>,
-    val: i128,
-    digit: MaspDigitPos,
-    address: Address,
-) -> Result<ValueSum<Address, I320>> {
-    // Put val into the correct digit position
-    let decoded_change = I320::from_masp_denominated(val, digit)
-        .map_err(|_| Error::new_const("Overflow in MASP value balance"))?;
-    // Tag the numerical change with the token type
-    let decoded_change = ValueSum::from_pair(address, decoded_change);
-    // Apply the change to the accumulator
-    acc.checked_add(&decoded_change)
-        .ok_or_else(|| Error::new_const("Overflow in MASP value balance"))
+/// Validate a shielded transaction off-chain, running the checks of
+/// [`validate_masp_tx_state_offline`] followed by the verification of its
+/// proofs. The gas of the verification is not charged.
+pub fn validate_masp_tx_offline<S: MaspStorageRead>(
+    state: &S,
+    context: &OfflineMaspContext<'_>,
+    transaction: &Transaction,
+) -> Result<()> {
+    validate_masp_tx_state_offline(state, context, transaction)?;
+    let gas_multipliers = verify_gas_multipliers(state)?;
+    verify_shielded_tx(transaction, &gas_multipliers, |_gas| Ok(()))
 }
 
-// Verify that the pre balance - the Sapling value balance = the post balance
-// using the decodings in tokens and conversion_state for assistance.
-#[allow(clippy::too_many_arguments)]
-fn verify_sapling_balancing_value(
-    pre: &ValueSum<Address, Amount>,
-    post: &ValueSum<Address, Amount>,
-    undated_pre: &ValueSum<Address, Amount>,
-    undated_post: &ValueSum<Address, Amount>,
-    sapling_value_balance: &I128Sum,
-    target_epoch: MaspEpoch,
-    tokens: &BTreeMap<AssetType, (Address, token::Denomination, MaspDigitPos)>,
-    conversion_state: &ConversionState,
+/// Check that a transaction carrying output descriptions correctly updates
+/// the tree and anchor in storage
+pub fn valid_note_commitment_update<S: MaspStorageRead>(
+    state: &S,
+    keys_changed: &BTreeSet<Key>,
+    transaction: &Transaction,
 ) -> Result<()> {
-    let mut acc = ValueSum::<Address, I320>::from_sum(post.clone());
-    let mut undated_acc =
-        ValueSum::<Address, I320>::from_sum(undated_post.clone());
-    for (asset_type, val) in sapling_value_balance.components() {
-        // Only assets with at most the target timestamp count
-        match conversion_state.assets.get(asset_type) {
-            Some(asset) if asset.epoch <= target_epoch => {
-                acc = apply_balance_component(
-                    &acc,
-                    *val,
-                    asset.digit_pos,
-                    asset.token.clone(),
-                )?;
-            }
-            None if tokens.contains_key(asset_type) => {
-                let (token, _denom, digit) = &tokens[asset_type];
-                acc =
-                    apply_balance_component(&acc, *val, *digit, token.clone())?;
-                // Additionally record separately the undated changes
-                undated_acc = apply_balance_component(
-                    &undated_acc,
-                    *val,
-                    *digit,
-                    token.clone(),
-                )?;
-            }
-            _ => {
-                let error = Error::new_const("Unable to decode asset type");
-                tracing::debug!("{error}");
-                return Err(error);
-            }
-        }
+    let tree_key = masp_commitment_tree_key();
+    let outputs = transaction
+        .sapling_bundle()
+        .map_or(&vec![], |bundle| &bundle.shielded_outputs);
+    // Without any output descriptions nor write to the tree, there is
+    // nothing to check
+    if outputs.is_empty() && !keys_changed.contains(&tree_key) {
+        return Ok(());
     }
-    if acc != ValueSum::from_sum(pre.clone()) {
-        let error = Error::new_const(
-            "MASP balance change not equal to Sapling value balance",
-        );
+
+    // Check that the merkle tree in storage has been correctly updated with
+    // the output descriptions cmu
+    let mut previous_tree: CommitmentTree<Node> = state
+        .read_pre(&tree_key)?
+        .ok_or(Error::new_const("Cannot read storage"))?;
+    let post_tree: CommitmentTree<Node> = state
+        .read_post(&tree_key)?
+        .ok_or(Error::new_const("Cannot read storage"))?;
+
+    // Only output descriptions can append notes to the tree
+    if outputs.is_empty()
+        && (previous_tree.size() != post_tree.size()
+            || previous_tree.root() != post_tree.root())
+    {
+        let error =
+            Error::from(MaspVpError::CommitmentTreeChangedWithoutOutputs);
         tracing::debug!("{error}");
-        Err(error)
-    } else if undated_acc != ValueSum::from_sum(undated_pre.clone()) {
-        let error = Error::new_const(
-            "MASP undated balance change not equal to undated Sapling value \
-             balance",
-        );
+        return Err(error);
+    }
+
+    // Based on the output descriptions of the transaction, update the
+    // previous tree in storage
+    append_output_commitments(&mut previous_tree, transaction)?;
+    // Check that the updated previous tree matches the actual post tree.
+    // Comparing the roots verifies that all and only the necessary notes
+    // have been appended to the tree, while comparing the sizes rules out
+    // trailing empty leaves, which do not affect the root
+    if previous_tree.size() != post_tree.size()
+        || previous_tree.root() != post_tree.root()
+    {
+        let error = Error::from(MaspVpError::CommitmentTreeMismatch);
         tracing::debug!("{error}");
         return Err(error);
-    } else {
-        Ok(())
     }
+    // Keep checking the stronger structural invariant in debug builds
+    debug_assert!(
+        previous_tree == post_tree,
+        "Commitment trees with matching roots should be equal"
+    );
+
+    Ok(())
 }
 
-#[cfg(test)]
-mod shielded_token_tests {
-    use std::cell::RefCell;
-    use std::collections::BTreeSet;
+/// Compute the root of the note commitment tree that results from applying
+/// the given transaction to `pre_tree`, i.e. the root that
+/// [`valid_note_commitment_update`] expects to find in the post-state. This
+/// can be used to predict the anchor of transactions chained after
+/// `transaction`.
+pub fn expected_post_tree_root(
+    pre_tree: &CommitmentTree<Node>,
+    transaction: &Transaction,
+) -> Result<Node> {
+    let mut tree = pre_tree.clone();
+    append_output_commitments(&mut tree, transaction)?;
+    Ok(tree.root())
+}
 
-    use namada_core::address::MASP;
-    use namada_core::address::testing::nam;
-    use namada_core::borsh::BorshSerializeExt;
-    use namada_gas::{TxGasMeter, VpGasMeter};
-    use namada_state::testing::{TestState, arb_account_storage_key, arb_key};
-    use namada_state::{StateRead, TxIndex};
-    use namada_trans_token::Amount;
-    use namada_trans_token::storage_key::balance_key;
-    use namada_tx::{BatchedTx, Tx};
-    use namada_vm::WasmCacheRwAccess;
-    use namada_vm::wasm::VpCache;
-    use namada_vm::wasm::compilation_cache::common::testing::vp_cache;
-    use namada_vm::wasm::run::VpEvalWasm;
-    use namada_vp::native_vp::{self, CtxPostStorageRead, CtxPreStorageRead};
-    use namada_vp_env::Error;
-    use proptest::proptest;
-    use proptest::strategy::Strategy;
+// Append the note commitments of the output descriptions of a transaction to
+// the given commitment tree
+fn append_output_commitments(
+    tree: &mut CommitmentTree<Node>,
+    transaction: &Transaction,
+) -> Result<()> {
+    let outputs = transaction
+        .sapling_bundle()
+        .map_or(&vec![], |bundle| &bundle.shielded_outputs);
+    for description in outputs {
+        tree.append(Node::from_scalar(description.cmu))
+            .map_err(|()| {
+                Error::new_const("Failed to update the commitment tree")
+            })?;
+    }
+    Ok(())
+}
 
-    use crate::storage_key::{
-        is_masp_key, is_masp_token_map_key, is_masp_transfer_key,
+/// Check that the spend descriptions anchors of a transaction are valid
+pub fn valid_spend_descriptions_anchor<S: MaspStorageRead>(
+    state: &S,
+    transaction: &Transaction,
+) -> Result<()> {
+    let spends = transaction
+        .sapling_bundle()
+        .map_or(&vec![], |bundle| &bundle.shielded_spends);
+
+    // The encoding of a sapling bundle declares a single anchor for all of
+    // its spend descriptions, taken from the first one. Reject spends that
+    // disagree with it, as their anchors would not survive serialization.
+    if let Some(bundle_anchor) = spends.first().map(|spend| spend.anchor) {
+        if spends.iter().any(|spend| spend.anchor != bundle_anchor) {
+            let error = Error::from(MaspVpError::InconsistentSpendAnchors);
+            tracing::debug!("{error}");
+            return Err(error);
+        }
+    }
+
+    for description in spends {
+        valid_spend_anchor(state, description.anchor)?;
+    }
+
+    Ok(())
+}
+
+/// Check that a spend description anchor was published before the tx, or
+/// that it is the root of the commitment tree left by the preceding txs of
+/// the current block
+pub fn valid_spend_anchor<S: MaspStorageRead>(
+    state: &S,
+    anchor: Scalar,
+) -> Result<()> {
+    let anchor_key = masp_commitment_anchor_key(anchor);
+
+    // Check if the provided anchor was published before
+    if state.has_key_pre(&anchor_key)? {
+        if is_within_anchor_retention_window(state, anchor)? {
+            return Ok(());
+        }
+        let error = Error::from(MaspVpError::ExpiredSpendAnchor);
+        tracing::debug!("{error}");
+        return Err(error);
+    }
+    // Anchors are only published at the end of a block, so notes created
+    // earlier in the same block can only be spent against the current root
+    // of the tree, which is about to be published anyway
+    let pre_tree: Option<CommitmentTree<Node>> =
+        state.read_pre(&masp_commitment_tree_key())?;
+    if pre_tree.is_some_and(|tree| tree.root() == Node::from_scalar(anchor)) {
+        return Ok(());
+    }
+    // Distinguish anchors published by this very tx, which point at an
+    // ordering bug rather than a bogus anchor
+    let error = if state.has_key_post(&anchor_key)? {
+        Error::from(MaspVpError::PostStateSpendAnchor)
+    } else {
+        Error::from(MaspVpError::InvalidSpendAnchor)
     };
+    tracing::debug!("{error}");
+    Err(error)
+}
 
-    type CA = WasmCacheRwAccess;
-    type Eval<S> = VpEvalWasm<<S as StateRead>::D, <S as StateRead>::H, CA>;
-    type Ctx<'ctx, S> = native_vp::Ctx<'ctx, S, VpCache<CA>, Eval<S>>;
-    type MaspVp<'ctx, S> = super::MaspVp<
-        'ctx,
-        Ctx<'ctx, S>,
-        namada_parameters::Store<
-            CtxPreStorageRead<'ctx, 'ctx, S, VpCache<CA>, Eval<S>>,
-        >,
-        namada_governance::Store<
-            CtxPreStorageRead<'ctx, 'ctx, S, VpCache<CA>, Eval<S>>,
-        >,
-        namada_ibc::Store<
-            CtxPostStorageRead<'ctx, 'ctx, S, VpCache<CA>, Eval<S>>,
-        >,
-        namada_trans_token::Store<
-            CtxPreStorageRead<'ctx, 'ctx, S, VpCache<CA>, Eval<S>>,
-        >,
-        (),
-    >;
+/// Check that a published spend description anchor is among the most recent
+/// ones, if an anchor retention window is configured
+pub fn is_within_anchor_retention_window<S: MaspStorageRead>(
+    state: &S,
+    anchor: Scalar,
+) -> Result<bool> {
+    if state
+        .read_pre::<u64>(&masp_anchor_retention_window_key())?
+        .is_none()
+    {
+        return Ok(true);
+    }
+    // Until an anchor gets recorded under the window, the current root of
+    // the tree is the only recent one
+    let recent_anchors: Vec<Node> =
+        match state.read_pre(&masp_recent_commitment_anchors_key())? {
+            Some(recent_anchors) => recent_anchors,
+            None => state
+                .read_pre::<CommitmentTree<Node>>(&masp_commitment_tree_key())?
+                .map(|tree| tree.root())
+                .into_iter()
+                .collect(),
+        };
+    Ok(recent_anchors.contains(&Node::from_scalar(anchor)))
+}
 
-    // Changing only the balance key of the MASP is invalid
-    #[test]
-    fn test_balance_change() {
-        let mut state = TestState::default();
-        namada_parameters::init_test_storage(&mut state).unwrap();
-        let src_key = balance_key(&nam(), &MASP);
-        let amount = Amount::native_whole(100);
-        let keys_changed = BTreeSet::from([src_key.clone()]);
-        let verifiers = Default::default();
+/// Check that the MASP parameters changed by a governance proposal are left
+/// with values that shielded transactions can be validated against
+pub fn valid_parameter_changes<S: MaspStorageRead>(
+    state: &S,
+    masp_keys_changed: &[&Key],
+) -> Result<()> {
+    for key in masp_keys_changed {
+        let error = if is_masp_anchor_retention_window_key(key) {
+            match state.read_post::<u64>(key)? {
+                Some(0) => MaspVpError::ZeroAnchorRetentionWindow,
+                _ => continue,
+            }
+        } else if is_masp_gas_multiplier_key(key) {
+            match state.read_post::<u64>(key)? {
+                Some(multiplier)
+                    if !VERIFY_GAS_MULTIPLIER_RANGE.contains(&multiplier) =>
+                {
+                    MaspVpError::GasMultiplierOutOfRange(multiplier)
+                }
+                _ => continue,
+            }
+        } else {
+            continue;
+        };
+        let error = Error::from(error);
+        tracing::debug!("{error}");
+        return Err(error);
+    }
+    Ok(())
+}
 
-        // Initialize MASP balance
-        state
-            .db_write(&src_key, Amount::native_whole(100).serialize_to_vec())
-            .unwrap();
+/// Check that the convert descriptions anchors of a transaction are valid
+pub fn valid_convert_descriptions_anchor<S: MaspStorageRead>(
+    state: &S,
+    transaction: &Transaction,
+) -> Result<()> {
+    if let Some(bundle) = transaction.sapling_bundle() {
+        if !bundle.shielded_converts.is_empty() {
+            let Some(accepted_anchors) = accepted_convert_anchors(state)?
+            else {
+                let error = Error::from(MaspVpError::UninitializedConvertTree);
+                tracing::debug!("{error}");
+                return Err(error);
+            };
 
-        state.db_write(&src_key, amount.serialize_to_vec()).unwrap();
+            for description in &bundle.shielded_converts {
+                // Check if the provided anchor matches the current
+                // conversion tree's one, or a recent one
+                let anchor =
+                    namada_core::hash::Hash(description.anchor.to_bytes());
+                if !accepted_anchors.contains(&anchor) {
+                    let error = Error::from(MaspVpError::InvalidConvertAnchor);
+                    tracing::debug!("{error}");
+                    return Err(error);
+                }
+            }
+        }
+    }
 
-        let tx_index = TxIndex::default();
-        let mut tx = Tx::from_type(namada_tx::data::TxType::Raw);
-        tx.push_default_inner_tx();
-        let BatchedTx { tx, cmt } = tx.batch_first_tx();
+    Ok(())
+}
 
-        // Test both credit and debit
-        for new_amount in [150, 1] {
-            // Update the balance key
-            let new_amount = Amount::native_whole(new_amount);
-            let _ = state
-                .write_log_mut()
-                .write(&src_key, new_amount.serialize_to_vec())
-                .unwrap();
+/// Read the convert anchors that convert descriptions are accepted against,
+/// starting with the current one, or `None` if the conversion tree was not
+/// initialized
+pub fn accepted_convert_anchors<S: MaspStorageRead>(
+    state: &S,
+) -> Result<Option<Vec<namada_core::hash::Hash>>> {
+    let Some(current_anchor) =
+        state.read_pre::<namada_core::hash::Hash>(&masp_convert_anchor_key())?
+    else {
+        return Ok(None);
+    };
+    // Converts built right before the conversion tree got updated refer to
+    // the anchor of the previous masp epoch
+    let recent_anchors: Vec<namada_core::hash::Hash> = state
+        .read_pre(&masp_recent_convert_anchors_key())?
+        .unwrap_or_default();
+    Ok(Some(
+        std::iter::once(current_anchor)
+            .chain(recent_anchors)
+            .collect(),
+    ))
+}
+
+// Make a map to help recognize asset types lacking an epoch
+fn undated_tokens(
+    token: &Address,
+    denom: token::Denomination,
+    tokens: &mut BTreeMap<
+        AssetType,
+        (Address, token::Denomination, MaspDigitPos),
+    >,
+) -> Result<()> {
+    for digit in MaspDigitPos::iter() {
+        let asset_type = encode_asset_type(token.clone(), denom, digit, None)
+            .wrap_err("unable to create asset type")?;
+        tokens.insert(asset_type, (token.clone(), denom, digit));
+    }
+    Ok(())
+}
+
+// Record the undated asset types of the given token, unless they were already
+// derived for this transaction. Returns whether they had to be derived.
+fn record_undated_tokens(
+    token: &Address,
+    denom: token::Denomination,
+    result: &mut ChangedBalances,
+) -> Result<bool> {
+    if result.undated_derived.contains(token) {
+        return Ok(false);
+    }
+    undated_tokens(token, denom, &mut result.undated_tokens)?;
+    result.undated_derived.insert(token.clone());
+    Ok(true)
+}
+
+// Non-masp sources add to the transparent tx pool
+fn add_transparent_input_to_pool<A: Authorization>(
+    vin: &TxIn<A>,
+    transparent_tx_pool: &mut I128Sum,
+) -> Result<()> {
+    *transparent_tx_pool = transparent_tx_pool
+        .checked_add(
+            &I128Sum::from_nonnegative(vin.asset_type, i128::from(vin.value))
+                .ok()
+                .ok_or_err_msg("invalid value or asset type for amount")?,
+        )
+        .ok_or_err_msg("Overflow in input sum")?;
+    Ok(())
+}
+
+// Non-masp destinations subtract from transparent tx pool
+fn sub_transparent_output_from_pool(
+    out: &TxOut,
+    transparent_tx_pool: &mut I128Sum,
+) -> Result<()> {
+    *transparent_tx_pool = transparent_tx_pool
+        .checked_sub(
+            &I128Sum::from_nonnegative(out.asset_type, i128::from(out.value))
+                .ok()
+                .ok_or_err_msg("invalid value or asset type for amount")?,
+        )
+        .ok_or_err_msg("Underflow in output subtraction")?;
+    Ok(())
+}
+
+/// Compute the residual value of the transparent transaction pool of a
+/// shielded transaction, i.e. its sapling value balance plus the transparent
+/// inputs minus the transparent outputs. The MASP VP requires this residual
+/// to be exactly zero: this function does not enforce it, such that clients
+/// can inspect which asset types are unbalanced and by how much.
+pub fn compute_transparent_pool_residual(
+    shielded_tx: &Transaction,
+) -> Result<I128Sum> {
+    let mut transparent_tx_pool = shielded_tx.sapling_value_balance();
+    if let Some(transp_bundle) = shielded_tx.transparent_bundle() {
+        for vin in transp_bundle.vin.iter() {
+            add_transparent_input_to_pool(vin, &mut transparent_tx_pool)?;
+        }
+        for out in transp_bundle.vout.iter() {
+            sub_transparent_output_from_pool(out, &mut transparent_tx_pool)?;
+        }
+    }
+    Ok(transparent_tx_pool)
+}
+
+// Returns false if the asset type of the input could not be recognized
+fn validate_transparent_input<A: Authorization>(
+    vin: &TxIn<A>,
+    undated_tokens: &BTreeMap<
+        AssetType,
+        (Address, token::Denomination, MaspDigitPos),
+    >,
+    debits: &mut BTreeMap<TransparentAddress, ValueSum<Address, Amount>>,
+    transparent_tx_pool: &mut I128Sum,
+    epoch: MaspEpoch,
+    conversion_state: &ConversionState,
+    authorizers: &mut BTreeSet<TransparentAddress>,
+) -> Result<bool> {
+    // A decrease in the balance of an account needs to be
+    // authorized by the account of this transparent input
+    authorizers.insert(vin.address);
+    add_transparent_input_to_pool(vin, transparent_tx_pool)?;
+
+    match resolve_asset_type(conversion_state, undated_tokens, &vin.asset_type)
+    {
+        // Note how the asset's epoch must be equal to the present: users
+        // must never be allowed to backdate transparent inputs to a
+        // transaction for they would then be able to claim rewards while
+        // locking their assets for negligible time periods.
+        Some(AssetData {
+            token,
+            position,
+            epoch: Some(asset_epoch),
+            ..
+        }) if asset_epoch == epoch => {
+            let amount = transparent_amount(vin.value, position)?;
+            add_debit(debits, vin.address, token, amount)?;
+        }
+        // Maybe the asset type has no attached epoch
+        Some(AssetData {
+            token,
+            denom,
+            position,
+            epoch: None,
+        }) => {
+            // Determine what the asset type would be if it were dated
+            let dated_asset_type =
+                encode_asset_type(token.clone(), denom, position, Some(epoch))
+                    .wrap_err("unable to create asset type")?;
+            if conversion_state.assets.contains_key(&dated_asset_type) {
+                // If such a dated asset type is available in the
+                // conversion tree, then we must reject the undated
+                // variant
+                let error =
+                    Error::new_const("epoch is missing from asset type");
+                tracing::debug!("{error}");
+                return Err(error);
+            } else {
+                // Otherwise note the contribution to this transparent input.
+                // This branch represents the case of an asset not being part
+                // of the conversion tree: the asset can carry no epoch at all
+                // or any epoch (even a future one). Given the way we construct
+                // conversions it's not an issue if we later add it to the
+                // conversion tree: if the epoch preceeds the one at which we
+                // start computing rewards or is missing, then this asset will
+                // not be entitled. If it had instead been constructed with a
+                // future epoch that matches or follows the one at which we
+                // start giving out rewards, then it will be entitled (and
+                // there's no issue with that since it was clearly in the pool
+                // even before that time)
+                let amount = transparent_amount(vin.value, position)?;
+                add_debit(debits, vin.address, token, amount)?;
+            }
+        }
+        // unrecognized asset
+        _ => return Ok(false),
+    };
+    Ok(true)
+}
+
+// Add an amount of the given token to the debits of a transparent address
+fn add_debit(
+    debits: &mut BTreeMap<TransparentAddress, ValueSum<Address, Amount>>,
+    address: TransparentAddress,
+    token: Address,
+    amount: Amount,
+) -> Result<()> {
+    let debit = debits.entry(address).or_insert(ValueSum::zero());
+    *debit = debit
+        .checked_add(&ValueSum::from_pair(token, amount))
+        .ok_or_else(|| Error::new_const("Overflow in bundle balance"))?;
+    Ok(())
+}
+
+// Convert the value of a transparent bundle entry into a token amount,
+// rejecting values that the digit position pushes beyond the range of token
+// amounts. Amounts are raw integers independent of the token's denomination,
+// so the latter places no further bound on the value.
+fn transparent_amount(
+    value: u64,
+    position: MaspDigitPos,
+) -> Result<token::Amount> {
+    let amount = token::Amount::from_masp_denominated(value, position);
+    if amount > token::Amount::max_signed() {
+        let error = Error::from(MaspVpError::TransparentValueOutOfRange {
+            value,
+            position,
+        });
+        tracing::debug!("{error}");
+        return Err(error);
+    }
+    Ok(amount)
+}
+
+// Returns false if the asset type of the output could not be recognized
+fn validate_transparent_output(
+    out: &TxOut,
+    undated_tokens: &BTreeMap<
+        AssetType,
+        (Address, token::Denomination, MaspDigitPos),
+    >,
+    debits: &mut BTreeMap<TransparentAddress, ValueSum<Address, Amount>>,
+    transparent_tx_pool: &mut I128Sum,
+    epoch: MaspEpoch,
+    conversion_state: &ConversionState,
+) -> Result<bool> {
+    sub_transparent_output_from_pool(out, transparent_tx_pool)?;
+
+    match resolve_asset_type(conversion_state, undated_tokens, &out.asset_type)
+    {
+        Some(AssetData {
+            token,
+            position,
+            epoch: Some(asset_epoch),
+            ..
+        }) if asset_epoch <= epoch => {
+            let amount = transparent_amount(out.value, position)?;
+            add_debit(debits, out.address, token, amount)?;
+        }
+        // Maybe the asset type has no attached epoch
+        Some(AssetData {
+            token,
+            position,
+            epoch: None,
+            ..
+        }) => {
+            // Otherwise note the contribution to this transparent output
+            let amount = transparent_amount(out.value, position)?;
+            add_debit(debits, out.address, token, amount)?;
+        }
+        // unrecognized asset
+        _ => return Ok(false),
+    };
+    Ok(true)
+}
+
+// The amounts debited from transparent addresses by a transparent bundle
+#[derive(Debug, Default)]
+struct BundleDebits {
+    // Debited from the initial balances by the transparent inputs
+    inputs: BTreeMap<TransparentAddress, ValueSum<Address, Amount>>,
+    // Debited from the final balances by the transparent outputs
+    outputs: BTreeMap<TransparentAddress, ValueSum<Address, Amount>>,
+}
+
+// Ensure that the Transaction is consistent with the balance changes. I.e. the
+// transparent inputs are not more than the initial balances and that the
+// transparent outputs are not more than the final balances. Also ensure that
+// the transparent bundle is valid on its own.
+fn validate_transparent_bundle(
+    shielded_tx: &Transaction,
+    changed_balances: &mut ChangedBalances,
+    epoch: MaspEpoch,
+    conversion_state: &ConversionState,
+    authorizers: &mut BTreeSet<TransparentAddress>,
+) -> Result<()> {
+    let BundleDebits { inputs, outputs } = transparent_bundle_debits(
+        shielded_tx,
+        &changed_balances.undated_tokens,
+        epoch,
+        conversion_state,
+        authorizers,
+    )?;
+    for (balances, debits) in [
+        (&mut changed_balances.pre, inputs),
+        (&mut changed_balances.post, outputs),
+    ] {
+        for (address, debit) in debits {
+            let bal_ref = balances.entry(address).or_insert(ValueSum::zero());
+            *bal_ref = bal_ref.checked_sub(&debit).ok_or_else(|| {
+                Error::new_const("Underflow in bundle balance")
+            })?;
+        }
+    }
+    Ok(())
+}
+
+// Update the transaction value pool and compute the amounts debited by the
+// transparent bundle, ensuring that its entries are recognized and move funds
+// in and out of the shielded pool. Also ensure that the sapling value balance
+// is exactly 0.
+fn transparent_bundle_debits(
+    shielded_tx: &Transaction,
+    undated_tokens: &BTreeMap<
+        AssetType,
+        (Address, token::Denomination, MaspDigitPos),
+    >,
+    epoch: MaspEpoch,
+    conversion_state: &ConversionState,
+    authorizers: &mut BTreeSet<TransparentAddress>,
+) -> Result<BundleDebits> {
+    let mut debits = BundleDebits::default();
+    // The Sapling value balance adds to the transparent tx pool
+    let mut transparent_tx_pool = shielded_tx.sapling_value_balance();
+
+    if let Some(transp_bundle) = shielded_tx.transparent_bundle() {
+        // Zero-value entries do not move any funds and would only pad the tx
+        let has_zero_value = transp_bundle.vin.iter().any(|vin| vin.value == 0)
+            || transp_bundle.vout.iter().any(|out| out.value == 0);
+        if has_zero_value {
+            let error = Error::from(MaspVpError::ZeroValueTransparentEntry);
+            tracing::debug!("{error}");
+            return Err(error);
+        }
+
+        // Collect every unrecognized entry so that the error can point at
+        // the offending indices
+        let mut unrecognized = vec![];
+        for (index, vin) in transp_bundle.vin.iter().enumerate() {
+            let recognized = validate_transparent_input(
+                vin,
+                undated_tokens,
+                &mut debits.inputs,
+                &mut transparent_tx_pool,
+                epoch,
+                conversion_state,
+                authorizers,
+            )?;
+            if !recognized {
+                unrecognized.push(format!("vin {index} ({})", vin.asset_type));
+            }
+        }
+
+        for (index, out) in transp_bundle.vout.iter().enumerate() {
+            let recognized = validate_transparent_output(
+                out,
+                undated_tokens,
+                &mut debits.outputs,
+                &mut transparent_tx_pool,
+                epoch,
+                conversion_state,
+            )?;
+            if !recognized {
+                unrecognized.push(format!("vout {index} ({})", out.asset_type));
+            }
+        }
+
+        if !unrecognized.is_empty() {
+            let error =
+                Error::from(MaspVpError::UnrecognizedTransparentAssets {
+                    entries: unrecognized,
+                });
+            tracing::debug!("{error}");
+            return Err(error);
+        }
+
+        ensure_no_orphan_transparent_assets(shielded_tx)?;
+    }
+
+    // Ensure that the shielded transaction exactly balances
+    ensure_balanced_transparent_pool(&transparent_tx_pool)?;
+    Ok(debits)
+}
+
+// Ensure that every asset type referenced by the transparent bundle is
+// matched by a contribution to the Sapling value balance, rather than merely
+// passing through the MASP
+fn ensure_no_orphan_transparent_assets(
+    shielded_tx: &Transaction,
+) -> Result<()> {
+    let Some(transp_bundle) = shielded_tx.transparent_bundle() else {
+        return Ok(());
+    };
+    let sapling_value_balance = shielded_tx.sapling_value_balance();
+    let sapling_asset_types: BTreeSet<_> = sapling_value_balance
+        .components()
+        .map(|(asset_type, _)| asset_type)
+        .collect();
+
+    let orphan = transp_bundle
+        .vin
+        .iter()
+        .map(|vin| &vin.asset_type)
+        .chain(transp_bundle.vout.iter().map(|out| &out.asset_type))
+        .find(|asset_type| !sapling_asset_types.contains(asset_type));
+    if let Some(asset_type) = orphan {
+        let error =
+            Error::from(MaspVpError::OrphanTransparentAsset(*asset_type));
+        tracing::debug!("{error}");
+        return Err(error);
+    }
+    Ok(())
+}
+
+// Ensure that every asset type in the transparent transaction value pool nets
+// out to exactly zero, naming each unbalanced asset type in the error
+fn ensure_balanced_transparent_pool(
+    transparent_tx_pool: &I128Sum,
+) -> Result<()> {
+    let mut deficits = vec![];
+    let mut surpluses = vec![];
+    for (asset_type, val) in transparent_tx_pool.components() {
+        match val.cmp(&0) {
+            Ordering::Less => deficits.push(format!("{asset_type} ({val})")),
+            Ordering::Greater => {
+                surpluses.push(format!("{asset_type} ({val})"))
+            }
+            Ordering::Equal => {}
+        }
+    }
+
+    // The remaining value in the transparent transaction value pool MUST be
+    // nonnegative, and no fees can be left on the MASP balance
+    if !deficits.is_empty() || !surpluses.is_empty() {
+        let error = Error::from(MaspVpError::TransparentPoolImbalance {
+            deficits,
+            surpluses,
+        });
+        tracing::debug!("{error}");
+        Err(error)
+    } else {
+        Ok(())
+    }
+}
+
+// Apply the given Sapling value balance component to the accumulator
+fn apply_balance_component(
+    acc: &ValueSum<Address, I320>,
+    val: i128,
+    digit: MaspDigitPos,
+    address: Address,
+) -> Result<ValueSum<Address, I320>> {
+    // Put val into the correct digit position
+    let decoded_change = I320::from_masp_denominated(val, digit)
+        .map_err(|_| Error::new_const("Overflow in MASP value balance"))?;
+    // Tag the numerical change with the token type
+    let decoded_change = ValueSum::from_pair(address, decoded_change);
+    // Apply the change to the accumulator
+    acc.checked_add(&decoded_change)
+        .ok_or_else(|| Error::new_const("Overflow in MASP value balance"))
+}
+
+// Describe every token whose expected balance differs from the balance
+// obtained by applying the Sapling value balance
+fn balance_mismatches(
+    expected: &ValueSum<Address, I320>,
+    found: &ValueSum<Address, I320>,
+) -> Vec<String> {
+    let expected: BTreeMap<_, _> = expected.components().collect();
+    let found: BTreeMap<_, _> = found.components().collect();
+    let tokens: BTreeSet<_> = expected.keys().chain(found.keys()).collect();
+    tokens
+        .into_iter()
+        .filter_map(|token| {
+            let expected =
+                expected.get(token).copied().copied().unwrap_or_default();
+            let found = found.get(token).copied().copied().unwrap_or_default();
+            (expected != found).then(|| {
+                format!(
+                    "{token} (expected {}, found {})",
+                    expected.to_string_native(),
+                    found.to_string_native()
+                )
+            })
+        })
+        .collect()
+}
+
+// Verify that the pre balance - the Sapling value balance = the post balance
+// using the decodings in tokens and conversion_state for assistance.
+#[allow(clippy::too_many_arguments)]
+fn verify_sapling_balancing_value(
+    pre: &ValueSum<Address, Amount>,
+    post: &ValueSum<Address, Amount>,
+    undated_pre: &ValueSum<Address, Amount>,
+    undated_post: &ValueSum<Address, Amount>,
+    sapling_value_balance: &I128Sum,
+    target_epoch: MaspEpoch,
+    tokens: &BTreeMap<AssetType, (Address, token::Denomination, MaspDigitPos)>,
+    conversion_state: &ConversionState,
+) -> Result<()> {
+    let mut acc = ValueSum::<Address, I320>::from_sum(post.clone());
+    let mut undated_acc =
+        ValueSum::<Address, I320>::from_sum(undated_post.clone());
+    for (asset_type, val) in sapling_value_balance.components() {
+        // Only assets with at most the target timestamp count
+        match resolve_asset_type(conversion_state, tokens, asset_type) {
+            Some(AssetData {
+                token,
+                position,
+                epoch: Some(asset_epoch),
+                ..
+            }) if asset_epoch <= target_epoch => {
+                acc = apply_balance_component(&acc, *val, position, token)?;
+            }
+            Some(AssetData {
+                token,
+                position,
+                epoch: None,
+                ..
+            }) => {
+                acc = apply_balance_component(
+                    &acc,
+                    *val,
+                    position,
+                    token.clone(),
+                )?;
+                // Additionally record separately the undated changes
+                undated_acc = apply_balance_component(
+                    &undated_acc,
+                    *val,
+                    position,
+                    token,
+                )?;
+            }
+            _ => {
+                let error = Error::new_const("Unable to decode asset type");
+                tracing::debug!("{error}");
+                return Err(error);
+            }
+        }
+    }
+    let mismatches = balance_mismatches(&ValueSum::from_sum(pre.clone()), &acc);
+    if !mismatches.is_empty() {
+        let error = Error::from(MaspVpError::BalanceMismatch {
+            undated: false,
+            mismatches,
+        });
+        tracing::debug!("{error}");
+        return Err(error);
+    }
+    let mismatches = balance_mismatches(
+        &ValueSum::from_sum(undated_pre.clone()),
+        &undated_acc,
+    );
+    if !mismatches.is_empty() {
+        let error = Error::from(MaspVpError::BalanceMismatch {
+            undated: true,
+            mismatches,
+        });
+        tracing::debug!("{error}");
+        Err(error)
+    } else {
+        Ok(())
+    }
+}
+
+/// Test fixtures for the MASP checks that don't need a ledger context
+#[cfg(test)]
+pub(crate) mod test_utils {
+    use std::cell::Cell;
+    use std::collections::BTreeMap;
+
+    use masp_primitives::consensus::{BlockHeight, BranchId};
+    use masp_primitives::ff::PrimeField;
+    use masp_primitives::merkle_tree::FrozenCommitmentTree;
+    use masp_primitives::sapling::redjubjub::{PublicKey, Signature};
+    use masp_primitives::sapling::{Node, Nullifier};
+    use masp_primitives::transaction::components::sapling::{
+        Authorized as SaplingAuthorized, Bundle as SaplingBundle,
+        ConvertDescription, OutputDescription, SpendDescription,
+    };
+    use masp_primitives::transaction::components::transparent::{
+        Authorized as TransparentAuthorized, Bundle as TransparentBundle,
+    };
+    use masp_primitives::transaction::components::{GROTH_PROOF_SIZE, I128Sum};
+    use masp_primitives::transaction::{
+        Authorized, Transaction, TransactionData, TxVersion,
+    };
+    use masp_primitives::{bls12_381, jubjub};
+    use namada_core::address::Address;
+    use namada_core::borsh::BorshSerializeExt;
+    use namada_core::masp::{MaspEpoch, encode_asset_type};
+    use namada_core::storage::Key;
+    use namada_core::token::{Denomination, MaspDigitPos};
+    use namada_state::testing::TestState;
+    use namada_state::{
+        ConversionLeaf, ReadConversionState, WithConversionState,
+    };
+
+    use super::{MaspStorageRead, Result};
+    use crate::storage_key::masp_convert_anchor_key;
+
+    /// An in-memory view of the storage before and after a tx
+    #[derive(Default)]
+    pub(crate) struct InMemoryMaspStorage {
+        pub pre: BTreeMap<Key, Vec<u8>>,
+        pub post: BTreeMap<Key, Vec<u8>>,
+        /// Number of reads of the post-state
+        pub post_reads: Cell<usize>,
+    }
+
+    impl MaspStorageRead for InMemoryMaspStorage {
+        fn read_bytes_pre(&self, key: &Key) -> Result<Option<Vec<u8>>> {
+            Ok(self.pre.get(key).cloned())
+        }
+
+        fn read_bytes_post(&self, key: &Key) -> Result<Option<Vec<u8>>> {
+            self.post_reads.set(self.post_reads.get().saturating_add(1));
+            Ok(self.post.get(key).cloned())
+        }
+    }
+
+    /// Seed the conversion state with an asset type of the given token for
+    /// each of the given epochs, carrying the paired conversion, and commit
+    /// the anchor of the resulting conversion tree to storage. Returns the
+    /// committed anchor.
+    pub(crate) fn seed_conversion_state(
+        state: &mut TestState,
+        token: &Address,
+        denom: Denomination,
+        conversions: &[(MaspEpoch, I128Sum)],
+    ) -> bls12_381::Scalar {
+        for (epoch, conversion) in conversions {
+            let asset_type = encode_asset_type(
+                token.clone(),
+                denom,
+                MaspDigitPos::Zero,
+                Some(*epoch),
+            )
+            .unwrap();
+            let leaf_pos = state.conversion_state().assets.len();
+            state.conversion_state_mut().assets.insert(
+                asset_type,
+                ConversionLeaf {
+                    token: token.clone(),
+                    denom,
+                    digit_pos: MaspDigitPos::Zero,
+                    epoch: *epoch,
+                    conversion: conversion.clone().into(),
+                    leaf_pos,
+                },
+            );
+        }
+        let mut leaves: Vec<_> =
+            state.conversion_state().assets.values().collect();
+        leaves.sort_by_key(|leaf| leaf.leaf_pos);
+        let notes: Vec<_> = leaves
+            .into_iter()
+            .map(|leaf| Node::new(leaf.conversion.cmu().to_repr()))
+            .collect();
+        state.conversion_state_mut().tree = FrozenCommitmentTree::new(&notes);
+        let anchor =
+            bls12_381::Scalar::from(state.conversion_state().tree.root());
+        state
+            .db_write(
+                &masp_convert_anchor_key(),
+                namada_core::hash::Hash(anchor.to_bytes()).serialize_to_vec(),
+            )
+            .unwrap();
+        anchor
+    }
+
+    /// A signature made of zero bytes, which never verifies
+    pub(crate) fn dummy_signature() -> Signature {
+        Signature::read(&[0; 64][..]).unwrap()
+    }
+
+    /// A spend description with the given anchor and a nullifier made of the
+    /// given byte, carrying a bogus proof and signature
+    pub(crate) fn dummy_spend(
+        anchor: bls12_381::Scalar,
+        nullifier: u8,
+    ) -> SpendDescription<SaplingAuthorized> {
+        SpendDescription {
+            cv: jubjub::ExtendedPoint::identity(),
+            anchor,
+            nullifier: Nullifier([nullifier; 32]),
+            rk: PublicKey(jubjub::ExtendedPoint::identity()),
+            zkproof: [0; GROTH_PROOF_SIZE],
+            spend_auth_sig: dummy_signature(),
+        }
+    }
+
+    /// A convert description with the given anchor and a bogus proof
+    pub(crate) fn dummy_convert(
+        anchor: bls12_381::Scalar,
+    ) -> ConvertDescription<[u8; GROTH_PROOF_SIZE]> {
+        ConvertDescription {
+            cv: jubjub::ExtendedPoint::identity(),
+            anchor,
+            zkproof: [0; GROTH_PROOF_SIZE],
+        }
+    }
+
+    /// An output description of the given note commitment with a bogus proof
+    pub(crate) fn dummy_output(
+        cmu: u64,
+    ) -> OutputDescription<[u8; GROTH_PROOF_SIZE]> {
+        OutputDescription {
+            cv: jubjub::ExtendedPoint::identity(),
+            cmu: bls12_381::Scalar::from(cmu),
+            ephemeral_key: [0u8; 32].into(),
+            enc_ciphertext: std::array::from_fn(|_| 0),
+            out_ciphertext: std::array::from_fn(|_| 0),
+            zkproof: [0; GROTH_PROOF_SIZE],
+        }
+    }
+
+    /// A balanced Sapling bundle of the given descriptions with a bogus
+    /// binding signature
+    pub(crate) fn sapling_bundle(
+        shielded_spends: Vec<SpendDescription<SaplingAuthorized>>,
+        shielded_converts: Vec<ConvertDescription<[u8; GROTH_PROOF_SIZE]>>,
+        shielded_outputs: Vec<OutputDescription<[u8; GROTH_PROOF_SIZE]>>,
+    ) -> SaplingBundle<SaplingAuthorized> {
+        SaplingBundle {
+            shielded_spends,
+            shielded_converts,
+            shielded_outputs,
+            value_balance: I128Sum::zero(),
+            authorization: SaplingAuthorized {
+                binding_sig: dummy_signature(),
+            },
+        }
+    }
+
+    /// A MASP tx on the MASP branch with the given expiry height and bundles
+    pub(crate) fn masp_tx(
+        expiry_height: u32,
+        transparent_bundle: Option<TransparentBundle<TransparentAuthorized>>,
+        sapling_bundle: Option<SaplingBundle<SaplingAuthorized>>,
+    ) -> Transaction {
+        TransactionData::<Authorized>::from_parts(
+            TxVersion::MASPv5,
+            BranchId::MASP,
+            0,
+            BlockHeight::from_u32(expiry_height),
+            transparent_bundle,
+            sapling_bundle,
+        )
+        .freeze()
+        .unwrap()
+    }
+
+    /// A shielded tx made of the given descriptions, expiring at genesis
+    pub(crate) fn shielded_tx(
+        shielded_spends: Vec<SpendDescription<SaplingAuthorized>>,
+        shielded_converts: Vec<ConvertDescription<[u8; GROTH_PROOF_SIZE]>>,
+        shielded_outputs: Vec<OutputDescription<[u8; GROTH_PROOF_SIZE]>>,
+    ) -> Transaction {
+        masp_tx(
+            0,
+            None,
+            Some(sapling_bundle(
+                shielded_spends,
+                shielded_converts,
+                shielded_outputs,
+            )),
+        )
+    }
+}
+
+#[cfg(test)]
+mod shielded_token_tests {
+    use std::cell::{Cell, RefCell};
+    use std::collections::{BTreeMap, BTreeSet};
+
+    use masp_primitives::bls12_381;
+    use masp_primitives::consensus::BranchId;
+    use masp_primitives::merkle_tree::CommitmentTree;
+    use masp_primitives::sapling::{Node, Nullifier};
+    use masp_primitives::transaction::components::{I128Sum, ValueSum};
+    use namada_core::address::testing::{btc, nam};
+    use namada_core::address::{Address, MASP};
+    use namada_core::arith::CheckedAdd;
+    use namada_core::borsh::BorshSerializeExt;
+    use namada_core::masp::{MaspEpoch, encode_asset_type};
+    use namada_core::storage::{Key, KeySeg};
+    use namada_core::token::{Denomination, MaspDigitPos};
+    use namada_gas::{TxGasMeter, VpGasMeter};
+    use namada_state::testing::{TestState, arb_account_storage_key, arb_key};
+    use namada_state::{ReadConversionState, StateRead, StorageRead, TxIndex};
+    use namada_trans_token::Amount;
+    use namada_trans_token::storage_key::balance_key;
+    use namada_tx::{BatchedTx, Tx};
+    use namada_vm::WasmCacheRwAccess;
+    use namada_vm::wasm::VpCache;
+    use namada_vm::wasm::compilation_cache::common::testing::vp_cache;
+    use namada_vm::wasm::run::VpEvalWasm;
+    use namada_vp::native_vp::{self, CtxPostStorageRead, CtxPreStorageRead};
+    use namada_vp_env::Error;
+    use proptest::proptest;
+    use proptest::strategy::Strategy;
+
+    use super::test_utils::{
+        InMemoryMaspStorage, dummy_convert, dummy_output, dummy_spend, masp_tx,
+        sapling_bundle, seed_conversion_state, shielded_tx,
+    };
+    use crate::storage_key::{
+        is_masp_key, is_masp_token_map_key, is_masp_transfer_key,
+        masp_convert_anchor_key, masp_extra_transfer_keys_key,
+        masp_frozen_until_key,
+    };
+
+    type CA = WasmCacheRwAccess;
+    type Eval<S> = VpEvalWasm<<S as StateRead>::D, <S as StateRead>::H, CA>;
+    type Ctx<'ctx, S> = native_vp::Ctx<'ctx, S, VpCache<CA>, Eval<S>>;
+    type MaspVp<'ctx, S> = super::MaspVp<
+        'ctx,
+        Ctx<'ctx, S>,
+        namada_parameters::Store<
+            CtxPreStorageRead<'ctx, 'ctx, S, VpCache<CA>, Eval<S>>,
+        >,
+        namada_governance::Store<
+            CtxPreStorageRead<'ctx, 'ctx, S, VpCache<CA>, Eval<S>>,
+        >,
+        namada_ibc::Store<
+            CtxPostStorageRead<'ctx, 'ctx, S, VpCache<CA>, Eval<S>>,
+        >,
+        namada_trans_token::Store<
+            CtxPreStorageRead<'ctx, 'ctx, S, VpCache<CA>, Eval<S>>,
+        >,
+        (),
+    >;
+
+    // Changing only the balance key of the MASP is invalid
+    #[test]
+    fn test_balance_change() {
+        let mut state = TestState::default();
+        namada_parameters::init_test_storage(&mut state).unwrap();
+        let src_key = balance_key(&nam(), &MASP);
+        let amount = Amount::native_whole(100);
+        let keys_changed = BTreeSet::from([src_key.clone()]);
+        let verifiers = Default::default();
+
+        // Initialize MASP balance
+        state
+            .db_write(&src_key, Amount::native_whole(100).serialize_to_vec())
+            .unwrap();
+
+        state.db_write(&src_key, amount.serialize_to_vec()).unwrap();
+
+        let tx_index = TxIndex::default();
+        let mut tx = Tx::from_type(namada_tx::data::TxType::Raw);
+        tx.push_default_inner_tx();
+        let BatchedTx { tx, cmt } = tx.batch_first_tx();
+
+        // Test both credit and debit
+        for new_amount in [150, 1] {
+            // Update the balance key
+            let new_amount = Amount::native_whole(new_amount);
+            let _ = state
+                .write_log_mut()
+                .write(&src_key, new_amount.serialize_to_vec())
+                .unwrap();
+
+            let gas_meter =
+                RefCell::new(VpGasMeter::new_from_tx_meter(&TxGasMeter::new(
+                    u64::MAX,
+                    namada_parameters::get_gas_scale(&state).unwrap(),
+                )));
+            let (vp_vp_cache, _vp_cache_dir) = vp_cache();
+            let ctx = Ctx::new(
+                &MASP,
+                &state,
+                &tx,
+                &cmt,
+                &tx_index,
+                &gas_meter,
+                &keys_changed,
+                &verifiers,
+                vp_vp_cache,
+            );
+
+            // We don't care about the specific error so long as it fails
+            assert!(
+                MaspVp::validate_tx(
+                    &ctx,
+                    &tx.batch_ref_tx(&cmt),
+                    &keys_changed,
+                    &verifiers
+                )
+                .is_err()
+            );
+        }
+    }
+
+    // A spend anchor only published in the post-state must be reported as such
+    #[test]
+    fn test_spend_anchor_only_in_post_state() {
+        let mut state = TestState::default();
+        namada_parameters::init_test_storage(&mut state).unwrap();
+        let anchor = bls12_381::Scalar::from(42u64);
+        let anchor_key = crate::storage_key::masp_commitment_anchor_key(anchor);
+        let keys_changed = BTreeSet::from([anchor_key.clone()]);
+        let verifiers = Default::default();
+
+        // Publish the anchor in the write log only
+        let _ = state
+            .write_log_mut()
+            .write(&anchor_key, ().serialize_to_vec())
+            .unwrap();
+
+        let tx_index = TxIndex::default();
+        let mut tx = Tx::from_type(namada_tx::data::TxType::Raw);
+        tx.push_default_inner_tx();
+        let BatchedTx { tx, cmt } = tx.batch_first_tx();
+        let gas_meter =
+            RefCell::new(VpGasMeter::new_from_tx_meter(&TxGasMeter::new(
+                u64::MAX,
+                namada_parameters::get_gas_scale(&state).unwrap(),
+            )));
+        let (vp_vp_cache, _vp_cache_dir) = vp_cache();
+        let ctx = Ctx::new(
+            &MASP,
+            &state,
+            &tx,
+            &cmt,
+            &tx_index,
+            &gas_meter,
+            &keys_changed,
+            &verifiers,
+            vp_vp_cache,
+        );
+
+        let err = super::valid_spend_anchor(&&ctx, anchor)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("only exists in the post-state"));
+
+        // An anchor that was never published gets the generic error
+        let err =
+            super::valid_spend_anchor(&&ctx, bls12_381::Scalar::from(7u64))
+                .unwrap_err()
+                .to_string();
+        assert!(err.contains("refers to an invalid anchor"));
+    }
+
+    // Spend anchors must be among the most recent ones if a retention window
+    // is configured
+    #[test]
+    fn test_spend_anchor_retention_window() {
+        use super::MaspVpError;
+
+        let mut storage = InMemoryMaspStorage::default();
+        let anchor = |i: u64| bls12_381::Scalar::from(i);
+        for i in 1..=3 {
+            storage.pre.insert(
+                crate::storage_key::masp_commitment_anchor_key(anchor(i)),
+                vec![],
+            );
+        }
+
+        // Without a window, all the published anchors are accepted
+        assert!(super::valid_spend_anchor(&storage, anchor(1)).is_ok());
+
+        // With a window of two anchors, the oldest one is rejected
+        storage.pre.insert(
+            crate::storage_key::masp_anchor_retention_window_key(),
+            2u64.serialize_to_vec(),
+        );
+        storage.pre.insert(
+            crate::storage_key::masp_recent_commitment_anchors_key(),
+            vec![Node::from_scalar(anchor(3)), Node::from_scalar(anchor(2))]
+                .serialize_to_vec(),
+        );
+        assert!(super::valid_spend_anchor(&storage, anchor(3)).is_ok());
+        assert!(super::valid_spend_anchor(&storage, anchor(2)).is_ok());
+        let err = super::valid_spend_anchor(&storage, anchor(1)).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<MaspVpError>(),
+            Some(MaspVpError::ExpiredSpendAnchor)
+        ));
+
+        // Anchors which were never published are still invalid
+        let err = super::valid_spend_anchor(&storage, anchor(4)).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<MaspVpError>(),
+            Some(MaspVpError::InvalidSpendAnchor)
+        ));
+
+        // Right after the window gets configured, the current root of the
+        // tree is still accepted
+        let mut tree = CommitmentTree::<Node>::empty();
+        tree.append(Node::from_scalar(anchor(5))).unwrap();
+        let root = bls12_381::Scalar::from(tree.root());
+        storage
+            .pre
+            .remove(&crate::storage_key::masp_recent_commitment_anchors_key());
+        storage.pre.insert(
+            crate::storage_key::masp_commitment_anchor_key(root),
+            vec![],
+        );
+        storage.pre.insert(
+            crate::storage_key::masp_commitment_tree_key(),
+            tree.serialize_to_vec(),
+        );
+        assert!(super::valid_spend_anchor(&storage, root).is_ok());
+        let err = super::valid_spend_anchor(&storage, anchor(3)).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<MaspVpError>(),
+            Some(MaspVpError::ExpiredSpendAnchor)
+        ));
+    }
+
+    // Governance proposals cannot set the anchor retention window to zero
+    #[test]
+    fn test_zero_anchor_retention_window() {
+        use super::MaspVpError;
+
+        let window_key = crate::storage_key::masp_anchor_retention_window_key();
+        let mut storage = InMemoryMaspStorage::default();
+        storage
+            .post
+            .insert(window_key.clone(), 2u64.serialize_to_vec());
+        assert!(
+            super::valid_parameter_changes(&storage, &[&window_key]).is_ok()
+        );
+
+        storage
+            .post
+            .insert(window_key.clone(), 0u64.serialize_to_vec());
+        let err = super::valid_parameter_changes(&storage, &[&window_key])
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<MaspVpError>(),
+            Some(MaspVpError::ZeroAnchorRetentionWindow)
+        ));
+
+        // Removing the window altogether is fine
+        storage.post.remove(&window_key);
+        assert!(
+            super::valid_parameter_changes(&storage, &[&window_key]).is_ok()
+        );
+    }
+
+    // Governance proposals cannot make the verification of descriptions
+    // free, nor unreasonably expensive
+    #[test]
+    fn test_gas_multiplier_bounds() {
+        use super::MaspVpError;
+
+        let multiplier_key =
+            crate::storage_key::masp_convert_gas_multiplier_key();
+        let mut storage = InMemoryMaspStorage::default();
+        for valid in [50u64, 100, 1_000] {
+            storage
+                .post
+                .insert(multiplier_key.clone(), valid.serialize_to_vec());
+            assert!(
+                super::valid_parameter_changes(&storage, &[&multiplier_key])
+                    .is_ok()
+            );
+        }
+        for invalid in [0u64, 49, 1_001, u64::MAX] {
+            storage
+                .post
+                .insert(multiplier_key.clone(), invalid.serialize_to_vec());
+            let err =
+                super::valid_parameter_changes(&storage, &[&multiplier_key])
+                    .unwrap_err();
+            assert!(matches!(
+                err.downcast_ref::<MaspVpError>(),
+                Some(MaspVpError::GasMultiplierOutOfRange(multiplier))
+                    if *multiplier == invalid
+            ));
+        }
+    }
+
+    // A note created by a tx can be spent by a later tx of the same block,
+    // before the anchor of the updated tree gets published
+    #[test]
+    fn test_spend_note_created_in_same_block() {
+        use super::MaspVpError;
+
+        let spend =
+            |anchor| shielded_tx(vec![dummy_spend(anchor, 1)], vec![], vec![]);
+
+        // The tree at the start of the block, whose anchor is published
+        let block_tree = CommitmentTree::<Node>::empty();
+        let mut storage = InMemoryMaspStorage::default();
+        storage.pre.insert(
+            crate::storage_key::masp_commitment_anchor_key(block_tree.root()),
+            vec![],
+        );
+
+        // The first tx of the block creates a note
+        let create = shielded_tx(vec![], vec![], vec![dummy_output(7)]);
+        let mut tree = block_tree.clone();
+        tree.append(Node::from_scalar(bls12_381::Scalar::from(7u64)))
+            .unwrap();
+        let tree_key = crate::storage_key::masp_commitment_tree_key();
+        storage
+            .pre
+            .insert(tree_key.clone(), block_tree.serialize_to_vec());
+        storage
+            .post
+            .insert(tree_key.clone(), tree.serialize_to_vec());
+        assert!(
+            super::valid_note_commitment_update(
+                &storage,
+                &BTreeSet::from([tree_key.clone()]),
+                &create
+            )
+            .is_ok()
+        );
+
+        // The second tx of the block spends it against the updated tree,
+        // whose anchor has not been published yet
+        storage
+            .pre
+            .insert(tree_key.clone(), tree.serialize_to_vec());
+        storage.post.insert(tree_key, tree.serialize_to_vec());
+        let anchor =
+            super::expected_post_tree_root(&block_tree, &create).unwrap();
+        assert!(
+            super::valid_spend_descriptions_anchor(
+                &storage,
+                &spend(anchor.into())
+            )
+            .is_ok()
+        );
+
+        // Roots of trees which were never reached are still invalid
+        let err = super::valid_spend_descriptions_anchor(
+            &storage,
+            &spend(bls12_381::Scalar::from(8u64)),
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<MaspVpError>(),
+            Some(MaspVpError::InvalidSpendAnchor)
+        ));
+    }
+
+    // The commitment tree is only read if the tx has output descriptions or
+    // writes to the tree
+    #[test]
+    fn test_note_commitment_update_without_outputs() {
+        use super::MaspVpError;
+
+        let transaction = masp_tx(0, None, None);
+        let tree_key = crate::storage_key::masp_commitment_tree_key();
+        let tree_changed = BTreeSet::from([tree_key.clone()]);
+
+        // Not even a missing tree is noticed if the tree is left untouched
+        let mut storage = InMemoryMaspStorage::default();
+        assert!(
+            super::valid_note_commitment_update(
+                &storage,
+                &BTreeSet::new(),
+                &transaction
+            )
+            .is_ok()
+        );
+        assert_eq!(storage.post_reads.get(), 0);
+
+        // Rewriting the same tree is fine
+        let pre_tree = CommitmentTree::<Node>::empty();
+        storage
+            .pre
+            .insert(tree_key.clone(), pre_tree.serialize_to_vec());
+        storage
+            .post
+            .insert(tree_key.clone(), pre_tree.serialize_to_vec());
+        assert!(
+            super::valid_note_commitment_update(
+                &storage,
+                &tree_changed,
+                &transaction
+            )
+            .is_ok()
+        );
+
+        // But appending to it is not
+        let mut post_tree = pre_tree;
+        post_tree
+            .append(Node::from_scalar(bls12_381::Scalar::from(1u64)))
+            .unwrap();
+        storage.post.insert(tree_key, post_tree.serialize_to_vec());
+        let err = super::valid_note_commitment_update(
+            &storage,
+            &tree_changed,
+            &transaction,
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<MaspVpError>(),
+            Some(MaspVpError::CommitmentTreeChangedWithoutOutputs)
+        ));
+    }
+
+    // A tx without output descriptions cannot modify the commitment tree
+    #[test]
+    fn test_commitment_tree_changed_without_outputs() {
+        use super::MaspVpError;
+
+        let mut storage = InMemoryMaspStorage::default();
+        let pre_tree = CommitmentTree::<Node>::empty();
+        let mut post_tree = pre_tree.clone();
+        post_tree
+            .append(Node::from_scalar(bls12_381::Scalar::from(1u64)))
+            .unwrap();
+        let tree_key = crate::storage_key::masp_commitment_tree_key();
+        storage
+            .pre
+            .insert(tree_key.clone(), pre_tree.serialize_to_vec());
+        storage
+            .post
+            .insert(tree_key.clone(), post_tree.serialize_to_vec());
+
+        let transaction = masp_tx(0, None, None);
+        let err = super::valid_note_commitment_update(
+            &storage,
+            &BTreeSet::from([tree_key]),
+            &transaction,
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<MaspVpError>(),
+            Some(MaspVpError::CommitmentTreeChangedWithoutOutputs)
+        ));
+        assert!(
+            err.to_string()
+                .contains("commitment tree changed without any output")
+        );
+    }
+
+    // The expected post-state tree root is the one of the tree in storage
+    // after the outputs of the tx have been appended to it
+    #[test]
+    fn test_expected_post_tree_root() {
+        let transaction =
+            shielded_tx(vec![], vec![], vec![dummy_output(2), dummy_output(3)]);
+
+        let mut pre_tree = CommitmentTree::<Node>::empty();
+        pre_tree
+            .append(Node::from_scalar(bls12_381::Scalar::from(1u64)))
+            .unwrap();
+        let expected_root =
+            super::expected_post_tree_root(&pre_tree, &transaction).unwrap();
+        // The pre-state tree is left untouched
+        assert_ne!(expected_root, pre_tree.root());
+
+        let mut post_tree = pre_tree.clone();
+        for cmu in [2u64, 3] {
+            post_tree
+                .append(Node::from_scalar(bls12_381::Scalar::from(cmu)))
+                .unwrap();
+        }
+        assert_eq!(expected_root, post_tree.root());
+
+        // The VP accepts the post-state tree with the predicted root
+        let mut storage = InMemoryMaspStorage::default();
+        let tree_key = crate::storage_key::masp_commitment_tree_key();
+        storage
+            .pre
+            .insert(tree_key.clone(), pre_tree.serialize_to_vec());
+        storage
+            .post
+            .insert(tree_key.clone(), post_tree.serialize_to_vec());
+        assert!(
+            super::valid_note_commitment_update(
+                &storage,
+                &BTreeSet::from([tree_key]),
+                &transaction
+            )
+            .is_ok()
+        );
+    }
+
+    // The structural checks on shielded txs can run without a ledger context
+    #[test]
+    fn test_structural_checks_on_in_memory_storage() {
+        let mut storage = InMemoryMaspStorage::default();
+        let published = bls12_381::Scalar::from(1u64);
+        let fresh = bls12_381::Scalar::from(2u64);
+        let published_key =
+            crate::storage_key::masp_commitment_anchor_key(published);
+        storage.pre.insert(published_key.clone(), vec![]);
+        storage.post.insert(published_key, vec![]);
+        storage.post.insert(
+            crate::storage_key::masp_commitment_anchor_key(fresh),
+            vec![],
+        );
+        let tree = CommitmentTree::<Node>::empty().serialize_to_vec();
+        let tree_key = crate::storage_key::masp_commitment_tree_key();
+        storage.pre.insert(tree_key.clone(), tree.clone());
+        storage.post.insert(tree_key, tree);
+
+        assert!(super::valid_spend_anchor(&storage, published).is_ok());
+        let err = super::valid_spend_anchor(&storage, fresh)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("only exists in the post-state"));
+
+        // A tx without any description leaves the tree untouched
+        let transaction = masp_tx(0, None, None);
+        assert!(
+            super::valid_note_commitment_update(
+                &storage,
+                &BTreeSet::new(),
+                &transaction
+            )
+            .is_ok()
+        );
+        assert!(
+            super::valid_spend_descriptions_anchor(&storage, &transaction)
+                .is_ok()
+        );
+        assert!(
+            super::valid_convert_descriptions_anchor(&storage, &transaction)
+                .is_ok()
+        );
+        // But it cannot reveal any nullifier
+        let nullifier_key =
+            crate::storage_key::masp_nullifier_key(&Nullifier([0; 32]));
+        assert!(
+            super::valid_nullifiers_reveal(
+                &storage,
+                &BTreeSet::from([nullifier_key]),
+                &transaction,
+            )
+            .is_err()
+        );
+    }
+
+    // Shielded txs can be pre-validated against a snapshot of the state
+    #[test]
+    fn test_validate_masp_tx_offline() {
+        use masp_primitives::sapling::redjubjub::Signature;
+        use namada_core::chain::BlockHeight as ChainHeight;
+        use namada_state::ConversionState;
+
+        use super::{MaspVpError, NO_EXPIRY_HEIGHT, OfflineMaspContext};
+
+        let anchor = bls12_381::Scalar::from(1u64);
+        let mut storage = InMemoryMaspStorage::default();
+        let anchor_key = crate::storage_key::masp_commitment_anchor_key(anchor);
+        storage.pre.insert(anchor_key.clone(), vec![]);
+        storage.post.insert(anchor_key, vec![]);
+
+        let conversion_state = ConversionState::default();
+        let tokens = BTreeMap::new();
+        let context = OfflineMaspContext {
+            block_height: ChainHeight(10),
+            masp_epoch: MaspEpoch::zero(),
+            conversion_state: &conversion_state,
+            tokens: &tokens,
+        };
+
+        // The encoding of the identity point followed by a zero scalar, i.e. a
+        // valid binding signature for the identity verification key of a
+        // bundle without descriptions
+        let mut binding_sig = [0; 64];
+        binding_sig[0] = 1;
+        let transaction = |expiry_height, spends: Vec<u8>| {
+            let mut bundle = sapling_bundle(
+                spends
+                    .into_iter()
+                    .map(|nullifier| dummy_spend(anchor, nullifier))
+                    .collect(),
+                vec![],
+                vec![],
+            );
+            bundle.authorization.binding_sig =
+                Signature::read(&binding_sig[..]).unwrap();
+            masp_tx(expiry_height, None, Some(bundle))
+        };
+
+        // A tx without any description and a valid binding signature passes
+        // every check, proofs included
+        assert!(
+            super::validate_masp_tx_offline(
+                &storage,
+                &context,
+                &transaction(NO_EXPIRY_HEIGHT, vec![])
+            )
+            .is_ok()
+        );
+
+        // Spending an unspent note against a published anchor is fine
+        assert!(
+            super::validate_masp_tx_state_offline(
+                &storage,
+                &context,
+                &transaction(NO_EXPIRY_HEIGHT, vec![1])
+            )
+            .is_ok()
+        );
+
+        // Revealing the same nullifier twice in a tx is not
+        let err = super::validate_masp_tx_state_offline(
+            &storage,
+            &context,
+            &transaction(NO_EXPIRY_HEIGHT, vec![1, 1]),
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<MaspVpError>(),
+            Some(MaspVpError::DoubleSpend(Nullifier([1, ..])))
+        ));
+
+        // Neither is an expired tx
+        let err = super::validate_masp_tx_state_offline(
+            &storage,
+            &context,
+            &transaction(9, vec![1]),
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<MaspVpError>(),
+            Some(MaspVpError::ExpiredTx { .. })
+        ));
+
+        // Spending a note whose nullifier was already revealed is not, and
+        // is rejected before any proof gets verified
+        let spent_key =
+            crate::storage_key::masp_nullifier_key(&Nullifier([2; 32]));
+        storage.pre.insert(spent_key.clone(), vec![]);
+        storage.post.insert(spent_key, vec![]);
+        let err = super::validate_masp_tx_offline(
+            &storage,
+            &context,
+            &transaction(NO_EXPIRY_HEIGHT, vec![2]),
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<MaspVpError>(),
+            Some(MaspVpError::DoubleSpend(Nullifier([2, ..])))
+        ));
+
+        // Nor can any tx be validated while the MASP is frozen
+        storage.pre.insert(
+            masp_frozen_until_key(),
+            ChainHeight(11).serialize_to_vec(),
+        );
+        let err = super::validate_masp_tx_state_offline(
+            &storage,
+            &context,
+            &transaction(NO_EXPIRY_HEIGHT, vec![1]),
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("MASP is frozen for migration"));
+    }
+
+    // The revealed nullifiers are those of the spend descriptions
+    #[test]
+    fn test_revealed_nullifiers() {
+        let anchor = bls12_381::Scalar::from(1u64);
+        let transaction = shielded_tx(
+            vec![dummy_spend(anchor, 1), dummy_spend(anchor, 2)],
+            vec![],
+            vec![],
+        );
+
+        let nullifier_keys: BTreeSet<_> = [1, 2]
+            .map(|nullifier| {
+                crate::storage_key::masp_nullifier_key(&Nullifier(
+                    [nullifier; 32],
+                ))
+            })
+            .into();
+        let mut storage = InMemoryMaspStorage::default();
+        for key in &nullifier_keys {
+            storage.post.insert(key.clone(), vec![]);
+        }
+
+        let revealed = super::valid_nullifiers_reveal(
+            &storage,
+            &nullifier_keys,
+            &transaction,
+        )
+        .unwrap();
+        assert_eq!(
+            revealed.into_iter().collect::<BTreeSet<_>>(),
+            nullifier_keys
+        );
+        assert_eq!(
+            super::reveal_nullifiers(&storage, &transaction).unwrap(),
+            vec![Nullifier([1; 32]), Nullifier([2; 32])]
+        );
+    }
+
+    // The nullifiers of a tx with many spends are all validated, with a
+    // single post-state read each
+    #[test]
+    fn test_many_revealed_nullifiers() {
+        use super::MaspVpError;
+
+        const SPENDS: u8 = 50;
+
+        let anchor = bls12_381::Scalar::from(1u64);
+        let transaction = shielded_tx(
+            (0..SPENDS)
+                .map(|nullifier| dummy_spend(anchor, nullifier))
+                .collect(),
+            vec![],
+            vec![],
+        );
+        let nullifier_keys: BTreeSet<_> = (0..SPENDS)
+            .map(|nullifier| {
+                crate::storage_key::masp_nullifier_key(&Nullifier(
+                    [nullifier; 32],
+                ))
+            })
+            .collect();
+        let mut storage = InMemoryMaspStorage::default();
+        for key in &nullifier_keys {
+            storage.post.insert(key.clone(), vec![]);
+        }
+
+        let revealed = super::valid_nullifiers_reveal(
+            &storage,
+            &nullifier_keys,
+            &transaction,
+        )
+        .unwrap();
+        assert_eq!(
+            revealed.into_iter().collect::<BTreeSet<_>>(),
+            nullifier_keys
+        );
+        assert_eq!(storage.post_reads.get(), usize::from(SPENDS));
+
+        // A single uncommitted nullifier among them is still caught
+        let last = Nullifier([SPENDS - 1; 32]);
+        storage
+            .post
+            .remove(&crate::storage_key::masp_nullifier_key(&last));
+        let err = super::valid_nullifiers_reveal(
+            &storage,
+            &nullifier_keys,
+            &transaction,
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<MaspVpError>(),
+            Some(MaspVpError::UncommittedNullifier(spent)) if *spent == last
+        ));
+    }
+
+    // Nullifiers missing from the post-state are told apart from those
+    // committed with some data
+    #[test]
+    fn test_uncommitted_nullifier() {
+        use super::MaspVpError;
+
+        let nullifier = Nullifier([1; 32]);
+        let transaction = shielded_tx(
+            vec![dummy_spend(bls12_381::Scalar::from(1u64), 1)],
+            vec![],
+            vec![],
+        );
+
+        // The nullifier key was written, then deleted by the tx, so it is
+        // absent from the post-state
+        let mut storage = InMemoryMaspStorage::default();
+        let err = super::reveal_nullifiers(&storage, &transaction).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<MaspVpError>(),
+            Some(MaspVpError::UncommittedNullifier(spent)) if *spent == nullifier
+        ));
+
+        let nullifier_key = crate::storage_key::masp_nullifier_key(&nullifier);
+        storage.post.insert(nullifier_key, vec![1]);
+        let err = super::reveal_nullifiers(&storage, &transaction).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<MaspVpError>(),
+            Some(MaspVpError::NullifierWithData(spent)) if *spent == nullifier
+        ));
+    }
+
+    // Keys under an extra prefix configured in storage can be changed by a
+    // transfer
+    #[test]
+    fn test_extra_transfer_key_accepted() {
+        let mut state = TestState::default();
+        namada_parameters::init_test_storage(&mut state).unwrap();
+        let extra_prefix = Key::from(MASP.to_db_key())
+            .push(&"new_feature".to_owned())
+            .unwrap();
+        let extra_key = extra_prefix.push(&"entry".to_owned()).unwrap();
+        let keys_changed = BTreeSet::from([extra_key.clone()]);
+        let verifiers = Default::default();
+
+        let tx_index = TxIndex::default();
+        let mut tx = Tx::from_type(namada_tx::data::TxType::Raw);
+        tx.push_default_inner_tx();
+        let BatchedTx { tx, cmt } = tx.batch_first_tx();
+
+        for (configured, expected) in [(false, false), (true, true)] {
+            if configured {
+                state
+                    .db_write(
+                        &masp_extra_transfer_keys_key(),
+                        BTreeSet::from([extra_prefix.clone()])
+                            .serialize_to_vec(),
+                    )
+                    .unwrap();
+            }
+
+            let gas_meter =
+                RefCell::new(VpGasMeter::new_from_tx_meter(&TxGasMeter::new(
+                    u64::MAX,
+                    namada_parameters::get_gas_scale(&state).unwrap(),
+                )));
+            let (vp_vp_cache, _vp_cache_dir) = vp_cache();
+            let ctx = Ctx::new(
+                &MASP,
+                &state,
+                &tx,
+                &cmt,
+                &tx_index,
+                &gas_meter,
+                &keys_changed,
+                &verifiers,
+                vp_vp_cache,
+            );
+
+            assert_eq!(
+                MaspVp::only_transfer_keys_changed(&ctx, &[&extra_key])
+                    .unwrap(),
+                expected
+            );
+        }
+    }
+
+    // Governance keys cannot be changed by a transfer, even if they fall
+    // under an extra prefix configured in storage
+    #[test]
+    fn test_extra_transfer_prefix_excludes_governance_keys() {
+        use crate::storage_key::{
+            masp_anchor_retention_window_key, masp_max_spends_per_tx_key,
+            masp_spend_gas_multiplier_key,
+        };
+
+        let mut state = TestState::default();
+        namada_parameters::init_test_storage(&mut state).unwrap();
+        // A prefix covering the whole MASP subspace
+        let extra_prefix = Key::from(MASP.to_db_key());
+        state
+            .db_write(
+                &masp_extra_transfer_keys_key(),
+                BTreeSet::from([extra_prefix.clone()]).serialize_to_vec(),
+            )
+            .unwrap();
+        let extra_key = extra_prefix.push(&"new_feature".to_owned()).unwrap();
+        let governance_keys = [
+            masp_frozen_until_key(),
+            masp_max_spends_per_tx_key(),
+            masp_spend_gas_multiplier_key(),
+            masp_anchor_retention_window_key(),
+            masp_extra_transfer_keys_key(),
+        ];
+        let keys_changed: BTreeSet<Key> = governance_keys
+            .iter()
+            .cloned()
+            .chain([extra_key.clone()])
+            .collect();
+        let verifiers = Default::default();
+
+        let tx_index = TxIndex::default();
+        let mut tx = Tx::from_type(namada_tx::data::TxType::Raw);
+        tx.push_default_inner_tx();
+        let BatchedTx { tx, cmt } = tx.batch_first_tx();
+        let gas_meter =
+            RefCell::new(VpGasMeter::new_from_tx_meter(&TxGasMeter::new(
+                u64::MAX,
+                namada_parameters::get_gas_scale(&state).unwrap(),
+            )));
+        let (vp_vp_cache, _vp_cache_dir) = vp_cache();
+        let ctx = Ctx::new(
+            &MASP,
+            &state,
+            &tx,
+            &cmt,
+            &tx_index,
+            &gas_meter,
+            &keys_changed,
+            &verifiers,
+            vp_vp_cache,
+        );
+
+        assert!(
+            MaspVp::only_transfer_keys_changed(&ctx, &[&extra_key]).unwrap()
+        );
+        for key in &governance_keys {
+            assert!(
+                !MaspVp::only_transfer_keys_changed(&ctx, &[key]).unwrap(),
+                "{key} must not be changed by a transfer"
+            );
+        }
+    }
+
+    // Shielded transactions must be rejected only while the MASP is frozen
+    #[test]
+    fn test_frozen_masp_window() {
+        let mut state = TestState::default();
+        namada_parameters::init_test_storage(&mut state).unwrap();
+        state
+            .db_write(
+                &masp_frozen_until_key(),
+                namada_core::chain::BlockHeight(10).serialize_to_vec(),
+            )
+            .unwrap();
+        let keys_changed = BTreeSet::new();
+        let verifiers = Default::default();
+
+        let tx_index = TxIndex::default();
+        let mut tx = Tx::from_type(namada_tx::data::TxType::Raw);
+        tx.push_default_inner_tx();
+        let BatchedTx { tx, cmt } = tx.batch_first_tx();
+
+        for (height, frozen) in [(5, true), (10, false), (15, false)] {
+            state.in_mem_mut().block.height =
+                namada_core::chain::BlockHeight(height);
 
             let gas_meter =
                 RefCell::new(VpGasMeter::new_from_tx_meter(&TxGasMeter::new(
@@ -1046,17 +3134,1032 @@ mod shielded_token_tests {
                 vp_vp_cache,
             );
 
-            // We don't care about the specific error so long as it fails
+            let result = MaspVp::ensure_not_frozen(&ctx);
+            if frozen {
+                assert!(
+                    result.unwrap_err().to_string().contains(
+                        "MASP is frozen for migration until height 10"
+                    )
+                );
+            } else {
+                assert!(result.is_ok());
+            }
+        }
+    }
+
+    // Rejections must be distinguishable by their structured error
+    #[test]
+    fn test_structured_vp_errors() {
+        use super::MaspVpError;
+
+        let storage = InMemoryMaspStorage::default();
+        let err =
+            super::valid_spend_anchor(&storage, bls12_381::Scalar::from(1u64))
+                .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<MaspVpError>(),
+            Some(MaspVpError::InvalidSpendAnchor)
+        ));
+        assert!(
+            err.to_string()
+                .contains("Spend description refers to an invalid anchor")
+        );
+
+        let unbalanced =
+            encode_asset_type(nam(), 6.into(), MaspDigitPos::One, None)
+                .unwrap();
+        let err = super::ensure_balanced_transparent_pool(&I128Sum::from_pair(
+            unbalanced, -10,
+        ))
+        .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<MaspVpError>(),
+            Some(MaspVpError::TransparentPoolImbalance { deficits, surpluses })
+                if deficits.len() == 1 && surpluses.is_empty()
+        ));
+    }
+
+    // Converts built against the previous conversion tree remain valid
+    // across a single epoch boundary
+    #[test]
+    fn test_convert_anchor_across_epoch_boundary() {
+        use namada_core::hash::Hash;
+
+        use super::MaspVpError;
+
+        let two_epochs_old = bls12_381::Scalar::from(1u64);
+        let previous = bls12_381::Scalar::from(2u64);
+        let current = bls12_381::Scalar::from(3u64);
+        let mut storage = InMemoryMaspStorage::default();
+        storage.pre.insert(
+            masp_convert_anchor_key(),
+            Hash(current.to_bytes()).serialize_to_vec(),
+        );
+        storage.pre.insert(
+            crate::storage_key::masp_recent_convert_anchors_key(),
+            vec![Hash(current.to_bytes()), Hash(previous.to_bytes())]
+                .serialize_to_vec(),
+        );
+
+        let transaction =
+            |anchor| shielded_tx(vec![], vec![dummy_convert(anchor)], vec![]);
+
+        for anchor in [current, previous] {
             assert!(
-                MaspVp::validate_tx(
-                    &ctx,
-                    &tx.batch_ref_tx(&cmt),
-                    &keys_changed,
-                    &verifiers
+                super::valid_convert_descriptions_anchor(
+                    &storage,
+                    &transaction(anchor)
                 )
-                .is_err()
+                .is_ok()
+            );
+        }
+        let err = super::valid_convert_descriptions_anchor(
+            &storage,
+            &transaction(two_epochs_old),
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<MaspVpError>(),
+            Some(MaspVpError::InvalidConvertAnchor)
+        ));
+    }
+
+    // Converts cannot be validated against a conversion tree that was never
+    // written to storage
+    #[test]
+    fn test_convert_anchor_missing_from_storage() {
+        use super::MaspVpError;
+
+        let storage = InMemoryMaspStorage::default();
+        let transaction = shielded_tx(
+            vec![],
+            vec![dummy_convert(bls12_381::Scalar::from(1u64))],
+            vec![],
+        );
+
+        let err =
+            super::valid_convert_descriptions_anchor(&storage, &transaction)
+                .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<MaspVpError>(),
+            Some(MaspVpError::UninitializedConvertTree)
+        ));
+    }
+
+    // All the spends of a bundle must share the anchor it declares
+    #[test]
+    fn test_inconsistent_spend_anchors() {
+        use super::MaspVpError;
+
+        let mut storage = InMemoryMaspStorage::default();
+        let bundle_anchor = bls12_381::Scalar::from(1u64);
+        let other_anchor = bls12_381::Scalar::from(2u64);
+        // Both anchors are published, such that only their mismatch fails
+        for anchor in [bundle_anchor, other_anchor] {
+            storage.pre.insert(
+                crate::storage_key::masp_commitment_anchor_key(anchor),
+                vec![],
+            );
+        }
+
+        let transaction = |spends| shielded_tx(spends, vec![], vec![]);
+
+        let consistent = transaction(vec![
+            dummy_spend(bundle_anchor, 1),
+            dummy_spend(bundle_anchor, 2),
+        ]);
+        assert!(
+            super::valid_spend_descriptions_anchor(&storage, &consistent)
+                .is_ok()
+        );
+
+        let inconsistent = transaction(vec![
+            dummy_spend(bundle_anchor, 1),
+            dummy_spend(other_anchor, 2),
+        ]);
+        let err =
+            super::valid_spend_descriptions_anchor(&storage, &inconsistent)
+                .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<MaspVpError>(),
+            Some(MaspVpError::InconsistentSpendAnchors)
+        ));
+    }
+
+    // Txs must target the consensus branch configured in storage, if any
+    #[test]
+    fn test_consensus_branch_check() {
+        use super::MaspVpError;
+
+        let transaction = masp_tx(0, None, None);
+
+        // Without any configured branch the MASP branch is expected
+        let mut storage = InMemoryMaspStorage::default();
+        assert!(super::valid_consensus_branch(&storage, &transaction).is_ok());
+
+        // An explicitly configured branch takes precedence
+        storage.pre.insert(
+            crate::storage_key::masp_consensus_branch_id_key(),
+            u32::from(BranchId::MASP).serialize_to_vec(),
+        );
+        assert!(super::valid_consensus_branch(&storage, &transaction).is_ok());
+
+        storage.pre.insert(
+            crate::storage_key::masp_consensus_branch_id_key(),
+            0xdead_beef_u32.serialize_to_vec(),
+        );
+        let err =
+            super::valid_consensus_branch(&storage, &transaction).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<MaspVpError>(),
+            Some(MaspVpError::WrongConsensusBranch {
+                expected: 0xdead_beef,
+                ..
+            })
+        ));
+        assert!(
+            err.to_string().contains(
+                "MASP transaction targets the wrong consensus branch"
+            )
+        );
+    }
+
+    // Transparent entries must carry a nonzero value, but the transparent
+    // bundle itself may be empty
+    #[test]
+    fn test_zero_value_transparent_entries() {
+        use masp_primitives::asset_type::AssetType;
+        use masp_primitives::transaction::TransparentAddress;
+        use masp_primitives::transaction::components::transparent::{
+            Authorized as TransparentAuthorized, Bundle as TransparentBundle,
+        };
+        use masp_primitives::transaction::components::{TxIn, TxOut};
+        use namada_state::ConversionState;
+
+        use super::{ChangedBalances, MaspVpError};
+
+        let asset_type = AssetType::new(b"test").unwrap();
+        let address = TransparentAddress([0; 20]);
+        let vin = |value| TxIn::<TransparentAuthorized> {
+            asset_type,
+            value,
+            address,
+            transparent_sig: (),
+        };
+        let vout = |value| TxOut {
+            asset_type,
+            value,
+            address,
+        };
+        let validate = |vin, vout| {
+            let transaction = masp_tx(
+                0,
+                Some(TransparentBundle {
+                    vin,
+                    vout,
+                    authorization: TransparentAuthorized,
+                }),
+                None,
+            );
+            super::validate_transparent_bundle(
+                &transaction,
+                &mut ChangedBalances::default(),
+                MaspEpoch::zero(),
+                &ConversionState::default(),
+                &mut BTreeSet::new(),
+            )
+        };
+
+        // An entirely empty bundle is fine
+        assert!(validate(vec![], vec![]).is_ok());
+
+        // A zero-value input is rejected
+        let err = validate(vec![vin(0)], vec![vout(1)]).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<MaspVpError>(),
+            Some(MaspVpError::ZeroValueTransparentEntry)
+        ));
+
+        // A zero-value output is rejected
+        let err = validate(vec![vin(1)], vec![vout(0)]).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<MaspVpError>(),
+            Some(MaspVpError::ZeroValueTransparentEntry)
+        ));
+    }
+
+    // The number of distinct tokens moved by a tx is bounded by the limit
+    // configured in storage
+    #[test]
+    fn test_max_tokens_per_tx() {
+        use masp_primitives::transaction::TransparentAddress;
+        use namada_core::address::testing::eth;
+
+        use super::{ChangedBalances, MaspVpError};
+
+        let mut changed_balances = ChangedBalances::default();
+        for (index, token) in [nam(), btc(), eth()].into_iter().enumerate() {
+            let address =
+                TransparentAddress([u8::try_from(index).unwrap(); 20]);
+            changed_balances.post.insert(
+                address,
+                ValueSum::from_pair(token, Amount::from_u64(1)),
             );
         }
+
+        // No limit is configured by default
+        let mut storage = InMemoryMaspStorage::default();
+        assert!(super::valid_token_count(&storage, &changed_balances).is_ok());
+
+        // Moving as many tokens as the limit is fine
+        let limit_key = crate::storage_key::masp_max_tokens_per_tx_key();
+        storage
+            .pre
+            .insert(limit_key.clone(), 3u64.serialize_to_vec());
+        assert!(super::valid_token_count(&storage, &changed_balances).is_ok());
+
+        // But not one token over it
+        storage.pre.insert(limit_key, 2u64.serialize_to_vec());
+        let err =
+            super::valid_token_count(&storage, &changed_balances).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<MaspVpError>(),
+            Some(MaspVpError::TooManyTokens { count: 3, limit: 2 })
+        ));
+    }
+
+    // Unrecognized transparent entries are reported by index
+    #[test]
+    fn test_unrecognized_transparent_assets_listed() {
+        use masp_primitives::asset_type::AssetType;
+        use masp_primitives::transaction::TransparentAddress;
+        use masp_primitives::transaction::components::transparent::{
+            Authorized as TransparentAuthorized, Bundle as TransparentBundle,
+        };
+        use masp_primitives::transaction::components::{TxIn, TxOut};
+        use namada_state::ConversionState;
+
+        use super::{ChangedBalances, MaspVpError};
+
+        let address = TransparentAddress([0; 20]);
+        let known =
+            encode_asset_type(nam(), Denomination(6), MaspDigitPos::Zero, None)
+                .unwrap();
+        let unknown = AssetType::new(b"test").unwrap();
+
+        let mut changed_balances = ChangedBalances::default();
+        changed_balances
+            .undated_tokens
+            .insert(known, (nam(), Denomination(6), MaspDigitPos::Zero));
+        changed_balances.pre.insert(
+            address,
+            ValueSum::from_pair(
+                nam(),
+                Amount::from_masp_denominated(1, MaspDigitPos::Zero),
+            ),
+        );
+
+        let vin = |asset_type| TxIn::<TransparentAuthorized> {
+            asset_type,
+            value: 1,
+            address,
+            transparent_sig: (),
+        };
+        let transaction = masp_tx(
+            0,
+            Some(TransparentBundle {
+                vin: vec![vin(known), vin(unknown)],
+                vout: vec![TxOut {
+                    asset_type: unknown,
+                    value: 1,
+                    address,
+                }],
+                authorization: TransparentAuthorized,
+            }),
+            None,
+        );
+
+        let err = super::validate_transparent_bundle(
+            &transaction,
+            &mut changed_balances,
+            MaspEpoch::zero(),
+            &ConversionState::default(),
+            &mut BTreeSet::new(),
+        )
+        .unwrap_err();
+        match err.downcast_ref::<MaspVpError>() {
+            Some(MaspVpError::UnrecognizedTransparentAssets { entries }) => {
+                assert_eq!(
+                    entries,
+                    &vec![
+                        format!("vin 1 ({unknown})"),
+                        format!("vout 0 ({unknown})"),
+                    ]
+                );
+            }
+            _ => panic!("unexpected error: {err}"),
+        }
+        assert!(err.to_string().contains(&format!("vin 1 ({unknown})")));
+    }
+
+    // Values placed beyond the range of token amounts by their digit position
+    // are rejected
+    #[test]
+    fn test_transparent_value_out_of_range() {
+        use masp_primitives::transaction::TransparentAddress;
+        use masp_primitives::transaction::components::TxOut;
+        use masp_primitives::transaction::components::transparent::{
+            Authorized as TransparentAuthorized, Bundle as TransparentBundle,
+        };
+        use namada_state::ConversionState;
+
+        use super::{ChangedBalances, MaspVpError};
+
+        // The largest value representable at the most significant digit
+        let max_value = u64::try_from(i64::MAX).unwrap();
+        assert!(
+            super::transparent_amount(max_value, MaspDigitPos::Three).is_ok()
+        );
+
+        let asset_type = encode_asset_type(
+            nam(),
+            Denomination(6),
+            MaspDigitPos::Three,
+            None,
+        )
+        .unwrap();
+        let mut changed_balances = ChangedBalances::default();
+        changed_balances
+            .undated_tokens
+            .insert(asset_type, (nam(), Denomination(6), MaspDigitPos::Three));
+        let transaction = masp_tx(
+            0,
+            Some(TransparentBundle {
+                vin: vec![],
+                vout: vec![TxOut {
+                    asset_type,
+                    value: u64::MAX,
+                    address: TransparentAddress([0; 20]),
+                }],
+                authorization: TransparentAuthorized,
+            }),
+            None,
+        );
+
+        let err = super::validate_transparent_bundle(
+            &transaction,
+            &mut changed_balances,
+            MaspEpoch::zero(),
+            &ConversionState::default(),
+            &mut BTreeSet::new(),
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<MaspVpError>(),
+            Some(MaspVpError::TransparentValueOutOfRange {
+                value: u64::MAX,
+                position: MaspDigitPos::Three,
+            })
+        ));
+    }
+
+    // Finite expiries are enforced, unlike the no-expiry sentinel
+    #[test]
+    fn test_valid_expiry() {
+        use namada_core::chain::BlockHeight;
+
+        use super::{MaspVpError, NO_EXPIRY_HEIGHT};
+
+        // A finite expiry in the past is rejected
+        let err = super::valid_expiry(BlockHeight(100), 99).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<MaspVpError>(),
+            Some(MaspVpError::ExpiredTx { .. })
+        ));
+
+        // A finite expiry in the future, or at the current height, is fine
+        assert!(super::valid_expiry(BlockHeight(100), 101).is_ok());
+        assert!(super::valid_expiry(BlockHeight(100), 100).is_ok());
+
+        // Transactions without expiry are accepted at any height
+        assert!(
+            super::valid_expiry(BlockHeight(u64::MAX), NO_EXPIRY_HEIGHT)
+                .is_ok()
+        );
+        assert!(
+            super::valid_expiry(BlockHeight(u64::MAX), NO_EXPIRY_HEIGHT - 1)
+                .is_err()
+        );
+    }
+
+    // The tokens of all the balances changed by a transfer are reported
+    #[test]
+    fn test_tokens_in_transfer() {
+        let keys_changed = BTreeSet::from([
+            balance_key(&nam(), &MASP),
+            balance_key(
+                &btc(),
+                &namada_core::address::testing::established_address_1(),
+            ),
+            crate::storage_key::masp_nullifier_key(&Nullifier([0; 32])),
+        ]);
+        let tokens =
+            MaspVp::<'_, TestState>::tokens_in_transfer(&keys_changed).unwrap();
+        assert_eq!(tokens, BTreeSet::from([nam(), btc()]));
+    }
+
+    // Transparent asset types without a Sapling contribution are rejected
+    #[test]
+    fn test_orphan_transparent_asset() {
+        use masp_primitives::transaction::TransparentAddress;
+        use masp_primitives::transaction::components::transparent::{
+            Authorized as TransparentAuthorized, Bundle as TransparentBundle,
+        };
+        use masp_primitives::transaction::components::{TxIn, TxOut};
+        use namada_state::ConversionState;
+
+        use super::{ChangedBalances, MaspVpError};
+
+        let address = TransparentAddress([0; 20]);
+        let asset_type =
+            encode_asset_type(nam(), Denomination(6), MaspDigitPos::Zero, None)
+                .unwrap();
+        let balance = ValueSum::from_pair(
+            nam(),
+            Amount::from_masp_denominated(1, MaspDigitPos::Zero),
+        );
+
+        let mut changed_balances = ChangedBalances::default();
+        changed_balances
+            .undated_tokens
+            .insert(asset_type, (nam(), Denomination(6), MaspDigitPos::Zero));
+        changed_balances.pre.insert(address, balance.clone());
+        changed_balances.post.insert(address, balance);
+
+        // The asset merely passes through the transparent bundle
+        let transaction = masp_tx(
+            0,
+            Some(TransparentBundle {
+                vin: vec![TxIn::<TransparentAuthorized> {
+                    asset_type,
+                    value: 1,
+                    address,
+                    transparent_sig: (),
+                }],
+                vout: vec![TxOut {
+                    asset_type,
+                    value: 1,
+                    address,
+                }],
+                authorization: TransparentAuthorized,
+            }),
+            None,
+        );
+
+        let err = super::validate_transparent_bundle(
+            &transaction,
+            &mut changed_balances,
+            MaspEpoch::zero(),
+            &ConversionState::default(),
+            &mut BTreeSet::new(),
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<MaspVpError>(),
+            Some(MaspVpError::OrphanTransparentAsset(orphan))
+                if *orphan == asset_type
+        ));
+        assert!(err.to_string().contains(&asset_type.to_string()));
+    }
+
+    // The residual of the transparent pool is reported without requiring
+    // it to be balanced
+    #[test]
+    fn test_transparent_pool_residual() {
+        use masp_primitives::asset_type::AssetType;
+        use masp_primitives::transaction::TransparentAddress;
+        use masp_primitives::transaction::components::transparent::{
+            Authorized as TransparentAuthorized, Bundle as TransparentBundle,
+        };
+        use masp_primitives::transaction::components::{TxIn, TxOut};
+
+        let asset_type = AssetType::new(b"test").unwrap();
+        let address = TransparentAddress([0; 20]);
+        let transaction = masp_tx(
+            0,
+            Some(TransparentBundle {
+                vin: vec![TxIn::<TransparentAuthorized> {
+                    asset_type,
+                    value: 5,
+                    address,
+                    transparent_sig: (),
+                }],
+                vout: vec![TxOut {
+                    asset_type,
+                    value: 3,
+                    address,
+                }],
+                authorization: TransparentAuthorized,
+            }),
+            None,
+        );
+
+        let residual =
+            super::compute_transparent_pool_residual(&transaction).unwrap();
+        assert_eq!(residual, I128Sum::from_pair(asset_type, 2));
+        assert!(super::ensure_balanced_transparent_pool(&residual).is_err());
+    }
+
+    // Every token whose balance does not match the Sapling value balance is
+    // reported in a single error
+    #[test]
+    fn test_balance_mismatch_reports_all_tokens() {
+        use namada_state::ConversionState;
+
+        use super::MaspVpError;
+
+        let pre = ValueSum::from_pair(nam(), Amount::from_u64(10))
+            .checked_add(&ValueSum::from_pair(btc(), Amount::from_u64(5)))
+            .unwrap();
+        let post = ValueSum::zero();
+        let err = super::verify_sapling_balancing_value(
+            &pre,
+            &post,
+            &ValueSum::zero(),
+            &ValueSum::zero(),
+            &I128Sum::zero(),
+            MaspEpoch::zero(),
+            &BTreeMap::new(),
+            &ConversionState::default(),
+        )
+        .unwrap_err();
+        match err.downcast_ref::<MaspVpError>() {
+            Some(MaspVpError::BalanceMismatch {
+                undated,
+                mismatches,
+            }) => {
+                assert!(!undated);
+                assert_eq!(mismatches.len(), 2);
+            }
+            _ => panic!("Unexpected error: {err}"),
+        }
+        let message = err.to_string();
+        assert!(message.contains(&format!("{} (expected", nam())));
+        assert!(message.contains(&format!("{} (expected", btc())));
+
+        // Matching balances pass
+        assert!(
+            super::verify_sapling_balancing_value(
+                &pre,
+                &pre,
+                &ValueSum::zero(),
+                &ValueSum::zero(),
+                &I128Sum::zero(),
+                MaspEpoch::zero(),
+                &BTreeMap::new(),
+                &ConversionState::default(),
+            )
+            .is_ok()
+        );
+    }
+
+    // The gas charged for the state checks grows proportionally to the
+    // number of descriptions in the bundle
+    #[test]
+    fn test_state_checks_gas_proportional() {
+        let small = u64::from(state_checks_gas(1, 1, 1));
+        let large = u64::from(state_checks_gas(10, 10, 10));
+        assert!(small > 0);
+        assert_eq!(small.checked_mul(10), Some(large));
+        assert_eq!(u64::from(state_checks_gas(0, 0, 0)), 0);
+        assert!(state_checks_gas(2, 0, 0) > state_checks_gas(1, 0, 0));
+        assert!(state_checks_gas(0, 2, 0) > state_checks_gas(0, 1, 0));
+        assert!(state_checks_gas(0, 0, 2) > state_checks_gas(0, 0, 1));
+    }
+
+    // Txs may carry at most as many descriptions of each kind as configured
+    // in storage
+    #[test]
+    fn test_description_count_limits() {
+        use namada_state::ConversionState;
+
+        use super::{MaspVpError, OfflineMaspContext};
+
+        let anchor = bls12_381::Scalar::from(1u64);
+        let transaction = shielded_tx(
+            vec![dummy_spend(anchor, 1), dummy_spend(anchor, 2)],
+            vec![dummy_convert(anchor)],
+            vec![dummy_output(1)],
+        );
+
+        // Without any configured limits descriptions are not bounded
+        let mut storage = InMemoryMaspStorage::default();
+        assert!(
+            super::valid_description_counts(&storage, &transaction).is_ok()
+        );
+
+        // Exactly at the limits
+        storage.pre.insert(
+            crate::storage_key::masp_max_spends_per_tx_key(),
+            2u64.serialize_to_vec(),
+        );
+        storage.pre.insert(
+            crate::storage_key::masp_max_converts_per_tx_key(),
+            1u64.serialize_to_vec(),
+        );
+        storage.pre.insert(
+            crate::storage_key::masp_max_outputs_per_tx_key(),
+            1u64.serialize_to_vec(),
+        );
+        assert!(
+            super::valid_description_counts(&storage, &transaction).is_ok()
+        );
+
+        // One spend over the limit
+        storage.pre.insert(
+            crate::storage_key::masp_max_spends_per_tx_key(),
+            1u64.serialize_to_vec(),
+        );
+        let err = super::valid_description_counts(&storage, &transaction)
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<MaspVpError>(),
+            Some(MaspVpError::TooManyDescriptions {
+                kind: "spend",
+                count: 2,
+                limit: 1,
+            })
+        ));
+
+        // One convert over the limit
+        storage.pre.insert(
+            crate::storage_key::masp_max_spends_per_tx_key(),
+            2u64.serialize_to_vec(),
+        );
+        storage.pre.insert(
+            crate::storage_key::masp_max_converts_per_tx_key(),
+            0u64.serialize_to_vec(),
+        );
+        let err = super::valid_description_counts(&storage, &transaction)
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<MaspVpError>(),
+            Some(MaspVpError::TooManyDescriptions {
+                kind: "convert",
+                count: 1,
+                limit: 0,
+            })
+        ));
+
+        // One output over the limit
+        storage.pre.insert(
+            crate::storage_key::masp_max_converts_per_tx_key(),
+            1u64.serialize_to_vec(),
+        );
+        storage.pre.insert(
+            crate::storage_key::masp_max_outputs_per_tx_key(),
+            0u64.serialize_to_vec(),
+        );
+        let err = super::valid_description_counts(&storage, &transaction)
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<MaspVpError>(),
+            Some(MaspVpError::TooManyDescriptions {
+                kind: "output",
+                count: 1,
+                limit: 0,
+            })
+        ));
+
+        // The counts are checked before the anchors, which are not even
+        // published here, and before the bogus proofs get verified
+        let conversion_state = ConversionState::default();
+        let tokens = BTreeMap::new();
+        let context = OfflineMaspContext {
+            block_height: namada_core::chain::BlockHeight(0),
+            masp_epoch: MaspEpoch::zero(),
+            conversion_state: &conversion_state,
+            tokens: &tokens,
+        };
+        let err =
+            super::validate_masp_tx_offline(&storage, &context, &transaction)
+                .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<MaspVpError>(),
+            Some(MaspVpError::TooManyDescriptions { kind: "output", .. })
+        ));
+    }
+
+    // The gas charged for verifying descriptions scales with the multipliers
+    // configured in storage
+    #[test]
+    fn test_verify_gas_multipliers() {
+        use crate::validation::{
+            VerifyGasMultipliers, charge_masp_check_bundle_gas,
+            charge_masp_validate_gas,
+        };
+
+        let anchor = bls12_381::Scalar::from(1u64);
+        let bundle = sapling_bundle(
+            vec![dummy_spend(anchor, 1), dummy_spend(anchor, 2)],
+            vec![],
+            vec![],
+        );
+        let charged = |multipliers: &VerifyGasMultipliers| {
+            let total = Cell::new(0u64);
+            let consume = |gas: namada_gas::Gas| -> crate::Result<()> {
+                total.set(total.get().saturating_add(u64::from(gas)));
+                Ok(())
+            };
+            charge_masp_check_bundle_gas(&bundle, multipliers, consume)
+                .unwrap();
+            charge_masp_validate_gas(&bundle, multipliers, consume).unwrap();
+            total.get()
+        };
+
+        // Without any configured multiplier the default costs are charged
+        let mut storage = InMemoryMaspStorage::default();
+        let multipliers = super::verify_gas_multipliers(&storage).unwrap();
+        assert_eq!(multipliers, VerifyGasMultipliers::default());
+        let default_gas = charged(&multipliers);
+
+        // Doubling the cost of spends doubles the gas charged for them, but
+        // not the one of the binding signature
+        storage.pre.insert(
+            crate::storage_key::masp_spend_gas_multiplier_key(),
+            200u64.serialize_to_vec(),
+        );
+        let multipliers = super::verify_gas_multipliers(&storage).unwrap();
+        assert_eq!(multipliers.spend, 200);
+        assert_eq!(
+            charged(&multipliers),
+            default_gas
+                .saturating_mul(2)
+                .saturating_sub(namada_gas::MASP_VERIFY_SIG_GAS)
+        );
+
+        // Multipliers of absent descriptions do not change the gas
+        storage.pre.insert(
+            crate::storage_key::masp_output_gas_multiplier_key(),
+            300u64.serialize_to_vec(),
+        );
+        let scaled_outputs = super::verify_gas_multipliers(&storage).unwrap();
+        assert_eq!(charged(&scaled_outputs), charged(&multipliers));
+    }
+
+    // Asset types must be derived only once per token, however many of its
+    // balances a transaction changes
+    #[test]
+    fn test_undated_asset_types_derived_once_per_token() {
+        let tokens: Vec<Address> = (0..20)
+            .map(|_| namada_core::address::testing::gen_established_address())
+            .collect();
+        let mut balances = super::ChangedBalances::default();
+
+        // Each token's balance changes for a source and a target
+        let derived = tokens
+            .iter()
+            .chain(tokens.iter())
+            .filter(|token| {
+                super::record_undated_tokens(token, 6.into(), &mut balances)
+                    .unwrap()
+            })
+            .count();
+
+        assert_eq!(derived, tokens.len());
+        // Each token is recorded with all its digit positions
+        assert!(tokens.iter().all(|token| {
+            balances
+                .undated_tokens
+                .values()
+                .filter(|(addr, _, _)| addr == token)
+                .count()
+                == MaspDigitPos::iter().count()
+        }));
+    }
+
+    // An unbalanced transparent pool must report the offending asset type
+    #[test]
+    fn test_unbalanced_pool_names_asset() {
+        let unbalanced =
+            encode_asset_type(nam(), 6.into(), MaspDigitPos::One, None)
+                .unwrap();
+
+        // A pool with a surplus of a single asset
+        let pool = I128Sum::from_pair(unbalanced, 10);
+        let err = super::ensure_balanced_transparent_pool(&pool)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("Transaction fees cannot be left"));
+        assert!(err.contains(&unbalanced.to_string()));
+
+        // A pool with a deficit of a single asset
+        let pool = I128Sum::from_pair(unbalanced, -10);
+        let err = super::ensure_balanced_transparent_pool(&pool)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("must be nonnegative"));
+        assert!(err.contains(&unbalanced.to_string()));
+
+        // An exactly balanced pool is accepted
+        assert!(
+            super::ensure_balanced_transparent_pool(&I128Sum::zero()).is_ok()
+        );
+    }
+
+    // Unshielding notes converted with a reward conversion must balance
+    // against the rewards minted to the MASP
+    #[test]
+    fn test_claim_seeded_reward_conversion() {
+        let mut state = TestState::default();
+        let epoch_0 = MaspEpoch::zero();
+        let epoch_1 = epoch_0.next().unwrap();
+        let asset = |token: Address, denom: u8, epoch| {
+            encode_asset_type(
+                token,
+                denom.into(),
+                MaspDigitPos::Zero,
+                Some(epoch),
+            )
+            .unwrap()
+        };
+        let btc_0 = asset(btc(), 8, epoch_0);
+        let btc_1 = asset(btc(), 8, epoch_1);
+        let nam_1 = asset(nam(), 6, epoch_1);
+
+        // Converting a unit of BTC from epoch 0 to epoch 1 is rewarded with
+        // 10 units of NAM
+        let reward_conversion = I128Sum::from_pair(btc_0, -1)
+            .checked_add(&I128Sum::from_pair(btc_1, 1))
+            .and_then(|conv| conv.checked_add(&I128Sum::from_pair(nam_1, 10)))
+            .unwrap();
+        seed_conversion_state(
+            &mut state,
+            &btc(),
+            8.into(),
+            &[(epoch_0, reward_conversion), (epoch_1, I128Sum::zero())],
+        );
+        let anchor = seed_conversion_state(
+            &mut state,
+            &nam(),
+            6.into(),
+            &[(epoch_1, I128Sum::zero())],
+        );
+        assert_eq!(state.conversion_state().assets.len(), 3);
+
+        // A convert description must be anchored to the seeded tree
+        let mut storage = InMemoryMaspStorage::default();
+        storage.pre.insert(
+            masp_convert_anchor_key(),
+            state
+                .read_bytes(&masp_convert_anchor_key())
+                .unwrap()
+                .expect("the convert anchor must be seeded"),
+        );
+        let transaction =
+            |anchor| shielded_tx(vec![], vec![dummy_convert(anchor)], vec![]);
+        assert!(
+            super::valid_convert_descriptions_anchor(
+                &storage,
+                &transaction(anchor)
+            )
+            .is_ok()
+        );
+        assert!(
+            super::valid_convert_descriptions_anchor(
+                &storage,
+                &transaction(bls12_381::Scalar::from(1u64))
+            )
+            .is_err()
+        );
+
+        // Unshield 100 units of BTC converted to epoch 1 along with the
+        // claimed NAM rewards
+        let sapling_value_balance = I128Sum::from_pair(btc_1, 100)
+            .checked_add(&I128Sum::from_pair(nam_1, 1000))
+            .unwrap();
+        let pre = ValueSum::from_pair(btc(), Amount::from_u64(100))
+            .checked_add(&ValueSum::from_pair(nam(), Amount::from_u64(1000)))
+            .unwrap();
+        let zero = ValueSum::zero();
+        assert!(
+            super::verify_sapling_balancing_value(
+                &pre,
+                &zero,
+                &zero,
+                &zero,
+                &sapling_value_balance,
+                epoch_1,
+                &BTreeMap::new(),
+                state.conversion_state(),
+            )
+            .is_ok()
+        );
+
+        // Rewards cannot be claimed ahead of the epoch they accrue in
+        assert!(
+            super::verify_sapling_balancing_value(
+                &pre,
+                &zero,
+                &zero,
+                &zero,
+                &sapling_value_balance,
+                epoch_0,
+                &BTreeMap::new(),
+                state.conversion_state(),
+            )
+            .is_err()
+        );
+    }
+
+    // A tx touching no MASP storage is accepted without further checks, even
+    // if it incidentally lists the MASP as a verifier
+    #[test]
+    fn test_unrelated_tx_with_masp_verifier_accepted() {
+        let mut state = TestState::default();
+        namada_parameters::init_test_storage(&mut state).unwrap();
+        let unrelated_key = balance_key(
+            &nam(),
+            &namada_core::address::testing::established_address_1(),
+        );
+        let keys_changed = BTreeSet::from([unrelated_key.clone()]);
+        let verifiers = BTreeSet::from([MASP]);
+        let _ = state
+            .write_log_mut()
+            .write(&unrelated_key, Amount::from_u64(1).serialize_to_vec())
+            .unwrap();
+
+        let tx_index = TxIndex::default();
+        let mut tx = Tx::from_type(namada_tx::data::TxType::Raw);
+        tx.push_default_inner_tx();
+        let BatchedTx { tx, cmt } = tx.batch_first_tx();
+        let gas_meter =
+            RefCell::new(VpGasMeter::new_from_tx_meter(&TxGasMeter::new(
+                u64::MAX,
+                namada_parameters::get_gas_scale(&state).unwrap(),
+            )));
+        let (vp_vp_cache, _vp_cache_dir) = vp_cache();
+        let ctx = Ctx::new(
+            &MASP,
+            &state,
+            &tx,
+            &cmt,
+            &tx_index,
+            &gas_meter,
+            &keys_changed,
+            &verifiers,
+            vp_vp_cache,
+        );
+
+        assert!(
+            MaspVp::validate_tx(
+                &ctx,
+                &tx.batch_ref_tx(&cmt),
+                &keys_changed,
+                &verifiers
+            )
+            .is_ok()
+        );
     }
 
     proptest! {
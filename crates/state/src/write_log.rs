@@ -542,6 +542,12 @@ impl WriteLog {
         gas_cost.map(|gas| gas.into())
     }
 
+    /// Get the number of storage keys modified in the block write-log, i.e.
+    /// the keys that will be written to the DB on the next block commit.
+    pub fn block_modifications_count(&self) -> usize {
+        self.block_write_log.len()
+    }
+
     /// Get the non-temporary storage keys changed and accounts keys initialized
     /// in the current transaction. The account keys point to the validity
     /// predicates of the newly created accounts.
@@ -5,6 +5,7 @@
 use std::cell::RefCell;
 use std::collections::{BTreeMap, btree_map};
 use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
 
 use itertools::Either;
 use namada_core::borsh::{BorshDeserialize, BorshSerialize};
@@ -55,6 +56,10 @@ pub struct MockDB(
     // The state is wrapped in `RefCell` to allow modifying it directly from
     // batch write method (which requires immutable self ref).
     RefCell<BTreeMap<String, Vec<u8>>>,
+    // Number of subspace reads that hit the DB, used by tests to assert on
+    // storage access patterns. Atomic, since the DB is shared across threads
+    // for parallelized VP runs.
+    AtomicU64,
 );
 
 // The `MockDB` is not `Sync`, but we're sharing it across threads for reading
@@ -68,6 +73,17 @@ unsafe impl Sync for MockDB {}
 pub struct MockDBWriteBatch;
 
 impl MockDB {
+    /// Get the number of subspace reads that have hit this DB so far. Each
+    /// prefix iteration counts as a single read, however many values it
+    /// yields.
+    pub fn subspace_reads(&self) -> u64 {
+        self.1.load(Ordering::Relaxed)
+    }
+
+    fn count_subspace_read(&self) {
+        self.1.fetch_add(1, Ordering::Relaxed);
+    }
+
     fn read_value<T>(&self, key: impl AsRef<str>) -> Result<Option<T>>
     where
         T: BorshDeserialize,
@@ -381,6 +397,7 @@ impl DB for MockDB {
     }
 
     fn read_subspace_val(&self, key: &Key) -> Result<Option<Vec<u8>>> {
+        self.count_subspace_read();
         let key = Key::parse(SUBSPACE_CF).map_err(Error::KeyError)?.join(key);
         Ok(self.0.borrow().get(&key.to_string()).cloned())
     }
@@ -725,6 +742,7 @@ impl<'iter> DBIter<'iter> for MockDB {
     type PrefixIter = MockPrefixIterator;
 
     fn iter_prefix(&'iter self, prefix: Option<&Key>) -> MockPrefixIterator {
+        self.count_subspace_read();
         let stripped_prefix = "subspace/".to_owned();
         let prefix = format!(
             "{}{}",
@@ -72,6 +72,7 @@ mod test_bridge_pool_vp {
             erc20_whitelist: vec![Erc20WhitelistEntry {
                 token_address: wnam(),
                 token_cap: Amount::from_u64(TOKEN_CAP).native_denominated(),
+                min_confirmations: None,
             }],
             eth_start_height: Default::default(),
             min_confirmations: Default::default(),
@@ -35,7 +35,7 @@ pub mod tx;
 
 /// Validity predicates
 pub mod vp {
-    pub use namada_shielded_token::vp::MaspVp;
+    pub use namada_shielded_token::vp::{MaspVp, MaspVpError};
     // The error and result type are the same as in `namada_trans_token` -
     // a native VP
     pub use namada_shielded_token::{Error, Result};
@@ -56,8 +56,11 @@ pub mod storage_key {
         is_masp_scheduled_reward_precision_key, is_masp_token_map_key,
         is_masp_transfer_key, masp_assets_hash_key,
         masp_base_native_precision_key, masp_commitment_anchor_key,
-        masp_commitment_tree_key, masp_conversion_key, masp_convert_anchor_key,
-        masp_nullifier_key, masp_scheduled_base_native_precision_key,
+        masp_commitment_tree_key, masp_conversion_key,
+        masp_convert_anchor_epoch_key, masp_convert_anchor_key,
+        masp_nullifier_key, masp_recent_commitment_anchors_key,
+        masp_recent_convert_anchors_key,
+        masp_scheduled_base_native_precision_key,
         masp_scheduled_reward_precision_key, masp_token_map_key,
         masp_total_rewards,
     };
@@ -9,8 +9,10 @@ use namada_core::chain::Epoch;
 use namada_core::collections::HashMap;
 use namada_core::eth_abi::{AbiEncode, Encode, Token};
 use namada_core::ethereum_events::EthAddress;
+use namada_core::hash::KeccakHasher;
 use namada_core::keccak::KeccakHash;
 use namada_core::key::common::{self, Signature};
+use namada_core::key::{SigScheme, Signable, VerifySigError};
 use namada_core::voting_power::{EthBridgeVotingPower, FractionalVotingPower};
 use namada_core::{ethereum_structs, token};
 use namada_macros::BorshDeserializer;
@@ -25,6 +27,14 @@ const BRIDGE_CONTRACT_NAMESPACE: &str = "bridge";
 const GOVERNANCE_CONTRACT_VERSION: u8 = 1;
 const GOVERNANCE_CONTRACT_NAMESPACE: &str = "governance";
 
+/// The version of the message format signed over by validators in
+/// a validator set update [`Vext`].
+///
+/// The version is the leading token of the signed message, so
+/// signatures produced under some other version of the format
+/// will not verify against the current one.
+pub const VALSET_UPD_MESSAGE_VERSION: u8 = GOVERNANCE_CONTRACT_VERSION;
+
 /// Type alias for a [`ValidatorSetUpdateVextDigest`].
 pub type VextDigest = ValidatorSetUpdateVextDigest;
 
@@ -83,6 +93,44 @@ impl VextDigest {
     }
 }
 
+/// Verify that every signature in a [`VextDigest`] was produced
+/// over the validator set update of `signing_epoch`, by the
+/// Ethereum hot key of the validator it is attributed to, as
+/// given by `hot_key_addrs`.
+///
+/// No storage access is required, which allows validators to
+/// check a digest before submitting it in a protocol tx.
+pub fn verify_digest_signatures(
+    digest: &VextDigest,
+    voting_powers: &VotingPowersMap,
+    hot_key_addrs: &HashMap<Address, EthAddress>,
+    signing_epoch: Epoch,
+) -> Result<(), VerifySigError> {
+    if &digest.voting_powers != voting_powers {
+        return Err(VerifySigError::SigVerifyError(
+            "The voting powers of the digest do not match the expected voting \
+             powers"
+                .into(),
+        ));
+    }
+    for (validator_addr, signature) in digest.signatures.iter() {
+        let hot_key_addr =
+            hot_key_addrs.get(validator_addr).ok_or_else(|| {
+                VerifySigError::SigVerifyError(format!(
+                    "The Ethereum hot key of validator {validator_addr} is \
+                     unknown"
+                ))
+            })?;
+        Vext {
+            voting_powers: voting_powers.clone(),
+            validator_addr: validator_addr.clone(),
+            signing_epoch,
+        }
+        .verify_signer(signature, hot_key_addr)?;
+    }
+    Ok(())
+}
+
 /// Represents a [`Vext`] signed by some validator, with
 /// an Ethereum key.
 #[derive(
@@ -159,6 +207,63 @@ impl Vext {
     pub fn sign(&self, sk: &common::SecretKey) -> SignedVext {
         SignedVext(Signed::new(sk, self.clone()))
     }
+
+    /// Creates a new signed [`Vext`], over the message format of
+    /// the given `version`.
+    ///
+    /// Only signatures over [`VALSET_UPD_MESSAGE_VERSION`] will
+    /// verify successfully.
+    pub fn sign_with_version(
+        &self,
+        sk: &common::SecretKey,
+        version: u8,
+    ) -> SignedVext {
+        let sig = common::SigScheme::sign_with_hasher::<KeccakHasher>(
+            sk,
+            self.signable_message(version),
+        );
+        SignedVext(Signed::new_from(self.clone(), sig))
+    }
+
+    /// Verify that `signature` was produced over this [`Vext`] by
+    /// the Ethereum hot key with the given address.
+    pub fn verify_signer(
+        &self,
+        signature: &Signature,
+        hot_key_addr: &EthAddress,
+    ) -> Result<(), VerifySigError> {
+        let Signature::Secp256k1(signature) = signature else {
+            return Err(VerifySigError::MismatchedScheme);
+        };
+        let signed_data = SerializeWithAbiEncode::as_signable(self);
+        let signer =
+            signature.recover_eth_address::<KeccakHasher>(&signed_data)?;
+        if &signer != hot_key_addr {
+            return Err(VerifySigError::SigVerifyError(format!(
+                "The signature of validator {} was not produced by its \
+                 Ethereum hot key",
+                self.validator_addr
+            )));
+        }
+        Ok(())
+    }
+
+    /// Return the message signed over by validators, formatted
+    /// according to the given `version`.
+    pub fn signable_message(&self, version: u8) -> KeccakHash {
+        // NOTE: the smart contract expects us to sign
+        // against the next nonce (i.e. the new epoch)
+        let next_epoch = self.signing_epoch.next();
+        let (KeccakHash(bridge_hash), KeccakHash(gov_hash)) =
+            self.voting_powers.get_bridge_and_gov_hashes(next_epoch);
+        AbiEncode::signable_keccak256(&[
+            Token::Uint(version.into()),
+            Token::String("updateValidatorSet".into()),
+            Token::FixedBytes(bridge_hash.to_vec()),
+            Token::FixedBytes(gov_hash.to_vec()),
+            epoch_to_token(next_epoch),
+        ])
+    }
 }
 
 /// Container type for both kinds of Ethereum bridge addresses:
@@ -286,6 +391,40 @@ pub fn valset_upd_toks_to_hashes(
     (bridge_hash, governance_hash)
 }
 
+/// Returns the fractional voting power that each validator in
+/// `voting_powers`, whose hot key address is not in `already_included`,
+/// would add to a quorum of signatures.
+///
+/// The returned validators are sorted in descending order by voting
+/// power, such that a quorum may be greedily built from them.
+pub fn marginal_powers(
+    voting_powers: &VotingPowersMap,
+    already_included: &[EthAddress],
+) -> Vec<(EthAddress, FractionalVotingPower)> {
+    let total_voting_power: token::Amount =
+        token::Amount::sum(voting_powers.values().copied())
+            .expect("Voting power sum must not overflow");
+
+    voting_powers
+        .get_sorted()
+        .into_iter()
+        .filter(|(addr_book, _)| {
+            !already_included.contains(&addr_book.hot_key_addr)
+        })
+        .map(|(addr_book, &voting_power)| {
+            let voting_power = FractionalVotingPower::new(
+                voting_power.into(),
+                total_voting_power.into(),
+            )
+            .expect(
+                "Voting power in map can't be larger than the total voting \
+                 power",
+            );
+            (addr_book.hot_key_addr, voting_power)
+        })
+        .collect()
+}
+
 /// Compare two items of [`VotingPowersMap`]. This comparison operation must
 /// match the equivalent comparison operation in Ethereum bridge code.
 fn compare_voting_powers_map_items(
@@ -414,17 +553,15 @@ impl Encode<1> for ValidatorSetArgs {
 // this is only here so we don't pollute the
 // outer namespace with serde traits
 mod tag {
-    use namada_core::eth_abi::{AbiEncode, Encode, Token};
     use namada_core::hash::KeccakHasher;
     use namada_core::keccak::KeccakHash;
     use namada_core::key::Signable;
     use serde::{Deserialize, Serialize};
 
-    use super::{
-        GOVERNANCE_CONTRACT_VERSION, Vext, VotingPowersMapExt, epoch_to_token,
-    };
+    use super::{VALSET_UPD_MESSAGE_VERSION, Vext};
 
-    /// Tag type that indicates we should use [`AbiEncode`]
+    /// Tag type that indicates we should use
+    /// [`AbiEncode`](namada_core::eth_abi::AbiEncode)
     /// to sign data in a [`namada_tx::Signed`] wrapper.
     #[derive(Eq, PartialEq, Clone, Debug, Serialize, Deserialize)]
     pub struct SerializeWithAbiEncode;
@@ -434,18 +571,7 @@ mod tag {
         type Output = KeccakHash;
 
         fn as_signable(ext: &Vext) -> Self::Output {
-            // NOTE: the smart contract expects us to sign
-            // against the next nonce (i.e. the new epoch)
-            let next_epoch = ext.signing_epoch.next();
-            let (KeccakHash(bridge_hash), KeccakHash(gov_hash)) =
-                ext.voting_powers.get_bridge_and_gov_hashes(next_epoch);
-            AbiEncode::signable_keccak256(&[
-                Token::Uint(GOVERNANCE_CONTRACT_VERSION.into()),
-                Token::String("updateValidatorSet".into()),
-                Token::FixedBytes(bridge_hash.to_vec()),
-                Token::FixedBytes(gov_hash.to_vec()),
-                epoch_to_token(next_epoch),
-            ])
+            ext.signable_message(VALSET_UPD_MESSAGE_VERSION)
         }
     }
 }
@@ -458,6 +584,8 @@ mod tests {
     use std::str::FromStr;
 
     use data_encoding::HEXLOWER;
+    use namada_core::address;
+    use namada_core::key::{RefTo, secp256k1};
 
     use super::*;
 
@@ -577,6 +705,101 @@ mod tests {
         assert_eq!(x, y);
     }
 
+    /// Test that [`verify_digest_signatures`] accepts a digest signed over
+    /// the expected validator set update, and rejects a digest containing
+    /// a signature over some other message, or by the hot key of some
+    /// other validator.
+    #[test]
+    fn test_verify_digest_signatures() {
+        let gen_sk = || {
+            common::SecretKey::Secp256k1(
+                namada_core::key::testing::gen_keypair::<secp256k1::SigScheme>(
+                ),
+            )
+        };
+        let (sk_1, sk_2) = (gen_sk(), gen_sk());
+        let validator_1 = address::testing::established_address_1();
+        let validator_2 = address::testing::established_address_2();
+        let hot_key_addrs = HashMap::from([
+            (
+                validator_1.clone(),
+                EthAddress::try_from(&sk_1.ref_to()).expect("Test failed"),
+            ),
+            (
+                validator_2.clone(),
+                EthAddress::try_from(&sk_2.ref_to()).expect("Test failed"),
+            ),
+        ]);
+        let voting_powers = VotingPowersMap::from([(
+            EthAddrBook {
+                hot_key_addr: hot_key_addrs[&validator_1],
+                cold_key_addr: EthAddress([0; 20]),
+            },
+            100.into(),
+        )]);
+        let signing_epoch = Epoch(1);
+        let ext = |validator_addr: &Address, signing_epoch| Vext {
+            voting_powers: voting_powers.clone(),
+            validator_addr: validator_addr.clone(),
+            signing_epoch,
+        };
+        let verify = |digest: &VextDigest| {
+            verify_digest_signatures(
+                digest,
+                &voting_powers,
+                &hot_key_addrs,
+                signing_epoch,
+            )
+        };
+
+        let digest =
+            VextDigest::singleton(ext(&validator_1, signing_epoch).sign(&sk_1));
+        assert!(verify(&digest).is_ok());
+
+        // sign over the wrong epoch, which corrupts the signature
+        // for the expected message
+        let digest = VextDigest::singleton(
+            ext(&validator_1, signing_epoch.next()).sign(&sk_1),
+        );
+        assert!(verify(&digest).is_err());
+
+        // a validator can't vote on behalf of another one, even
+        // with a signature over the other validator's message
+        let digest =
+            VextDigest::singleton(ext(&validator_2, signing_epoch).sign(&sk_1));
+        assert!(verify(&digest).is_err());
+    }
+
+    /// Test that [`marginal_powers`] only returns the validators that have
+    /// not been included in a quorum yet.
+    #[test]
+    fn test_marginal_powers_exclude_included_validators() {
+        let addr_book = |byte| EthAddrBook {
+            hot_key_addr: EthAddress([byte; 20]),
+            cold_key_addr: EthAddress([byte; 20]),
+        };
+        let voting_powers = VotingPowersMap::from([
+            (addr_book(0), 50.into()),
+            (addr_book(1), 30.into()),
+            (addr_book(2), 20.into()),
+        ]);
+
+        let marginal = marginal_powers(&voting_powers, &[EthAddress([1; 20])]);
+        assert_eq!(
+            marginal,
+            vec![
+                (
+                    EthAddress([0; 20]),
+                    FractionalVotingPower::new_u64(1, 2).unwrap()
+                ),
+                (
+                    EthAddress([2; 20]),
+                    FractionalVotingPower::new_u64(1, 5).unwrap()
+                ),
+            ]
+        );
+    }
+
     #[test]
     fn test_abi_encode_valset_args() {
         let valset_update = ValidatorSetArgs {